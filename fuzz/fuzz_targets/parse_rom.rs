@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nass_core::Emulator;
+
+fuzz_target!(|data: &[u8]| {
+    let mut emulator = Emulator::new();
+    if emulator.load_rom(data).is_ok() {
+        emulator.step();
+    }
+});
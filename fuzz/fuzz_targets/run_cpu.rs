@@ -0,0 +1,27 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nass_core::Emulator;
+
+const PRG_SIZE: usize = 0x4000;
+const STEPS: usize = 4;
+
+// Wraps the input in a 16KB NROM image: the program sits at $8000 and every
+// vector points at it.
+fuzz_target!(|data: &[u8]| {
+    let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let mut prg = vec![0xEA; PRG_SIZE];
+    let len = data.len().min(PRG_SIZE - 6);
+    prg[..len].copy_from_slice(&data[..len]);
+    for vector in (PRG_SIZE - 6..PRG_SIZE).step_by(2) {
+        prg[vector] = 0x00;
+        prg[vector + 1] = 0x80;
+    }
+    rom.extend_from_slice(&prg);
+
+    let mut emulator = Emulator::new();
+    emulator.load_rom(&rom).unwrap();
+    for _ in 0..STEPS {
+        emulator.step();
+    }
+});
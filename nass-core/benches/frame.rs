@@ -0,0 +1,42 @@
+// Frames per second of a scene like a game's: every background tile drawn, 64 sprites put up
+// by OAM DMA and the scroll set in the NMI. Compare builds with
+//   cargo bench -p nass-core --bench frame
+//   cargo bench -p nass-core --bench frame --no-default-features
+// for what the "instrumentation" feature costs when it's compiled in but off.
+#![feature(test)]
+extern crate test;
+
+use nass_core::Emulator;
+use test::Bencher;
+
+const PROGRAM: [u8; 78] = [
+    0xA9, 0x3F, 0x8D, 0x06, 0x20, 0xA9, 0x00, 0x8D, 0x06, 0x20, // Palettes at $3F00
+    0xA2, 0x00, 0x8A, 0x8D, 0x07, 0x20, 0xE8, 0xE0, 0x20, 0xD0, 0xF7, // 32 of them, colors 0-31
+    0xA9, 0x20, 0x8D, 0x06, 0x20, 0xA9, 0x00, 0x8D, 0x06, 0x20, // Nametable at $2000
+    0xA0, 0x04, 0x8A, 0x9D, 0x00, 0x02, 0x29, 0x03, 0x18, 0x69, 0x01, 0x8D, 0x07, 0x20, // Tiles 1-4, OAM page at $0200
+    0xE8, 0xD0, 0xF1, 0x88, 0xD0, 0xEE,
+    0xA9, 0x80, 0x8D, 0x00, 0x20, 0xA9, 0x1E, 0x8D, 0x01, 0x20, // NMI and rendering on
+    0x4C, 0x3D, 0x80, // JMP *
+    0xA9, 0x02, 0x8D, 0x14, 0x40, 0xA9, 0x00, 0x8D, 0x05, 0x20, 0x8D, 0x05, 0x20, 0x40, // NMI: OAM DMA, scroll
+];
+
+fn rom() -> Vec<u8> {
+    let mut rom = b"NES\x1A\x02\x01\x01\x00".to_vec();
+    rom.resize(16, 0);
+    let mut prg = vec![0xEA; 0x8000];
+    prg[..PROGRAM.len()].copy_from_slice(&PROGRAM);
+    prg[0x7FFA..].copy_from_slice(&[0x40, 0x80, 0x00, 0x80, 0x00, 0x80]);
+    rom.extend(prg);
+    rom.extend((0..0x2000u32).map(|i| (i.wrapping_mul(37) ^ i >> 3) as u8));
+    rom
+}
+
+#[bench]
+fn frame(b: &mut Bencher) {
+    let mut emulator = Emulator::new();
+    emulator.load_rom(&rom()).unwrap();
+    for _ in 0..3 {
+        emulator.run_frame();
+    }
+    b.iter(|| emulator.run_frame());
+}
@@ -0,0 +1,28 @@
+use bitflags::bitflags;
+
+bitflags! {
+    // Behaviors that cost speed, each one can be switched on its own on top of a preset.
+    #[derive(Clone, Copy, PartialEq)]
+    pub struct Accuracy: u8 {
+        const PER_DOT_PPU       = 0b0001; // PPU ticked after every instruction (lockstep)
+        const OPEN_BUS_DECAY    = 0b0010; // PPU I/O latch bits fade after ~600ms
+        const DMC_DMA_CONFLICTS = 0b0100; // DMC fetches re-reading $4016/$2007 (needs the APU)
+        const OAM_CORRUPTION    = 0b1000; // OAMADDR >= 8 at the start of rendering
+        const PPU_ADDR_CONFLICTS = 0b1_0000; // $2006 writes during rendering mangling v
+    }
+}
+
+impl Accuracy {
+    pub const FAST: Accuracy = Accuracy::empty();
+    pub const BALANCED: Accuracy = Accuracy::PER_DOT_PPU;
+    pub const ACCURATE: Accuracy = Accuracy::all();
+
+    pub fn preset(preset: u8) -> Result<Accuracy, String> {
+        match preset {
+            0 => Ok(Accuracy::FAST),
+            1 => Ok(Accuracy::BALANCED),
+            2 => Ok(Accuracy::ACCURATE),
+            _ => Err("Unknown accuracy preset.".to_string())
+        }
+    }
+}
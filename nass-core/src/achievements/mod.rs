@@ -0,0 +1,59 @@
+use crate::emulator::Emulator;
+
+// The memory view and frame hook rcheevos (RetroAchievements) expects from an NES core.
+// Addresses are CPU addresses, the same flat 64KB map rcheevos uses for console 7, and
+// reads have no side effects (PPU and APU registers read as 0). Wire `read_memory` into the
+// client's read_memory callback and call rc_client_do_frame from the frame hook.
+// https://github.com/RetroAchievements/rcheevos/blob/develop/src/rcheevos/consoleinfo.c
+pub const CONSOLE_ID: u32 = 7;
+pub const MEMORY_SIZE: u32 = 0x10000;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RegionKind {
+    SystemRam,
+    VirtualRam,
+    HardwareController,
+    ReadOnlyMemory,
+    SaveRam,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MemoryRegion {
+    pub start: u32,
+    pub end: u32,
+    pub real_address: u32,
+    pub kind: RegionKind,
+    pub description: &'static str,
+}
+
+const fn region(start: u32, end: u32, real_address: u32, kind: RegionKind, description: &'static str) -> MemoryRegion {
+    MemoryRegion { start, end, real_address, kind, description }
+}
+
+pub const MEMORY_REGIONS: [MemoryRegion; 11] = [
+    region(0x0000, 0x07FF, 0x0000, RegionKind::SystemRam, "System RAM"),
+    region(0x0800, 0x0FFF, 0x0000, RegionKind::VirtualRam, "Mirror RAM"),
+    region(0x1000, 0x17FF, 0x0000, RegionKind::VirtualRam, "Mirror RAM"),
+    region(0x1800, 0x1FFF, 0x0000, RegionKind::VirtualRam, "Mirror RAM"),
+    region(0x2000, 0x2007, 0x2000, RegionKind::HardwareController, "PPU Register"),
+    region(0x2008, 0x3FFF, 0x2008, RegionKind::VirtualRam, "Mirrored PPU Register"),
+    region(0x4000, 0x4017, 0x4000, RegionKind::HardwareController, "APU and I/O register"),
+    region(0x4018, 0x401F, 0x4018, RegionKind::HardwareController, "APU and I/O test register"),
+    region(0x4020, 0x5FFF, 0x4020, RegionKind::ReadOnlyMemory, "Cartridge data"),
+    region(0x6000, 0x7FFF, 0x6000, RegionKind::SaveRam, "Cartridge save data"),
+    region(0x8000, 0xFFFF, 0x8000, RegionKind::ReadOnlyMemory, "Cartridge ROM"),
+];
+
+// Called at the start of every vblank (the end of a frame) with a memory reader.
+pub type FrameHook = Box<dyn FnMut(&dyn Fn(u16) -> u8)>;
+
+impl Emulator {
+    // Fills `buffer` from `address` on, returns how many bytes were inside the map.
+    pub fn read_memory(&self, address: u32, buffer: &mut [u8]) -> usize {
+        let len = buffer.len().min(MEMORY_SIZE.saturating_sub(address) as usize);
+        for (i, byte) in buffer[..len].iter_mut().enumerate() {
+            *byte = self.debug_read((address as usize + i) as u16);
+        }
+        len
+    }
+}
@@ -0,0 +1,128 @@
+use crate::state::{StateWriter, StateReader};
+
+// Everything is driven by the master clock, each component steps once every `divider`
+// master cycles. NTSC runs the CPU at /12 and the PPU at /4 (3 dots per CPU cycle), PAL
+// at /16 and /5 (3.2 dots per CPU cycle).
+// https://www.nesdev.org/wiki/Cycle_reference_chart
+#[derive(PartialEq, Clone, Copy)]
+pub enum Region {
+    Ntsc,
+    Pal,
+}
+
+// Where the region a ROM runs with came from, highest priority first.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum RegionSource {
+    Settings = 0, // Per-game settings, how users override the rest
+    GameDb = 1,
+    Header = 2, // NES 2.0 timing byte or iNES byte 9 saying PAL
+    Default = 3, // `Emulator::set_region`, also with detection off
+}
+
+// CPU/PPU alignments a console can power on in, one per master cycle of a PPU dot: 4 on NTSC
+// and 5 on PAL.
+pub const MAX_ALIGNMENTS: usize = 5;
+
+// Master cycles into a CPU cycle where its bus access lands. Picked so alignment 0 puts
+// register accesses right on a dot boundary, like the vblank race tests expect.
+const ACCESS_DELAY: usize = 2;
+
+pub struct Clock {
+    pub region: Region,
+    cpu_divider: usize,
+    ppu_divider: usize,
+    ppu_lag: usize, // Master cycles the PPU still owes the CPU
+    ppu_ahead: usize, // Dots of the current CPU cycle already run for a register access
+    master: u64,
+}
+
+impl Clock {
+    pub fn new(region: Region) -> Self {
+        let (cpu_divider, ppu_divider) = match region {
+            Region::Ntsc => (12, 4),
+            Region::Pal => (16, 5),
+        };
+        Clock { region, cpu_divider, ppu_divider, ppu_lag: 0, ppu_ahead: 0, master: 0 }
+    }
+
+    pub fn alignments(&self) -> usize {
+        self.ppu_divider
+    }
+
+    // How many master cycles into a PPU dot each CPU cycle starts. Real consoles power on in
+    // one of `alignments` at random, which decides whether a register access lands before or
+    // after a dot boundary (and on PAL where the extra dot every 5 cycles falls), so a few
+    // games and test ROMs behave differently in each.
+    // https://www.nesdev.org/wiki/PPU_frame_timing#CPU-PPU_Clock_Alignment
+    pub fn set_alignment(&mut self, alignment: usize) {
+        self.ppu_lag = alignment % self.ppu_divider;
+    }
+
+    // Advances the master clock by one CPU cycle, returns how many PPU dots fit in it.
+    pub fn cpu_cycle(&mut self) -> usize {
+        self.master = self.master.wrapping_add(self.cpu_divider as u64);
+        self.ppu_lag += self.cpu_divider;
+        let dots = self.ppu_lag / self.ppu_divider;
+        self.ppu_lag %= self.ppu_divider;
+        dots - std::mem::take(&mut self.ppu_ahead)
+    }
+
+    // Dots the PPU has to run before a register access in the coming CPU cycle sees it, they
+    // aren't returned again by `cpu_cycle`.
+    pub fn access_dots(&mut self) -> usize {
+        let dots = ((self.ppu_lag + ACCESS_DELAY) / self.ppu_divider).saturating_sub(self.ppu_ahead);
+        self.ppu_ahead += dots;
+        dots
+    }
+
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.write_usize(self.ppu_lag);
+        w.write_bytes(&self.master.to_le_bytes());
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.ppu_lag = r.read_usize()? % self.ppu_divider;
+        self.ppu_ahead = 0;
+        let mut master = [0; 8];
+        r.read_bytes(&mut master)?;
+        self.master = u64::from_le_bytes(master);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Dots in each of 5 CPU cycles, and before an access in each.
+    fn dots(region: Region, alignment: usize) -> Vec<(usize, usize)> {
+        let mut clock = Clock::new(region);
+        clock.set_alignment(alignment);
+        (0..5).map(|_| {
+            let early = clock.access_dots();
+            (early, early + clock.cpu_cycle())
+        }).collect()
+    }
+
+    #[test]
+    fn ntsc_alignments() {
+        assert_eq!(Clock::new(Region::Ntsc).alignments(), 4);
+        assert_eq!(dots(Region::Ntsc, 0), [(0, 3); 5]);
+        assert_eq!(dots(Region::Ntsc, 1), [(0, 3); 5]);
+        assert_eq!(dots(Region::Ntsc, 2), [(1, 3); 5]);
+        assert_eq!(dots(Region::Ntsc, 3), [(1, 3); 5]);
+    }
+
+    // Each PAL alignment puts the 4th dot of the 5 cycles somewhere else.
+    #[test]
+    fn pal_alignments() {
+        let mut seen = Vec::new();
+        for alignment in 0..Clock::new(Region::Pal).alignments() {
+            let dots = dots(Region::Pal, alignment);
+            assert_eq!(dots.iter().map(|d| d.1).sum::<usize>(), 16);
+            assert!(!seen.contains(&dots));
+            seen.push(dots);
+        }
+        assert_eq!(seen.len(), 5);
+    }
+}
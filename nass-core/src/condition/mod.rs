@@ -0,0 +1,104 @@
+mod rules;
+
+pub use self::rules::Rules;
+
+// A comparison between two bytes, written `<operand> <op> <operand>` with operands
+//   $0075   CPU memory at an address (hex)
+//   d$0075  the same address at the end of the previous frame, for deltas
+//   05      a constant (hex, 0x optional)
+// e.g. `$0075 == 00` or `$00A0 > d$00A0`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Compare {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Compare {
+    pub fn parse(op: &str) -> Result<Compare, String> {
+        match op {
+            "==" => Ok(Compare::Eq),
+            "!=" => Ok(Compare::Ne),
+            "<" => Ok(Compare::Lt),
+            "<=" => Ok(Compare::Le),
+            ">" => Ok(Compare::Gt),
+            ">=" => Ok(Compare::Ge),
+            _ => Err(format!("Unknown comparison {op}")),
+        }
+    }
+
+    pub fn test(self, left: u8, right: u8) -> bool {
+        match self {
+            Compare::Eq => left == right,
+            Compare::Ne => left != right,
+            Compare::Lt => left < right,
+            Compare::Le => left <= right,
+            Compare::Gt => left > right,
+            Compare::Ge => left >= right,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Operand {
+    Value(u8),
+    Memory(u16),
+    Prior(u16),
+}
+
+impl Operand {
+    pub fn parse(text: &str) -> Result<Operand, String> {
+        let address = |text: &str| parse_hex(text).and_then(|addr| u16::try_from(addr).ok()).ok_or(format!("Bad address {text}"));
+        if let Some(addr) = text.strip_prefix("d$") {
+            Ok(Operand::Prior(address(addr)?))
+        } else if let Some(addr) = text.strip_prefix('$') {
+            Ok(Operand::Memory(address(addr)?))
+        } else {
+            parse_hex(text).and_then(|value| u8::try_from(value).ok()).map(Operand::Value).ok_or(format!("Bad value {text}"))
+        }
+    }
+
+    fn get(self, peek: &impl Fn(u16) -> u8, prior: &impl Fn(u16) -> u8) -> u8 {
+        match self {
+            Operand::Value(value) => value,
+            Operand::Memory(addr) => peek(addr),
+            Operand::Prior(addr) => prior(addr),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Condition {
+    pub left: Operand,
+    pub compare: Compare,
+    pub right: Operand,
+}
+
+impl Condition {
+    pub fn parse(text: &str) -> Result<Condition, String> {
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        let [left, op, right] = fields[..] else { return Err(format!("Expected <operand> <op> <operand>, got {text}")) };
+        Ok(Condition { left: Operand::parse(left)?, compare: Compare::parse(op)?, right: Operand::parse(right)? })
+    }
+
+    // `prior` gives what an address held at the end of the previous frame.
+    pub fn check(&self, peek: impl Fn(u16) -> u8, prior: impl Fn(u16) -> u8) -> bool {
+        self.compare.test(self.left.get(&peek, &prior), self.right.get(&peek, &prior))
+    }
+
+    // Addresses read as `d$`, their values have to be kept from frame to frame.
+    pub fn prior_addresses(&self) -> impl Iterator<Item = u16> {
+        [self.left, self.right].into_iter().filter_map(|operand| match operand {
+            Operand::Prior(addr) => Some(addr),
+            _ => None,
+        })
+    }
+}
+
+pub fn parse_hex(text: &str) -> Option<u32> {
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    u32::from_str_radix(text, 16).ok()
+}
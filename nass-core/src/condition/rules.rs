@@ -0,0 +1,93 @@
+use std::collections::{HashMap, VecDeque};
+use super::Condition;
+
+struct Requirement {
+    condition: Condition,
+    target: u32, // Frames the condition has to have been true, 0 for "true this frame"
+    hits: u32,
+}
+
+struct Rule {
+    id: u32,
+    requirements: Vec<Requirement>,
+    triggered: bool,
+}
+
+// Rules over CPU memory checked once per frame, the building block for achievements.
+// A rule is conditions joined by &&, each optionally followed by a hit count:
+//   $0075 == 05 && $00A0 > d$00A0 (3)
+// Without a count a condition has to be true on the frame itself, with one it has to have
+// been true on that many frames since the rule was added or reset. A rule triggers once,
+// the first frame all of its conditions are met.
+pub struct Rules {
+    rules: Vec<Rule>,
+    prior: HashMap<u16, u8>,
+    triggered: VecDeque<u32>,
+}
+
+impl Rules {
+    pub fn new() -> Self {
+        Rules { rules: Vec::new(), prior: HashMap::new(), triggered: VecDeque::new() }
+    }
+
+    // Adds a rule, replacing any with the same id.
+    pub fn add(&mut self, id: u32, text: &str) -> Result<(), String> {
+        let mut requirements = Vec::new();
+        for part in text.split("&&") {
+            let part = part.trim();
+            let (condition, target) = match part.strip_suffix(')').and_then(|part| part.rsplit_once('(')) {
+                Some((condition, count)) => (condition, count.trim().parse().map_err(|_| format!("Bad hit count in {part}"))?),
+                None => (part, 0),
+            };
+            requirements.push(Requirement { condition: Condition::parse(condition)?, target, hits: 0 });
+        }
+        self.remove(id);
+        self.rules.push(Rule { id, requirements, triggered: false });
+        Ok(())
+    }
+
+    pub fn remove(&mut self, id: u32) {
+        self.rules.retain(|rule| rule.id != id);
+    }
+
+    // Clears the hit counts and lets the rule trigger again.
+    pub fn reset(&mut self, id: u32) {
+        for rule in self.rules.iter_mut().filter(|rule| rule.id == id) {
+            rule.triggered = false;
+            rule.requirements.iter_mut().for_each(|requirement| requirement.hits = 0);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    // Checks every rule against memory at the end of a frame, returns how many triggered.
+    pub fn evaluate(&mut self, peek: impl Fn(u16) -> u8) -> usize {
+        let prior = |addr: u16| self.prior.get(&addr).copied().unwrap_or_else(|| peek(addr));
+        let mut triggered = Vec::new();
+        for rule in self.rules.iter_mut().filter(|rule| !rule.triggered) {
+            let mut met = true;
+            for requirement in rule.requirements.iter_mut() {
+                let true_now = requirement.condition.check(&peek, prior);
+                if true_now { requirement.hits = requirement.hits.saturating_add(1); }
+                met &= if requirement.target == 0 { true_now } else { requirement.hits >= requirement.target };
+            }
+            if met {
+                rule.triggered = true;
+                triggered.push(rule.id);
+            }
+        }
+        self.prior = self.rules.iter()
+            .flat_map(|rule| rule.requirements.iter().flat_map(|requirement| requirement.condition.prior_addresses()))
+            .map(|addr| (addr, peek(addr)))
+            .collect();
+        self.triggered.extend(&triggered);
+        triggered.len()
+    }
+
+    // Ids of triggered rules, oldest first.
+    pub fn pop_triggered(&mut self) -> Option<u32> {
+        self.triggered.pop_front()
+    }
+}
@@ -0,0 +1,367 @@
+use crate::ppu::{PPU, FrameCapture, PpuAccess, VBLANK_SCANLINE, DOTS_PER_SCANLINE};
+use crate::input::*;
+use crate::mapper::*;
+use crate::state::{StateWriter, StateReader, Chunk};
+use crate::event::*;
+use crate::rng::Rng;
+use crate::clock::{Clock, Region};
+use super::{Timeline, TimelineKind};
+use Interrupt::*;
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum Interrupt {
+    Nmi,
+    Irq,
+}
+
+const RAM_SIZE: usize = 0x800;
+// Outside lockstep the PPU is allowed to fall behind by about a scanline.
+const CATCH_UP_DOTS: usize = 341;
+
+// Stands in for the whole memory map when checking the CPU alone, see `FlatCpu`: every
+// address is RAM and each access is logged as (address, value, write).
+#[cfg(feature = "single-step")]
+pub struct FlatMemory {
+    pub bytes: Vec<u8>,
+    pub accesses: Vec<(u16, u8, bool)>,
+}
+
+pub struct BUS {
+    ram: [u8; RAM_SIZE],
+    pub mapper: Mapper_,
+    pub ppu: PPU,
+    pub interrupt: Option<Interrupt>,
+    pub dma_page: Option<u8>, // OAM DMA from a $4014 write, run by the CPU after the write
+    pub ports: [Box<dyn InputDevice>; 3], // Controller port 1, 2 and the expansion port
+    pub microphone: Microphone,
+    pub rom: *const u8,
+    pub events: EventQueue,
+    pub clock: Clock,
+    pub lockstep: bool,
+    pub capture: FrameCapture,
+    // Extra scanlines the CPU runs each frame with the PPU stopped after the vblank line,
+    // games that lag get more time while the picture keeps its timing.
+    pub overclock_lines: u16,
+    overclock_dots: usize, // Left in this frame's pause
+    // A frame that never reads $4016/$4017 is a lag frame, counted until reset.
+    input_polled: bool,
+    pub lag_frame: bool,
+    pub lag_count: u32,
+    pub timeline: Timeline,
+    pub io_accessed: bool, // PPU, APU or input registers touched, cleared by the watchdog
+    pub rng: Rng,
+    ppu_pending: usize,
+    #[cfg(feature = "single-step")]
+    pub flat: Option<Box<FlatMemory>>,
+    #[cfg(feature = "rcheevos")]
+    pub frame_hook: Option<crate::achievements::FrameHook>,
+}
+
+impl BUS {
+    pub fn new(rom: *const u8, mapper: Mapper_, ppu: PPU) -> Self {
+        let bus = BUS {
+            ram: [0; RAM_SIZE],
+            mapper,
+            ppu,
+            dma_page: None,
+            interrupt: None,
+            rom,
+            ports: [new_device(DeviceKind::Joypad), new_device(DeviceKind::Joypad), new_device(DeviceKind::Empty)],
+            microphone: Microphone::new(),
+            events: EventQueue::new(),
+            clock: Clock::new(Region::Ntsc),
+            lockstep: true,
+            capture: FrameCapture::new(),
+            overclock_lines: 0,
+            overclock_dots: 0,
+            input_polled: false,
+            lag_frame: false,
+            lag_count: 0,
+            timeline: Timeline::new(),
+            io_accessed: false,
+            rng: Rng::new(0),
+            ppu_pending: 0,
+            #[cfg(feature = "single-step")]
+            flat: None,
+            #[cfg(feature = "rcheevos")]
+            frame_hook: None,
+        };
+        bus
+    }
+
+    pub fn randomize_ram(&mut self) {
+        self.rng.fill(&mut self.ram);
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        #[cfg(feature = "single-step")]
+        if let Some(flat) = self.flat.as_mut() {
+            flat.bytes[addr as usize] = value;
+            flat.accesses.push((addr, value, true));
+            return
+        }
+        if (0x2000..0x4020).contains(&addr) { self.io_accessed = true; }
+        // Mapper writes can switch CHR banks or mirroring under the PPU as well.
+        if (0x2000..=0x3FFF).contains(&addr) || addr == 0x4014 || addr >= 0x4020 { self.catch_up_access(); }
+        match addr {
+            0x2000..=0x3FFF => log_trace!(target: "nass::ppu::reg", addr = addr & 0x2007, value, "write"),
+            0x4016 => log_trace!(target: "nass::input", value, "strobe"),
+            0x4020..=0xFFFF => log_trace!(target: "nass::mapper::reg", addr, value, "write"),
+            _ => (),
+        }
+        match addr {
+            0x0000..=0x1FFF => self.ram[(addr as usize) & 0x07FF] = value,
+            0x2000..=0x3FFF => {
+                if self.capture.is_enabled() { self.capture.record(self.ppu.position(), PpuAccess::Write(addr & 0x2007, value)); }
+                if self.ppu.write_register(addr, value, &mut self.mapper) { self.interrupt = Some(Nmi) }
+            },
+            // Both ports share the strobe line.
+            0x4016 => self.ports.iter_mut().for_each(|port| port.strobe(value)),
+            // $4017 writes go to the APU frame counter.
+            0x4017 => (),
+            0x4014 => {
+                log_debug!(target: "nass::bus", page = value, "OAM DMA");
+                if self.timeline.is_enabled() { self.timeline.record(TimelineKind::OamDma, self.ppu.position()); }
+                self.dma_page = Some(value);
+            },
+            0x4020..=0xFFFF => {
+                if self.capture.is_enabled() { self.capture.record(self.ppu.position(), PpuAccess::MapperWrite(addr, value)); }
+                self.mapper.write_prg(addr, value)
+            },
+            _ => ()
+        }
+    }
+
+    pub fn read(&mut self, addr: u16) -> u8 { 
+        #[cfg(feature = "single-step")]
+        if let Some(flat) = self.flat.as_mut() {
+            let value = flat.bytes[addr as usize];
+            flat.accesses.push((addr, value, false));
+            return value
+        }
+        if (0x2000..=0x3FFF).contains(&addr) { self.catch_up_access(); }
+        if (0x2000..0x4020).contains(&addr) { self.io_accessed = true; }
+        match addr {
+            0x0000..=0x1FFF => self.ram[addr as usize & 0x07FF],
+            0x2000..=0x3FFF => {
+                // Status and data reads change PPU state, a re-render has to repeat them.
+                if self.capture.is_enabled() && matches!(addr & 0x2007, 0x2002 | 0x2007) {
+                    self.capture.record(self.ppu.position(), PpuAccess::Read(addr & 0x2007));
+                }
+                let value = self.ppu.read_register(addr, self.rom, &self.mapper);
+                if std::mem::take(&mut self.ppu.nmi_cancelled) && self.interrupt == Some(Nmi) {
+                    self.interrupt = None;
+                }
+                value
+            },
+            0x4014 => 0,
+            0x4016 => { self.input_polled = true; self.ports[0].read(0) | self.ports[2].read(0) | self.microphone.read() },
+            0x4017 => { self.input_polled = true; self.ports[1].read(1) | self.ports[2].read(1) },
+            0x4020..=0xFFFF => self.mapper.read_prg(self.rom, addr),
+            _ => 0
+        }
+    }
+
+    // `read` for debuggers, cheat tools and agents watching the game: PPU registers show what
+    // a read would return without its side effects, the input ports read 0 instead of shifting.
+    pub fn debug_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x1FFF => self.ram[addr as usize & 0x07FF],
+            0x2000..=0x3FFF => self.ppu.peek_register(addr),
+            0x4020..=0xFFFF => self.mapper.read_prg(self.rom, addr),
+            _ => 0
+        }
+    }
+
+    // Writes RAM and PRG RAM, registers and bank switching are left alone.
+    pub fn debug_write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram[addr as usize & 0x07FF] = value,
+            0x6000..=0x7FFF => self.mapper.write_prg(addr, value),
+            _ => (),
+        }
+    }
+
+    // Runs everything else for the CPU cycles just spent, at the region's clock ratios.
+    // Without lockstep the PPU dots are only queued and run in batches, or as soon as
+    // the CPU touches a PPU register.
+    pub fn tick(&mut self, cycles: usize) {
+        self.mapper.cpu_tick(cycles);
+        self.ports[2].cpu_tick(cycles);
+        if self.timeline.is_enabled() { self.timeline.tick(cycles); }
+        for _ in 0..cycles {
+            self.ppu_pending += self.clock.cpu_cycle();
+        }
+        if self.lockstep || self.ppu_pending >= CATCH_UP_DOTS {
+            self.catch_up();
+        }
+        if self.interrupt.is_none() && self.mapper.irq() {
+            self.interrupt = Some(Irq);
+        }
+    }
+
+    pub fn catch_up(&mut self) {
+        self.ppu.forget_tile();
+        for _ in 0..self.ppu_pending {
+            self.ppu_tick();
+        }
+        self.ppu_pending = 0;
+    }
+
+    // Also runs the dots of the current CPU cycle before its access, depending on the alignment.
+    fn catch_up_access(&mut self) {
+        self.catch_up();
+        for _ in 0..self.clock.access_dots() {
+            self.ppu_tick();
+        }
+    }
+
+    fn ppu_tick(&mut self) {
+        if self.overclock_dots > 0 {
+            self.overclock_dots -= 1;
+            return
+        }
+        if self.capture.is_enabled() && self.ppu.position() == (self.ppu.pre_render_scanline(), 0) {
+            self.capture.start_frame(&self.ppu, &self.mapper);
+        }
+        self.ppu.tick(self.rom, &mut self.mapper);
+        if self.ppu.nmi_occured {
+            self.interrupt = Some(Nmi); 
+            self.ppu.nmi_occured = false;
+        }
+        if self.ppu.frame_completed {
+            self.events.push(Event::FrameCompleted);
+            self.ppu.frame_completed = false;
+            if self.timeline.is_enabled() { self.timeline.end_frame(); }
+            #[cfg(feature = "rcheevos")]
+            if let Some(mut hook) = self.frame_hook.take() {
+                hook(&|addr| self.debug_read(addr));
+                self.frame_hook = Some(hook);
+            }
+            self.lag_frame = !std::mem::take(&mut self.input_polled);
+            if self.lag_frame {
+                self.lag_count = self.lag_count.wrapping_add(1);
+                self.events.push(Event::LagFrame);
+            }
+        }
+        if self.ppu.position() == (VBLANK_SCANLINE + 1, 0) {
+            self.overclock_dots = self.overclock_lines as usize * DOTS_PER_SCANLINE as usize;
+        }
+    }
+
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.ram);
+        w.write_u8(match self.interrupt { None => 0, Some(Nmi) => 1, Some(Irq) => 2 });
+        w.write_bool(false); // A pending OAM DMA, there never is one between instructions
+        self.clock.save_state(w);
+        w.write_usize(self.overclock_dots);
+        w.write_bool(self.input_polled);
+        w.write_bool(self.lag_frame);
+        w.write_u32(self.lag_count);
+        self.rng.save_state(w);
+        w.begin_chunk(Chunk::Ports);
+        for port in self.ports.iter() {
+            w.write_u8(port.kind() as u8);
+            port.save_state(w);
+        }
+        self.microphone.save_state(w);
+        w.begin_chunk(Chunk::Ppu);
+        self.ppu.save_state(w);
+        w.begin_chunk(Chunk::Mapper);
+        self.mapper.save_state(w);
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        r.read_bytes(&mut self.ram)?;
+        self.interrupt = match r.read_u8()? { 1 => Some(Nmi), 2 => Some(Irq), _ => None };
+        r.read_bool()?;
+        self.clock.load_state(r)?;
+        self.overclock_dots = if r.version() >= 7 { r.read_usize()? } else { 0 };
+        if r.version() >= 8 {
+            self.input_polled = r.read_bool()?;
+            self.lag_frame = r.read_bool()?;
+            self.lag_count = r.read_u32()?;
+        } else {
+            (self.input_polled, self.lag_frame, self.lag_count) = (false, false, 0);
+        }
+        // Older states keep drawing from where this machine's generator is.
+        if r.version() >= 12 { self.rng.load_state(r)?; }
+        self.ppu_pending = 0;
+        // Before version 5 there was no expansion port.
+        if r.version() < 5 { self.ports[2] = new_device(DeviceKind::Empty); }
+        let ports = if r.version() < 5 { 2 } else { self.ports.len() };
+        for port in self.ports.iter_mut().take(ports) {
+            let kind = DeviceKind::from_u8(r.read_u8()?)?;
+            if port.kind() != kind { *port = new_device(kind); }
+            port.load_state(r)?;
+        }
+        self.microphone.load_state(r)?;
+        self.ppu.load_state(r)?;
+        self.mapper.load_state(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::tests::nrom;
+
+    // Reads $2002 with NMIs on when the PPU is about to run (241, dot), returns the value read,
+    // whether vblank shows on a later read and whether the NMI is still pending.
+    // https://www.nesdev.org/wiki/PPU_frame_timing#VBL_Flag_Timing
+    fn race(dot: u16) -> (u8, bool, bool) {
+        race_aligned(0, dot)
+    }
+
+    fn race_aligned(alignment: usize, dot: u16) -> (u8, bool, bool) {
+        let (_rom, mut cpu) = nrom(&[]);
+        let bus = &mut cpu.bus;
+        bus.clock.set_alignment(alignment);
+        bus.write(0x2000, 0x80);
+        bus.tick(1);
+        while bus.ppu.position() != (VBLANK_SCANLINE, dot) {
+            bus.ppu_tick();
+        }
+        let status = bus.read(0x2002) & 0x80;
+        while bus.ppu.position() != (VBLANK_SCANLINE, 20) {
+            bus.ppu_tick();
+        }
+        (status, bus.ppu.peek_register(0x2002) & 0x80 != 0, bus.interrupt == Some(Nmi))
+    }
+
+    #[test]
+    fn vblank_read_race() {
+        assert_eq!(race(0), (0x00, true, true));
+        // On the dot the flag gets set: it never does and there's no NMI.
+        assert_eq!(race(1), (0x00, false, false));
+        // One or two dots later the flag reads set and clears, and the NMI is cancelled.
+        assert_eq!(race(2), (0x80, false, false));
+        assert_eq!(race(3), (0x80, false, false));
+        assert_eq!(race(4), (0x80, false, true));
+    }
+
+    // In alignments 2 and 3 the read lands after the next dot has started.
+    #[test]
+    fn vblank_read_race_by_alignment() {
+        for dot in 0..4 {
+            assert_eq!(race_aligned(1, dot), race(dot), "dot {dot}");
+            assert_eq!(race_aligned(2, dot), race(dot + 1), "dot {dot}");
+            assert_eq!(race_aligned(3, dot), race(dot + 1), "dot {dot}");
+        }
+    }
+
+    // PPU registers read as they would without clearing vblank, RAM and ROM as they are.
+    #[test]
+    fn debug_read_sees_every_area() {
+        let (_rom, mut cpu) = nrom(&[0xA9]);
+        let bus = &mut cpu.bus;
+        bus.write(0x0010, 0x42);
+        while bus.ppu.position() != (VBLANK_SCANLINE, 2) {
+            bus.ppu_tick();
+        }
+        assert_eq!((bus.debug_read(0x0810), bus.debug_read(0x8000)), (0x42, 0xA9));
+        assert_eq!(bus.debug_read(0x2002) & 0x80, 0x80);
+        assert_eq!(bus.debug_read(0x3FFA) & 0x80, 0x80);
+        assert_eq!(bus.read(0x2002) & 0x80, 0x80);
+    }
+}
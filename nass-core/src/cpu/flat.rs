@@ -0,0 +1,55 @@
+use super::*;
+
+// The CPU alone on a flat 64KB bus, for checking instructions against per-cycle vectors like
+// SingleStepTests' (tests/single_step.rs). Nothing but memory is connected.
+pub struct FlatCpu {
+    cpu: CPU,
+    _rom: Vec<u8>, // Empty NROM image the CPU's mapper points into
+}
+
+impl Default for FlatCpu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlatCpu {
+    pub fn new() -> Self {
+        let mut rom = b"NES\x1A\x02\x01\x00\x00".to_vec();
+        rom.resize(16 + 0x8000 + 0x2000, 0);
+        let mapper = crate::mapper::new(&rom, false).unwrap();
+        let mut cpu = CPU::new(rom.as_ptr(), mapper);
+        cpu.bus.flat = Some(Box::new(FlatMemory { bytes: vec![0; 0x10000], accesses: Vec::new() }));
+        FlatCpu { cpu, _rom: rom }
+    }
+
+    pub fn registers(&self) -> Registers {
+        self.cpu.registers()
+    }
+
+    // Everything but the cycle counter.
+    pub fn set_registers(&mut self, registers: Registers) {
+        self.cpu.a = registers.a;
+        self.cpu.x = registers.x;
+        self.cpu.y = registers.y;
+        self.cpu.status.update(registers.p);
+        self.cpu.s = registers.s;
+        self.cpu.pc = registers.pc;
+    }
+
+    pub fn memory(&mut self) -> &mut [u8] {
+        &mut self.flat().bytes
+    }
+
+    // Runs one instruction, returns its accesses as (address, value, write) and cycle count.
+    pub fn step(&mut self) -> (&[(u16, u8, bool)], usize) {
+        self.flat().accesses.clear();
+        self.cpu.tick();
+        let cycles = self.cpu.cycles_left;
+        (&self.flat().accesses, cycles)
+    }
+
+    fn flat(&mut self) -> &mut FlatMemory {
+        self.cpu.bus.flat.as_mut().unwrap()
+    }
+}
@@ -33,14 +33,14 @@ impl CPU {
     fn relative(&mut self, cond: bool) { 
         if cond {
             let offset = self.bus.read(self.pc) as u16;
-            self.pc += 1;
+            self.pc = self.pc.wrapping_add(1);
             let offset = if offset > 127 { 0xFFFF - (256 - offset) + 1 } else { offset };
-            let new_pc = self.pc + offset;
+            let new_pc = self.pc.wrapping_add(offset);
             self.cycles_left += 1;
             self.set_page_crossed(self.pc, new_pc);
             self.pc = new_pc;
         } else { 
-            self.pc += 1;
+            self.pc = self.pc.wrapping_add(1);
         }
     }
 
@@ -53,22 +53,31 @@ impl CPU {
     fn bne(&mut self, _: u16) { self.relative(!self.status.zero()); }
     fn beq(&mut self, _: u16) { self.relative(self.status.zero()); }
 
-    fn jsr(&mut self, value: u16) { 
-        let return_addr = self.pc - 1;
-        self.push_stack(((return_addr  & 0xFF00) >> 8) as u8);
-        self.push_stack((return_addr & 0x00FF) as u8);
-        self.pc = value;
+    // The high byte of the target is only read after pushing the return address (its own
+    // address), so a JSR on the stack page can jump through the byte it just pushed.
+    // https://www.nesdev.org/6502_cpu.txt
+    fn jsr(&mut self, _: u16) {
+        let low = self.bus.read(self.pc);
+        self.pc = self.pc.wrapping_add(1);
+        self.stack_dummy_read();
+        self.push_stack(((self.pc & 0xFF00) >> 8) as u8);
+        self.push_stack((self.pc & 0x00FF) as u8);
+        self.pc = u16::from_le_bytes([low, self.bus.read(self.pc)]);
     }
 
     fn brk(&mut self, _: u16) {
-        let return_addr = self.pc + 1;
-        self.push_stack((return_addr & 0xFF00 >> 8) as u8);
+        // The byte after BRK is read and skipped.
+        self.bus.read(self.pc);
+        let return_addr = self.pc.wrapping_add(1);
+        self.push_stack(((return_addr & 0xFF00) >> 8) as u8);
         self.push_stack((return_addr & 0x00FF) as u8);
         self.push_stack(self.status.bits() | 0x10);
         self.status.set_interrupt(true); 
         self.pc = self.read_address(IRQ_VECTOR);
     }
 
+    // The 2A03 has no BCD: ADC/SBC stay binary with D set, SED/CLD and PHP/PLP still keep the flag.
+    // https://www.nesdev.org/wiki/CPU_status_flag_behavior
     fn add(&mut self, value: u8) {
         let carry = self.status.bits() & 0x1 == 1;
         let (sum, carry) = self.a.carrying_add(value, carry);
@@ -84,13 +93,20 @@ impl CPU {
     }
 
     fn rti(&mut self, _: u16) {
+        self.bus.read(self.pc);
+        self.stack_dummy_read();
         let value = self.pull_stack();
         self.status.update(value);
         self.pc = (self.pull_stack() as u16) | ((self.pull_stack() as u16) * 0x100);
     }
 
     fn rts(&mut self, _: u16) {
-        self.pc = ((self.pull_stack() as u16) | ((self.pull_stack() as u16 ) * 0x100))+1
+        self.bus.read(self.pc);
+        self.stack_dummy_read();
+        self.pc = (self.pull_stack() as u16) | ((self.pull_stack() as u16 ) * 0x100);
+        // Reads the last byte of the JSR while incrementing the pulled address.
+        self.bus.read(self.pc);
+        self.pc = self.pc.wrapping_add(1);
     }
 
     fn ldy(&mut self, value: u16) {
@@ -107,7 +123,7 @@ impl CPU {
 
     fn cpx(&mut self, value: u16) {
         let value = self.bus.read(value); 
-        let diff = self.x - value;
+        let diff = self.x.wrapping_sub(value);
         self.status.set_carry(self.x >= value);
         self.status.set_zn(diff);
     }
@@ -119,7 +135,7 @@ impl CPU {
 
     fn cmp(&mut self, value: u16) {
         let value = self.bus.read(value);
-        let diff = self.a - value;
+        let diff = self.a.wrapping_sub(value);
         self.status.set_carry(self.a >= value);
         self.status.set_zn(diff);
     }
@@ -143,12 +159,20 @@ impl CPU {
         self.status.set_zn(self.a);
     }
 
-    fn jam(&mut self, _: u16) { self.pc -= 1 }
+    fn jam(&mut self, _: u16) { self.pc = self.pc.wrapping_sub(1) }
 
     fn nop(&mut self, _: u16) { }
 
+    // Read-modify-write instructions write the value they read back unchanged before the
+    // result, mappers and PPU registers see both writes. https://www.nesdev.org/wiki/CPU_addressing_modes
+    fn read_modify(&mut self, addr: u16) -> u8 {
+        let value = self.bus.read(addr);
+        self.bus.write(addr, value);
+        value
+    }
+
     fn slo(&mut self, value: u16) {
-        let mut operand = self.bus.read(value);
+        let mut operand = self.read_modify(value);
         self.status.set_carry((operand & 0x80) > 0);
         operand <<= 1;
         self.bus.write(value, operand);
@@ -157,7 +181,7 @@ impl CPU {
     }
 
     fn rla(&mut self, value: u16) {
-        let mut operand = self.bus.read(value);
+        let mut operand = self.read_modify(value);
         let carry = self.status.bits() & 0x1;
         self.status.set_carry((operand & 0x80) > 0);
         operand = (operand << 1) | carry;
@@ -167,7 +191,7 @@ impl CPU {
     }
 
     fn sre(&mut self, value: u16) {
-        let mut operand = self.bus.read(value);
+        let mut operand = self.read_modify(value);
         self.status.set_carry((operand & 0x1) == 1);
         operand >>= 1;
         self.bus.write(value, operand);
@@ -176,7 +200,7 @@ impl CPU {
     }
 
     fn rra(&mut self, value: u16) {
-        let mut operand = self.bus.read(value);
+        let mut operand = self.read_modify(value);
         let carry = self.status.bits() & 0x1;
         let carry_op = (operand & 0x1) == 1;
         operand = (operand >> 1) | carry << 7;
@@ -200,15 +224,15 @@ impl CPU {
     }
 
     fn dcp(&mut self, value: u16) {
-        let operand = self.bus.read(value) - 1;
+        let operand = self.read_modify(value).wrapping_sub(1);
         self.bus.write(value, operand);
-        let diff = self.a - operand;
+        let diff = self.a.wrapping_sub(operand);
         self.status.set_carry(self.a >= operand);
         self.status.set_zn(diff);
     }
 
     fn isc(&mut self, value: u16) {
-        let operand = self.bus.read(value) + 1;
+        let operand = self.read_modify(value).wrapping_add(1);
         self.bus.write(value, operand);
         self.add(operand);
     }
@@ -226,7 +250,7 @@ impl CPU {
         self.status.set_zn(self.a);
     }
     fn asl(&mut self, value: u16) {
-        let mut operand = self.bus.read(value);
+        let mut operand = self.read_modify(value);
         self.status.set_carry((operand & 0x80) > 0);
         operand <<= 1;
         self.bus.write(value, operand);
@@ -240,7 +264,7 @@ impl CPU {
         self.status.set_zn(self.a);
     }
     fn rol(&mut self, value: u16) {
-        let mut operand = self.bus.read(value);
+        let mut operand = self.read_modify(value);
         let carry = self.status.bits() & 0x1;
         self.status.set_carry((operand & 0x80) > 0);
         operand = (operand << 1) | carry;
@@ -254,7 +278,7 @@ impl CPU {
         self.status.set_zn(self.a);
     }
     fn lsr(&mut self, value: u16) {
-        let mut operand = self.bus.read(value);
+        let mut operand = self.read_modify(value);
         self.status.set_carry((operand & 0x1) == 1);
         operand >>= 1;
         self.status.set_zn(operand);
@@ -268,7 +292,7 @@ impl CPU {
         self.status.set_zn(self.a);
     }
     fn ror(&mut self, value: u16) {
-        let mut operand = self.bus.read(value);
+        let mut operand = self.read_modify(value);
         let carry = self.status.bits() & 0x1;
         self.status.set_carry((operand & 0x1) == 1);
         operand = (operand >> 1) | carry << 7;
@@ -291,13 +315,13 @@ impl CPU {
     }
 
     fn dec(&mut self, value: u16) {
-        let operand = self.bus.read(value) - 1;
+        let operand = self.read_modify(value).wrapping_sub(1);
         self.bus.write(value, operand);
         self.status.set_zn(operand);
     }
 
     fn inc(&mut self, value: u16) {
-        let operand = self.bus.read(value) + 1;
+        let operand = self.read_modify(value).wrapping_add(1);
         self.status.set_zn(operand);
         self.bus.write(value, operand);
     }
@@ -316,7 +340,7 @@ impl CPU {
         self.s = self.x
     }
     fn dex(&mut self, _: u16) {
-        self.x -= 1;
+        self.x = self.x.wrapping_sub(1);
         self.status.set_zn(self.x);
     }
 
@@ -345,7 +369,7 @@ impl CPU {
     fn sei(&mut self, _: u16) { self.status.set_interrupt(true) }
 
     fn dey(&mut self, _: u16) {
-        self.y -= 1;
+        self.y = self.y.wrapping_sub(1);
         self.status.set_zn(self.y);
     }
 
@@ -362,14 +386,14 @@ impl CPU {
     fn clv(&mut self, _: u16) { self.status.set_overflow(false) }
 
     fn iny(&mut self, _: u16) {
-        self.y += 1;
+        self.y = self.y.wrapping_add(1);
         self.status.set_zn(self.y);
     }
 
     fn cld(&mut self, _: u16) { self.status.set_decimal(false) }
 
     fn inx(&mut self, _: u16) {
-        self.x += 1;
+        self.x = self.x.wrapping_add(1);
         self.status.set_zn(self.x);
     }
 
@@ -424,7 +448,7 @@ impl CPU {
         (CPU::php, Impl(0x03)), (CPU::ora,   Imm(0x02)), (CPU::asl_a, Acc(0x02)), (CPU::anc,   Imm(0x02)), (CPU::nop,  Abs(0x04)), (CPU::ora,  Abs(0x04)), (CPU::asl,  Abs(0x06)), (CPU::slo,  Abs(0x06)),
         (CPU::bpl,  Rel(0x02)), (CPU::ora, IndrY(0x05)), (CPU::jam,        None), (CPU::slo, IndrY(0x88)), (CPU::nop,  ZpX(0x04)), (CPU::ora,  ZpX(0x04)), (CPU::asl,  ZpX(0x06)), (CPU::slo,  ZpX(0x06)), 
         (CPU::clc, Impl(0x02)), (CPU::ora,  AbsY(0x04)), (CPU::nop,  Impl(0x02)), (CPU::slo,  AbsY(0x87)), (CPU::nop, AbsX(0x04)), (CPU::ora, AbsX(0x04)), (CPU::asl, AbsX(0x87)), (CPU::slo, AbsX(0x87)),
-        (CPU::jsr,  Abs(0x06)), (CPU::and,  IndX(0x06)), (CPU::jam,        None), (CPU::rla,  IndX(0x08)), (CPU::bit,   Zp(0x03)), (CPU::and,   Zp(0x03)), (CPU::rol,   Zp(0x05)), (CPU::rla,   Zp(0x05)), 
+        (CPU::jsr, Impl(0x06)), (CPU::and,  IndX(0x06)), (CPU::jam,        None), (CPU::rla,  IndX(0x08)), (CPU::bit,   Zp(0x03)), (CPU::and,   Zp(0x03)), (CPU::rol,   Zp(0x05)), (CPU::rla,   Zp(0x05)), 
         (CPU::plp, Impl(0x04)), (CPU::and,   Imm(0x02)), (CPU::rol_a, Acc(0x02)), (CPU::anc,   Imm(0x02)), (CPU::bit,  Abs(0x04)), (CPU::and,  Abs(0x04)), (CPU::rol,  Abs(0x06)), (CPU::rla,  Abs(0x06)),
         (CPU::bmi,  Rel(0x02)), (CPU::and, IndrY(0x05)), (CPU::jam,        None), (CPU::rla, IndrY(0x88)), (CPU::nop,  ZpX(0x04)), (CPU::and,  ZpX(0x04)), (CPU::rol,  ZpX(0x06)), (CPU::rla,  ZpX(0x06)), 
         (CPU::sec, Impl(0x02)), (CPU::and,  AbsY(0x04)), (CPU::nop,  Impl(0x02)), (CPU::rla,  AbsY(0x87)), (CPU::nop, AbsX(0x04)), (CPU::and, AbsX(0x04)), (CPU::rol, AbsX(0x87)), (CPU::rla, AbsX(0x87)),
@@ -454,3 +478,89 @@ impl CPU {
         (CPU::sed, Impl(0x02)), (CPU::sbc,  AbsY(0x04)), (CPU::nop,  Impl(0x02)), (CPU::isc,  AbsY(0x87)), (CPU::nop, AbsX(0x04)), (CPU::sbc, AbsX(0x04)), (CPU::inc, AbsX(0x87)), (CPU::isc, AbsX(0x87)),
     ];
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::cpu::tests::nrom;
+
+    // Runs `opcode #operand` on `a` with the carry in, D set or clear, returns A, N, Z, C and V.
+    fn arithmetic(opcode: u8, a: u8, operand: u8, carry: bool, decimal: bool) -> (u8, bool, bool, bool, bool) {
+        let flags = [if decimal { 0xF8 } else { 0xD8 }, if carry { 0x38 } else { 0x18 }]; // SED/CLD, SEC/CLC
+        let (_rom, mut cpu) = nrom(&[flags[0], flags[1], 0xA9, a, opcode, operand]); // LDA #a
+        for _ in 0..4 {
+            cpu.step_instruction();
+        }
+        (cpu.a, cpu.status.negative(), cpu.status.zero(), cpu.status.carry(), cpu.status.overflow())
+    }
+
+    #[test]
+    fn adc() {
+        let cases = [
+            // A, operand, carry in => A, N, Z, C, V
+            (0x50, 0x10, false, (0x60, false, false, false, false)),
+            (0x50, 0x50, false, (0xA0, true, false, false, true)),
+            (0x50, 0x90, false, (0xE0, true, false, false, false)),
+            (0x50, 0xD0, false, (0x20, false, false, true, false)),
+            (0xD0, 0x90, false, (0x60, false, false, true, true)),
+            (0x80, 0xFF, false, (0x7F, false, false, true, true)),
+            (0x7F, 0x00, true, (0x80, true, false, false, true)),
+            (0xFF, 0x00, true, (0x00, false, true, true, false)),
+            (0x80, 0x80, false, (0x00, false, true, true, true)),
+        ];
+        for (a, operand, carry, expected) in cases {
+            assert_eq!(arithmetic(0x69, a, operand, carry, false), expected, "{a:#04X} + {operand:#04X} + {}", carry as u8);
+        }
+    }
+
+    #[test]
+    fn sbc() {
+        let cases = [
+            // A, operand, carry in (not borrow) => A, N, Z, C, V
+            (0x50, 0xF0, true, (0x60, false, false, false, false)),
+            (0x50, 0xB0, true, (0xA0, true, false, false, true)),
+            (0xD0, 0x70, true, (0x60, false, false, true, true)),
+            (0x80, 0x01, true, (0x7F, false, false, true, true)),
+            (0x7F, 0xFF, true, (0x80, true, false, false, true)),
+            (0x00, 0x00, false, (0xFF, true, false, false, false)),
+            (0x40, 0x40, true, (0x00, false, true, true, false)),
+        ];
+        for (a, operand, carry, expected) in cases {
+            assert_eq!(arithmetic(0xE9, a, operand, carry, false), expected, "{a:#04X} - {operand:#04X} - {}", !carry as u8);
+        }
+    }
+
+    // The 2A03 has no decimal mode, D is only a flag.
+    #[test]
+    fn decimal_flag_is_ignored() {
+        assert_eq!(arithmetic(0x69, 0x09, 0x01, false, true), (0x0A, false, false, false, false));
+        assert_eq!(arithmetic(0x69, 0x58, 0x46, true, true), (0x9F, true, false, false, true));
+        assert_eq!(arithmetic(0xE9, 0x10, 0x01, true, true), (0x0F, false, false, true, false));
+        assert_eq!(arithmetic(0xE9, 0x00, 0x01, true, true), (0xFF, true, false, false, false));
+    }
+
+    #[test]
+    fn jsr() {
+        let (_rom, mut cpu) = nrom(&[0x20, 0x00, 0x90]); // JSR $9000
+        cpu.step_instruction();
+        assert_eq!((cpu.pc, cpu.s), (0x9000, 0xFB));
+        assert_eq!((cpu.bus.debug_read(0x01FD), cpu.bus.debug_read(0x01FC)), (0x80, 0x02));
+    }
+
+    // With S at $02 the push of the return address' high byte lands on the operand's high
+    // byte before it's read.
+    #[test]
+    fn jsr_reads_target_high_byte_last() {
+        let (_rom, mut cpu) = nrom(&[
+            0xA2, 0x02, 0x9A, // LDX #$02, TXS
+            0xA9, 0x20, 0x8D, 0x00, 0x01, // LDA #$20, STA $0100
+            0xA9, 0x34, 0x8D, 0x01, 0x01, // LDA #$34, STA $0101
+            0xA9, 0x80, 0x8D, 0x02, 0x01, // LDA #$80, STA $0102
+            0x4C, 0x00, 0x01, // JMP $0100 (JSR $8034)
+        ]);
+        for _ in 0..10 {
+            cpu.step_instruction();
+        }
+        assert_eq!((cpu.pc, cpu.s), (0x0134, 0x00));
+        assert_eq!((cpu.bus.debug_read(0x0102), cpu.bus.debug_read(0x0101)), (0x01, 0x02));
+    }
+}
@@ -0,0 +1,500 @@
+mod bus;
+mod instructions;
+mod cpu_status;
+mod view;
+mod timeline;
+mod watchdog;
+#[cfg(feature = "single-step")]
+mod flat;
+
+pub use self::bus::*;
+pub use view::{StackView, ZeroPageEntry, zero_page};
+pub use timeline::{Timeline, TimelineEntry, TimelineKind};
+pub use watchdog::Watchdog;
+#[cfg(feature = "single-step")]
+pub use flat::FlatCpu;
+use crate::mapper::*;
+use crate::ppu::*;
+use cpu_status::*;
+use crate::cpu::instructions::*;
+use crate::state::{StateWriter, StateReader, Chunk};
+use crate::event::Event;
+use crate::trace::TraceLog;
+use std::fmt::Write;
+
+// CPU is guaranteed to receive NMI every interrupt
+const CYCLES_PER_FRAME: usize = 29780;
+// Bank numbers in (bank:address) debugger addresses count 16KB of PRG-ROM, like iNES does.
+const PRG_BANK_SIZE: usize = 0x4000;
+
+#[derive(Clone, Copy)]
+pub struct Registers {
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub p: u8,
+    pub s: u8,
+    pub pc: u16,
+    pub cycles: usize,
+}
+
+pub struct CPU {
+    a: u8, // Accumulator
+    y: u8, // register y
+    x: u8, // register x
+    pc: u16, // Program counter
+    s: u8, // Stack pointer (256-byte stack at $0100-$01FF.)
+    status: CPUStatus,
+    cycles_left: usize,
+    cycles: usize,
+    pub bus: BUS,
+    pub breakpoints: Vec<u16>,
+    pub bank_breakpoints: Vec<(usize, u16)>, // Only hit with that PRG bank mapped at the address
+    resume_from: Option<u16>,
+    pub trace: TraceLog,
+    pub interrupts: (u32, u32), // NMIs and IRQs taken, never reset
+}
+
+impl CPU {
+    pub fn new(rom: *const u8, mapper: Mapper_) -> Self {
+        CPU {
+            a: 0,
+            x: 0,
+            y: 0,
+            pc: 0,
+            s: 0xFD,
+            status: CPUStatus::new(),
+            bus: BUS::new(rom, mapper, PPU::new()),
+            cycles_left: 0,
+            cycles: 0,
+            breakpoints: Vec::new(),
+            bank_breakpoints: Vec::new(),
+            resume_from: None,
+            trace: TraceLog::new(),
+            interrupts: (0, 0),
+        }
+    }
+
+    // Runs CYCLES_PER_FRAME instructions (a few frames), `on_frame` is called as each frame
+    // completes.
+    pub fn run(&mut self, mut on_frame: impl FnMut(&mut BUS)) {
+        self.run_until(|bus| { on_frame(bus); false });
+    }
+
+    // Runs until the instruction during which the PPU completes a frame.
+    pub fn run_frame(&mut self, mut on_frame: impl FnMut(&mut BUS)) {
+        self.run_until(|bus| { on_frame(bus); true });
+    }
+
+    // Stops early on a breakpoint or once `on_frame` returns true.
+    fn run_until(&mut self, mut on_frame: impl FnMut(&mut BUS) -> bool) {
+        #[cfg(feature = "tracing")]
+        let _frame = tracing::debug_span!(target: "nass::cpu", "frame").entered();
+        self.trace.start_frame();
+        let mut frames = self.bus.ppu.frame_count();
+        for _ in 0..CYCLES_PER_FRAME { 
+            if self.hit_breakpoint() { break }
+            self.tick();
+            self.bus.tick(self.cycles_left);
+            if self.bus.ppu.frame_count() != frames {
+                frames = self.bus.ppu.frame_count();
+                if on_frame(&mut self.bus) { break }
+            }
+        }
+        self.bus.catch_up();
+        if self.bus.ppu.frame_count() != frames { on_frame(&mut self.bus); }
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    pub fn registers(&self) -> Registers {
+        Registers { a: self.a, x: self.x, y: self.y, p: self.status.bits(), s: self.s, pc: self.pc, cycles: self.cycles }
+    }
+
+    // Runs a single instruction (or interrupt), ignoring breakpoints.
+    pub fn step_instruction(&mut self) {
+        self.tick();
+        self.bus.tick(self.cycles_left);
+        self.bus.catch_up();
+    }
+
+    // PRG bank mapped at a CPU address, None outside ROM.
+    pub fn prg_bank(&self, addr: u16) -> Option<usize> {
+        self.bus.mapper.prg_rom_address(addr).map(|offset| offset / PRG_BANK_SIZE)
+    }
+
+    // Whether the last run stopped on a breakpoint.
+    pub fn at_breakpoint(&self) -> bool {
+        self.resume_from == Some(self.pc)
+    }
+
+    // Stops before executing a breakpoint address, the next run resumes past it.
+    fn hit_breakpoint(&mut self) -> bool {
+        if self.resume_from.take() == Some(self.pc) {
+            return false
+        }
+        let banked = self.bank_breakpoints.iter().any(|&(bank, addr)| addr == self.pc && self.prg_bank(addr) == Some(bank));
+        if !banked && !self.breakpoints.contains(&self.pc) {
+            return false
+        }
+        self.resume_from = Some(self.pc);
+        self.bus.events.push(Event::Breakpoint);
+        true
+    }
+
+    fn tick(&mut self) {
+        self.cycles_left = 0;
+        match self.bus.interrupt.take() {
+            Some(Interrupt::Nmi) => self.nmi(),
+            Some(Interrupt::Irq) if !self.status.interrupt() => self.irq(),
+            _ => self.execute(),
+        }
+        self.cycles = self.cycles.wrapping_add(self.cycles_left);
+    }
+
+    fn execute(&mut self) {
+        let op = self.bus.read(self.pc);
+        log_trace!(target: "nass::cpu", bank = ?self.prg_bank(self.pc), pc = self.pc, op, a = self.a, x = self.x, y = self.y, p = self.status.bits(), s = self.s);
+        if self.trace.is_enabled() {
+            let bank = match self.prg_bank(self.pc) { Some(bank) => format!("{bank:02X}"), None => "--".to_string() };
+            if let Some(trace) = self.trace.current() {
+                let _ = writeln!(trace, "{bank}:{:04X}  {:02X}  A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} CYC:{}",
+                    self.pc, op, self.a, self.x, self.y, self.status.bits(), self.s, self.cycles);
+            }
+        }
+        self.pc = self.pc.wrapping_add(1);
+        let (fun, addr_mode) = &CPU::OPCODES[op as usize];
+        let addr = self.get_address_mode(addr_mode.clone()); 
+        fun(self, addr);
+        if let Some(page) = self.bus.dma_page.take() {
+            self.oam_dma(page);
+        }
+    }
+
+    // OAM DMA halts the CPU for a cycle, and one more when it starts on an odd cycle to line up
+    // with a read cycle, then alternates reading the page and writing $2004 for 512 cycles.
+    // Unlike instructions it runs the bus a cycle at a time, so the PPU and the input ports
+    // (the page can be $40) see each access when it happens. https://www.nesdev.org/wiki/DMA
+    fn oam_dma(&mut self, page: u8) {
+        let halt = if self.cycles & 1 == 0 { 1 } else { 2 };
+        self.bus.tick(self.cycles_left);
+        self.cycles = self.cycles.wrapping_add(self.cycles_left);
+        self.cycles_left = 0;
+        for _ in 0..halt {
+            self.bus.read(self.pc); // The halted CPU repeats its read
+            self.dma_cycle();
+        }
+        for addr in (page as u16) << 8..=(page as u16) << 8 | 0xFF {
+            let value = self.bus.read(addr);
+            self.dma_cycle();
+            self.bus.write(0x2004, value);
+            self.dma_cycle();
+        }
+    }
+
+    fn dma_cycle(&mut self) {
+        self.bus.tick(1);
+        self.cycles = self.cycles.wrapping_add(1);
+    }
+
+    pub fn reset(&mut self) {
+        self.cycles_left = 7;
+        self.x = 0;
+        self.y = 0;
+        self.a = 0;
+        self.s = 0xFD;
+        self.status = CPUStatus::new();
+        self.pc = self.read_address(RESET_VECTOR);
+    }
+
+    fn nmi(&mut self) {
+        log_debug!(target: "nass::cpu", pc = self.pc, "NMI");
+        self.bus.events.push(Event::Nmi);
+        if self.bus.timeline.is_enabled() { self.bus.timeline.record(TimelineKind::Nmi, self.bus.ppu.position()); }
+        self.interrupts.0 = self.interrupts.0.wrapping_add(1);
+        self.cycles_left = 7; 
+        self.interrupt_dummy_reads();
+        self.push_stack(((self.pc & 0xFF00) >> 8) as u8);
+        self.push_stack((self.pc & 0x00FF) as u8);
+        self.push_stack(self.status.bits() & !0x10);
+        self.status.set_interrupt(true);
+        self.pc = self.read_address(NMI_VECTOR);
+    }
+
+    fn irq(&mut self) {
+        log_debug!(target: "nass::cpu", pc = self.pc, "IRQ");
+        self.bus.events.push(Event::MapperIrq);
+        if self.bus.timeline.is_enabled() { self.bus.timeline.record(TimelineKind::MapperIrq, self.bus.ppu.position()); }
+        self.interrupts.1 = self.interrupts.1.wrapping_add(1);
+        self.cycles_left = 7; 
+        self.interrupt_dummy_reads();
+        self.push_stack(((self.pc & 0xFF00) >> 8) as u8);
+        self.push_stack((self.pc & 0x00FF) as u8);
+        self.push_stack(self.status.bits() & !0x10);
+        self.status.set_interrupt(true);
+        self.pc = self.read_address(IRQ_VECTOR);
+    }
+
+    fn get_address_mode(&mut self, addr_mode: AddrMode) -> u16 {
+        match addr_mode {
+            AddrMode::Rel(cycles) => { self.cycles_left += cycles & CYCLE_MASK; 0 },
+            AddrMode::Acc(cycles) => { self.cycles_left += cycles & CYCLE_MASK; 0 },
+            AddrMode::Impl(cycles) => { self.cycles_left += cycles & CYCLE_MASK; 0 },
+            AddrMode::Imm(cycles) => {
+                self.cycles_left += cycles & CYCLE_MASK;
+                let operand = self.pc;
+                self.pc = self.pc.wrapping_add(1);
+                operand
+            }
+            AddrMode::Ind(cycles) => {
+                self.cycles_left += cycles & CYCLE_MASK;
+                let addr = self.read_address(self.pc);
+                self.pc = self.pc.wrapping_add(2);
+                let operand = (self.bus.read((addr & 0xFF00) | (addr.wrapping_add(1) & 0x00FF)) as u16) * 0x100 | self.bus.read(addr) as u16;
+                operand
+            }
+            AddrMode::Abs(cycles) => {
+                self.cycles_left += cycles & CYCLE_MASK;
+                let operand = self.read_address(self.pc);
+                self.pc = self.pc.wrapping_add(2);
+                operand
+            }
+            AddrMode::Zp(cycles) => { 
+                self.cycles_left += cycles & CYCLE_MASK;
+                let operand = self.bus.read(self.pc) as u16;
+                self.pc = self.pc.wrapping_add(1);
+                operand
+            }
+            AddrMode::ZpX(cycles) => {
+                self.cycles_left += cycles & CYCLE_MASK;
+                let operand = self.bus.read(self.pc).wrapping_add(self.x) as u16; 
+                self.pc = self.pc.wrapping_add(1);
+                operand
+            }
+            AddrMode::ZpY(cycles) => {
+                self.cycles_left += cycles & CYCLE_MASK;
+                let operand = self.bus.read(self.pc).wrapping_add(self.y) as u16;
+                self.pc = self.pc.wrapping_add(1);
+                operand
+            }
+            AddrMode::AbsX(cycles) => {
+                self.cycles_left += cycles & CYCLE_MASK;
+                let addr = self.read_address(self.pc);
+                self.pc = self.pc.wrapping_add(2);
+                let operand = addr.wrapping_add(self.x as u16);
+                if (cycles as u8 & CYCLE_PAGE_CROSS_MASK) == 0 {
+                    self.set_page_crossed(addr, operand);
+                }
+                self.dummy_read(addr, operand, cycles);
+                operand
+            }
+            AddrMode::AbsY(cycles) => {
+                self.cycles_left += cycles & CYCLE_MASK;
+                let addr = self.read_address(self.pc);
+                self.pc = self.pc.wrapping_add(2);
+                let operand = addr.wrapping_add(self.y as u16);
+                if (cycles as u8 & CYCLE_PAGE_CROSS_MASK) == 0 {
+                    self.set_page_crossed(addr, operand);
+                }
+                self.dummy_read(addr, operand, cycles);
+                operand
+            }
+            AddrMode::IndX(cycles) => {
+                self.cycles_left += cycles & CYCLE_MASK;
+                let arg = self.bus.read(self.pc).wrapping_add(self.x) as u16;
+                self.pc = self.pc.wrapping_add(1);
+                self.bus.read(arg & 0xFF) as u16 | (self.bus.read((arg + 1) & 0xFF) as u16) * 0x100
+            }
+            AddrMode::IndrY(cycles) => {
+                self.cycles_left += cycles & CYCLE_MASK;
+                let arg = self.bus.read(self.pc) as u16;
+                self.pc = self.pc.wrapping_add(1);
+                let addr = self.bus.read(arg) as u16 | (self.bus.read((arg + 1) & 0xFF) as u16) * 0x100;
+                let operand = addr.wrapping_add(self.y as u16);
+                if (cycles as u8 & CYCLE_PAGE_CROSS_MASK) == 0 {
+                    self.set_page_crossed(addr, operand);
+                }
+                self.dummy_read(addr, operand, cycles);
+                operand
+            }
+            AddrMode::None => 0
+        }
+    }
+
+    // Indexed modes read once before fixing up the high byte of the address: when the index
+    // crosses a page, and always for stores and read-modify-writes, the bus sees a read with
+    // the old high byte. Reads of $2002, $2007 and $4016 act on it like any other.
+    // https://www.nesdev.org/wiki/CPU_addressing_modes
+    fn dummy_read(&mut self, base: u16, operand: u16, cycles: usize) {
+        let crossed = (base & 0xFF00) != (operand & 0xFF00);
+        if crossed || (cycles as u8 & CYCLE_PAGE_CROSS_MASK) != 0 {
+            self.bus.read((base & 0xFF00) | (operand & 0x00FF));
+        }
+    }
+
+    fn set_page_crossed(&mut self, a: u16, b: u16) {
+        if (a & 0xFF00) != (b & 0xFF00) { 
+            self.cycles_left += 1; 
+        }
+    }
+
+    // Interrupt entry fetches the next opcode and its operand and throws both away.
+    // https://www.nesdev.org/wiki/CPU_interrupts
+    fn interrupt_dummy_reads(&mut self) {
+        self.bus.read(self.pc);
+        self.bus.read(self.pc);
+    }
+
+    // JSR, RTS and RTI read the stack at S once before pushing or pulling, while S is updated.
+    // https://www.nesdev.org/6502_cpu.txt
+    fn stack_dummy_read(&mut self) {
+        self.bus.read(0x100 + self.s as u16);
+    }
+
+    fn push_stack(&mut self, val: u8) {
+        self.bus.write(0x100 + self.s as u16, val);
+        self.s = self.s.wrapping_sub(1);
+    }
+    
+    fn pull_stack(&mut self) -> u8 {
+        self.s = self.s.wrapping_add(1);
+        let v = self.bus.read(0x100 + self.s as u16);
+        v
+    }
+
+    fn read_address(&mut self, addr: u16) -> u16 {
+        let addr = (self.bus.read(addr.wrapping_add(1)) as u16) * 0x100 + self.bus.read(addr) as u16;
+        addr
+    }
+
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.begin_chunk(Chunk::Cpu);
+        w.write_u8(self.a);
+        w.write_u8(self.x);
+        w.write_u8(self.y);
+        w.write_u16(self.pc);
+        w.write_u8(self.s);
+        w.write_u8(self.status.bits());
+        w.write_usize(self.cycles_left);
+        w.write_usize(self.cycles);
+        self.bus.save_state(w);
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.a = r.read_u8()?;
+        self.x = r.read_u8()?;
+        self.y = r.read_u8()?;
+        self.pc = r.read_u16()?;
+        self.s = r.read_u8()?;
+        self.status.update(r.read_u8()?);
+        self.cycles_left = r.read_usize()?;
+        self.cycles = r.read_usize()?;
+        self.bus.load_state(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // NROM image with `program` at $8000, where every vector points. The image has to
+    // outlive the CPU, which only keeps a pointer to it.
+    pub fn nrom(program: &[u8]) -> (Vec<u8>, CPU) {
+        let mut rom = b"NES\x1A\x02\x01\x00\x00".to_vec();
+        rom.resize(16, 0);
+        let mut prg = vec![0xEA; 0x8000];
+        prg[..program.len()].copy_from_slice(program);
+        prg[0x7FFA..].copy_from_slice(&[0x00, 0x80, 0x00, 0x80, 0x00, 0x80]);
+        rom.extend(prg);
+        rom.extend(vec![0; 0x2000]);
+        let mapper = crate::mapper::new(&rom, false).unwrap();
+        let mut cpu = CPU::new(rom.as_ptr(), mapper);
+        cpu.reset();
+        (rom, cpu)
+    }
+
+    #[test]
+    fn cycle_counter_wraps() {
+        let (_rom, mut cpu) = nrom(&[0xEA]); // NOP
+        cpu.cycles = usize::MAX - 1;
+        cpu.step_instruction();
+        assert_eq!(cpu.cycles, 0);
+    }
+
+    #[test]
+    fn registers_wrap() {
+        let (_rom, mut cpu) = nrom(&[0xCA, 0x88, 0xC8, 0xC8]); // DEX, DEY, INY, INY
+        cpu.step_instruction();
+        cpu.step_instruction();
+        assert_eq!((cpu.x, cpu.y), (0xFF, 0xFF));
+        cpu.step_instruction();
+        cpu.step_instruction();
+        assert_eq!(cpu.y, 0x01);
+    }
+
+    // Cycles taken by the last of `program`'s instructions.
+    fn last_cycles(program: &[u8], instructions: usize) -> usize {
+        let (_rom, mut cpu) = nrom(program);
+        for _ in 1..instructions {
+            cpu.step_instruction();
+        }
+        let start = cpu.cycles;
+        cpu.step_instruction();
+        cpu.cycles - start
+    }
+
+    #[test]
+    fn oam_dma_cycles() {
+        // STA $4014 on an even cycle, then on an odd one after a 3 cycle LDA
+        assert_eq!(last_cycles(&[0xA9, 0x02, 0x8D, 0x14, 0x40], 2), 4 + 513);
+        assert_eq!(last_cycles(&[0xA5, 0x00, 0xA9, 0x02, 0x8D, 0x14, 0x40], 3), 4 + 514);
+    }
+
+    #[test]
+    fn oam_dma_copies_page() {
+        let (_rom, mut cpu) = nrom(&[0xA9, 0x02, 0x8D, 0x14, 0x40]); // LDA #$02, STA $4014
+        for i in 0..=0xFF {
+            cpu.bus.write(0x0200 + i, i as u8 ^ 0x5A);
+        }
+        let dots = cpu.bus.ppu.position();
+        cpu.step_instruction();
+        cpu.step_instruction();
+        assert!(cpu.bus.ppu.oam().iter().enumerate().all(|(i, &value)| value == i as u8 ^ 0x5A));
+        // The PPU ran through the DMA
+        assert_ne!(cpu.bus.ppu.position(), dots);
+    }
+
+    // A DMA from $4000-$40FF reads $4016 like the CPU would, the joypad shifts a button out.
+    #[test]
+    fn oam_dma_clocks_joypad() {
+        let (_rom, mut cpu) = nrom(&[
+            0xA9, 0x01, 0x8D, 0x16, 0x40, // LDA #$01, STA $4016
+            0xA9, 0x00, 0x8D, 0x16, 0x40, // LDA #$00, STA $4016
+            0xA9, 0x40, 0x8D, 0x14, 0x40, // LDA #$40, STA $4014
+            0xAD, 0x16, 0x40, // LDA $4016
+        ]);
+        cpu.bus.ports[0].set_input(0x01); // A
+        for _ in 0..7 {
+            cpu.step_instruction();
+        }
+        assert_eq!(cpu.bus.ppu.oam()[0x16], 0x01); // A, read by the DMA
+        assert_eq!(cpu.a, 0x00); // B, read by the CPU
+    }
+
+    // With the strobe held high every read, the DMA's too, returns A.
+    #[test]
+    fn oam_dma_with_strobe_high() {
+        let (_rom, mut cpu) = nrom(&[
+            0xA9, 0x01, 0x8D, 0x16, 0x40, // LDA #$01, STA $4016
+            0xA9, 0x40, 0x8D, 0x14, 0x40, // LDA #$40, STA $4014
+            0xAD, 0x16, 0x40, // LDA $4016
+        ]);
+        cpu.bus.ports[0].set_input(0x01);
+        for _ in 0..5 {
+            cpu.step_instruction();
+        }
+        assert_eq!((cpu.bus.ppu.oam()[0x16], cpu.a), (0x01, 0x01));
+    }
+}
@@ -0,0 +1,54 @@
+// Interrupts and DMA of the frame being run and the last completed one, with when they
+// happened, for timing diagnostics. Frames end at vblank like `Event::FrameCompleted`.
+// https://www.nesdev.org/wiki/CPU_interrupts
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimelineKind {
+    Nmi,
+    MapperIrq,
+    OamDma,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimelineEntry {
+    pub cycle: u32, // CPU cycles into the frame, at the start of the instruction
+    pub scanline: u16,
+    pub dot: u16,
+    pub kind: TimelineKind,
+}
+
+pub struct Timeline {
+    pub enabled: bool,
+    cycle: u32,
+    current: Vec<TimelineEntry>,
+    last: Vec<TimelineEntry>,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Timeline { enabled: false, cycle: 0, current: Vec::new(), last: Vec::new() }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        cfg!(feature = "instrumentation") && self.enabled
+    }
+
+    pub fn tick(&mut self, cycles: usize) {
+        self.cycle = self.cycle.wrapping_add(cycles as u32);
+    }
+
+    pub fn record(&mut self, kind: TimelineKind, (scanline, dot): (u16, u16)) {
+        self.current.push(TimelineEntry { cycle: self.cycle, scanline, dot, kind });
+    }
+
+    pub fn end_frame(&mut self) {
+        self.last = std::mem::take(&mut self.current);
+        self.cycle = 0;
+    }
+
+    pub fn last(&self) -> &[TimelineEntry] {
+        &self.last
+    }
+}
@@ -0,0 +1,61 @@
+// Decoded views of the stack page and zero page for debugger frontends.
+// https://www.nesdev.org/wiki/Stack
+
+const JSR: u8 = 0x20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StackSlot {
+    pub addr: u16,
+    pub value: u8,
+    pub in_use: bool, // Between the stack pointer and $01FF, pushed and not pulled yet
+    // On the low byte of a JSR return address: where RTS goes back to.
+    pub return_to: Option<u16>,
+}
+
+pub struct StackView {
+    pub s: u8,
+    pub slots: Vec<StackSlot>, // $0100-$01FF
+}
+
+impl StackView {
+    // Pairs of pushed bytes pointing just past a JSR are taken as return addresses.
+    pub fn read(s: u8, peek: impl Fn(u16) -> u8) -> Self {
+        let mut slots: Vec<StackSlot> = (0x100..=0x1FF).map(|addr| {
+            StackSlot { addr, value: peek(addr), in_use: addr > 0x100 | s as u16, return_to: None }
+        }).collect();
+        let mut i = s as usize + 1;
+        while i < 0xFF {
+            let pushed = u16::from_le_bytes([slots[i].value, slots[i + 1].value]);
+            if peek(pushed.wrapping_sub(2)) == JSR {
+                slots[i].return_to = Some(pushed.wrapping_add(1));
+                i += 2;
+            } else {
+                i += 1;
+            }
+        }
+        StackView { s, slots }
+    }
+
+    // One u32 per slot: value, bit 8 in use, bit 9 return address, return address << 16.
+    pub fn to_words(&self) -> Vec<u32> {
+        self.slots.iter().map(|slot| {
+            slot.value as u32 | (slot.in_use as u32) << 8 | (slot.return_to.is_some() as u32) << 9
+                | (slot.return_to.unwrap_or(0) as u32) << 16
+        }).collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ZeroPageEntry {
+    pub addr: u8,
+    pub value: u8,
+    // The little-endian word starting here, what (zp),Y and (zp,X) would use as a pointer.
+    pub word: u16,
+}
+
+pub fn zero_page(peek: impl Fn(u16) -> u8) -> Vec<ZeroPageEntry> {
+    (0..=0xFF).map(|addr: u8| {
+        let word = u16::from_le_bytes([peek(addr as u16), peek(addr.wrapping_add(1) as u16)]);
+        ZeroPageEntry { addr, value: word as u8, word }
+    }).collect()
+}
@@ -0,0 +1,34 @@
+// Crash detection: a game that spins in a few bytes of code with IRQs masked, NMI off and
+// no I/O can only be woken by a reset. After `frames` frames like that the frontend gets
+// Event::Hang once, so it can offer to reset.
+// Window of PC values still counted as the same loop.
+const LOOP_BYTES: u16 = 16;
+
+pub struct Watchdog {
+    pub frames: u32, // 0 is off
+    stuck: u32,
+    pc: u16,
+    fired: bool,
+}
+
+impl Watchdog {
+    pub fn new() -> Self {
+        Watchdog { frames: 0, stuck: 0, pc: 0, fired: false }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.frames > 0
+    }
+
+    // Once per completed frame, true when the game has just been declared hung.
+    pub fn frame(&mut self, pc: u16, interrupts_off: bool, io: bool) -> bool {
+        if !interrupts_off || io || pc.abs_diff(self.pc) >= LOOP_BYTES {
+            (self.stuck, self.pc, self.fired) = (0, pc, false);
+            return false
+        }
+        self.stuck += 1;
+        if self.fired || self.stuck < self.frames { return false }
+        self.fired = true;
+        true
+    }
+}
@@ -0,0 +1,139 @@
+mod websocket;
+
+use std::{io, net::{TcpListener, ToSocketAddrs}, fmt::Write};
+use crate::emulator::Emulator;
+use self::websocket::{WebSocket, Message, base64};
+
+// Caps a single memory read so a typo can't ask for the whole address space a byte at a time.
+const MAX_READ: usize = 0x1000;
+
+// Lets a browser debugger attach to a native emulator over WebSocket. Call `poll` once per
+// frame, and only step the emulator while `paused` is false. One client at a time.
+//
+// Requests are flat JSON objects with a "cmd" and an optional "id" that is echoed back:
+//   {"cmd":"registers"}             -> {"a":..,"x":..,"y":..,"p":..,"s":..,"pc":..,"cycles":..}
+//   {"cmd":"read","addr":N,"len":N} -> {"data":[..]} (RAM and cartridge space only)
+//   {"cmd":"break","addr":N}        -> {"ok":true}, "unbreak" removes it
+//   {"cmd":"pause"} / {"cmd":"resume"}
+//   {"cmd":"step"}                  -> one instruction, answers like "registers"
+//   {"cmd":"frame"}                 -> one frame (stops at breakpoints), answers like "registers"
+//   {"cmd":"screenshot"}            -> {"png":"<base64>"}
+// Failures answer {"error":"..."}.
+pub struct DebugServer {
+    listener: TcpListener,
+    client: Option<WebSocket>,
+    paused: bool,
+}
+
+enum Value {
+    Str(String),
+    Num(i64),
+}
+
+impl DebugServer {
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<DebugServer> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(DebugServer { listener, client: None, paused: false })
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    // Accepts a new client and answers everything it has sent so far.
+    pub fn poll(&mut self, emulator: &mut Emulator) {
+        if let Ok((stream, _)) = self.listener.accept() {
+            let _ = stream.set_nonblocking(false);
+            self.client = WebSocket::accept(stream).ok();
+        }
+        while let Some(client) = self.client.as_mut() {
+            let request = match client.poll() {
+                Ok(Some(Message::Text(text))) => text,
+                Ok(None) => break,
+                Ok(Some(Message::Close)) | Err(_) => { self.client = None; break }
+            };
+            let response = self.handle(&request, emulator);
+            if let Some(client) = self.client.as_mut() {
+                if client.send(&response).is_err() { self.client = None; }
+            }
+        }
+    }
+
+    fn handle(&mut self, request: &str, emulator: &mut Emulator) -> String {
+        let fields = match parse_object(request) {
+            Some(fields) => fields,
+            None => return "{\"error\":\"bad request\"}".to_string(),
+        };
+        let number = |key: &str| fields.iter().find_map(|(k, v)| match v {
+            Value::Num(n) if k == key => Some(*n),
+            _ => None,
+        });
+        let cmd = fields.iter().find_map(|(k, v)| match v {
+            Value::Str(s) if k == "cmd" => Some(s.as_str()),
+            _ => None,
+        });
+
+        let mut response = String::from("{");
+        if let Some(id) = number("id") { let _ = write!(response, "\"id\":{id},"); }
+        let addr = number("addr").map(|addr| addr as u16);
+        match (cmd, addr) {
+            (Some("registers"), _) => write_registers(&mut response, emulator),
+            (Some("read"), Some(addr)) => {
+                let len = (number("len").unwrap_or(1).max(0) as usize).min(MAX_READ);
+                let data: Vec<String> = (0..len).map(|i| emulator.debug_read(addr.wrapping_add(i as u16)).to_string()).collect();
+                let _ = write!(response, "\"data\":[{}]", data.join(","));
+            },
+            (Some("break"), Some(addr)) => { emulator.add_breakpoint(addr); response.push_str("\"ok\":true"); },
+            (Some("unbreak"), Some(addr)) => { emulator.remove_breakpoint(addr); response.push_str("\"ok\":true"); },
+            (Some("pause"), _) => { self.paused = true; response.push_str("\"ok\":true"); },
+            (Some("resume"), _) => { self.paused = false; response.push_str("\"ok\":true"); },
+            (Some("step"), _) => { emulator.step_instruction(); write_registers(&mut response, emulator); },
+            (Some("frame"), _) => { emulator.step(); write_registers(&mut response, emulator); },
+            (Some("screenshot"), _) => { let _ = write!(response, "\"png\":\"{}\"", base64(&emulator.frame_png())); },
+            _ => response.push_str("\"error\":\"unknown command\""),
+        }
+        response.push('}');
+        response
+    }
+}
+
+fn write_registers(response: &mut String, emulator: &Emulator) {
+    let r = emulator.registers();
+    let _ = write!(response, "\"a\":{},\"x\":{},\"y\":{},\"p\":{},\"s\":{},\"pc\":{},\"cycles\":{}",
+        r.a, r.x, r.y, r.p, r.s, r.pc, r.cycles);
+}
+
+// A flat object of string and integer values, all the protocol needs.
+fn parse_object(text: &str) -> Option<Vec<(String, Value)>> {
+    let mut chars = text.trim().strip_prefix('{')?.strip_suffix('}')?.chars().peekable();
+    let mut fields = Vec::new();
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ',').is_some() {}
+        if chars.peek().is_none() { return Some(fields) }
+        let key = parse_string(&mut chars)?;
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.next()? != ':' { return None }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let value = if chars.peek() == Some(&'"') {
+            Value::Str(parse_string(&mut chars)?)
+        } else {
+            let mut number = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '-') { number.push(c); }
+            Value::Num(number.parse().ok()?)
+        };
+        fields.push((key, value));
+    }
+}
+
+fn parse_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<String> {
+    if chars.next()? != '"' { return None }
+    let mut string = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(string),
+            '\\' => string.push(chars.next()?),
+            c => string.push(c),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::{io::{self, Read, Write}, net::TcpStream};
+
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+pub enum Message {
+    Text(String),
+    Close,
+}
+
+// Just enough of RFC 6455 for one debugger client: unfragmented text frames, ping and close.
+pub struct WebSocket {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+}
+
+impl WebSocket {
+    // Answers the HTTP upgrade request, then switches the stream to non-blocking.
+    pub fn accept(mut stream: TcpStream) -> io::Result<WebSocket> {
+        stream.set_read_timeout(Some(std::time::Duration::from_secs(2)))?;
+        let mut request = Vec::new();
+        let mut byte = [0; 1];
+        while !request.ends_with(b"\r\n\r\n") {
+            if stream.read(&mut byte)? == 0 || request.len() > 8192 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "bad handshake"))
+            }
+            request.push(byte[0]);
+        }
+        let request = String::from_utf8_lossy(&request);
+        let key = request.lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("sec-websocket-key"))
+            .map(|(_, value)| value.trim().to_string())
+            .ok_or(io::Error::new(io::ErrorKind::InvalidData, "not a websocket upgrade"))?;
+        let accept = base64(&sha1(format!("{key}{GUID}").as_bytes()));
+        write!(stream, "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {accept}\r\n\r\n")?;
+        stream.set_nonblocking(true)?;
+        Ok(WebSocket { stream, buffer: Vec::new() })
+    }
+
+    // Next complete message, None when nothing has fully arrived yet.
+    pub fn poll(&mut self) -> io::Result<Option<Message>> {
+        let mut chunk = [0; 4096];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Ok(Some(Message::Close)),
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        while let Some((opcode, payload, length)) = parse_frame(&self.buffer) {
+            self.buffer.drain(..length);
+            match opcode {
+                0x1 => return Ok(Some(Message::Text(String::from_utf8_lossy(&payload).into_owned()))),
+                0x8 => return Ok(Some(Message::Close)),
+                0x9 => self.send_frame(0xA, &payload)?,
+                _ => (),
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn send(&mut self, text: &str) -> io::Result<()> {
+        self.send_frame(0x1, text.as_bytes())
+    }
+
+    fn send_frame(&mut self, opcode: u8, payload: &[u8]) -> io::Result<()> {
+        let mut frame = vec![0x80 | opcode];
+        match payload.len() {
+            len @ 0..=125 => frame.push(len as u8),
+            len @ 126..=0xFFFF => { frame.push(126); frame.extend_from_slice(&(len as u16).to_be_bytes()); },
+            len => { frame.push(127); frame.extend_from_slice(&(len as u64).to_be_bytes()); },
+        }
+        frame.extend_from_slice(payload);
+        // The socket is non-blocking, big frames (screenshots) may need a few tries.
+        let mut written = 0;
+        while written < frame.len() {
+            match self.stream.write(&frame[written..]) {
+                Ok(n) => written += n,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::yield_now(),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+// (opcode, unmasked payload, frame length) of the first frame once all of it is buffered.
+fn parse_frame(bytes: &[u8]) -> Option<(u8, Vec<u8>, usize)> {
+    let opcode = bytes.first()? & 0x0F;
+    let masked = bytes.get(1)? & 0x80 != 0;
+    let (length, mut offset) = match bytes[1] & 0x7F {
+        126 => (u16::from_be_bytes(bytes.get(2..4)?.try_into().ok()?) as usize, 4),
+        127 => (u64::from_be_bytes(bytes.get(2..10)?.try_into().ok()?) as usize, 10),
+        length => (length as usize, 2),
+    };
+    let mask = if masked {
+        offset += 4;
+        bytes.get(offset - 4..offset)?.to_vec()
+    } else {
+        vec![0; 4]
+    };
+    let payload = bytes.get(offset..offset.checked_add(length)?)?;
+    let payload = payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]).collect();
+    Some((opcode, payload, offset + length))
+}
+
+fn sha1(message: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut data = message.to_vec();
+    data.push(0x80);
+    while data.len() % 64 != 56 { data.push(0); }
+    data.extend_from_slice(&(message.len() as u64 * 8).to_be_bytes());
+    for block in data.chunks(64) {
+        let mut w = [0u32; 80];
+        for i in 0..16 {
+            w[i] = u32::from_be_bytes(block[4 * i..4 * i + 4].try_into().unwrap());
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+    let mut digest = [0; 20];
+    for (i, word) in h.iter().enumerate() {
+        digest[4 * i..4 * i + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3F] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
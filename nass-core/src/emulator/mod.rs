@@ -0,0 +1,1911 @@
+use crate::{ cpu::*, mapper::*, ppu::{PPU, PowerOn, Overlay, COLORS, SPRITES_PER_SCANLINE, HdPack, Split, LineHook}, state::*, event::Event, rng::Rng, clock::{Clock, Region, RegionSource, MAX_ALIGNMENTS}, input::{DeviceKind, TapeMode, Macros, new_device}, report, png, frame::{Frame, FrameBuffer, Burst, Replay, REPLAY_WIDTH, REPLAY_HEIGHT, MAX_REPLAY_SECONDS, REPLAY_FRAME_STEP}, gif::{self, GifFrame}, header::{self, Header, GameDb}, accuracy::Accuracy, patch::Patch, text::Table, hash::{crc32, crc32_iter}, settings::SettingsDb, condition::{Condition, Rules}, practice::Practice, stats::{Stats, RomStats, NTSC_FRAME_US, PAL_FRAME_US}, metrics::{Metrics, FrameTimer}, save_sync::{SaveSync, SyncKind, SyncStore, Diff, encode} };
+use std::fmt::Write;
+
+// blargg's test ROMs report through PRG RAM: status at $6000, signature at $6001-$6003
+// and a zero-terminated message from $6004.
+const TEST_SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61];
+const TEST_RUNNING: u8 = 0x80;
+const TEST_NEEDS_RESET: u8 = 0x81;
+const TEST_TIMEOUT: u8 = 0xFF;
+// Differing pixels in `frame_diff_png`.
+const DIFF_COLOR: u32 = 0xFF0000FF;
+// Range accepted by `set_speed`.
+const MIN_SPEED: f32 = 0.1;
+const MAX_SPEED: f32 = 16.0;
+
+// `set_alignment` value asking for a random one.
+const RANDOM_ALIGNMENT: u8 = 0xFF;
+
+// What one `run_frame` did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameReport {
+    pub cycles: usize, // CPU cycles executed
+    pub nmis: u32,
+    pub irqs: u32,
+    pub sprite_hit_line: Option<u16>, // Scanline of the first sprite 0 hit
+    pub frame_hash: u32, // CRC-32 of the frame's pixels
+}
+
+pub struct Emulator {
+    cpu: Option<CPU>,
+    rom: Vec<u8>,
+    save: Vec<u8>,
+    state: Vec<u8>,
+    tape: Vec<u8>,
+    report: Vec<u8>,
+    image: Vec<u8>,
+    rects: Vec<u16>,
+    rerendered: Vec<u32>,
+    game_db: GameDb,
+    db_text: Vec<u8>,
+    settings: SettingsDb,
+    settings_text: Vec<u8>,
+    macros: Macros,
+    practice: Practice,
+    rules: Rules,
+    stats: Stats,
+    // Of the ROM as loaded, edits (`edit_rom`) don't make it another game: states, resume
+    // snapshots, stats and settings stay keyed by it.
+    rom_crc: Option<u32>,
+    resume: Resume,
+    state_rom_check: bool,
+    repaired: Vec<u8>,
+    hd_file: Vec<u8>,
+    hd_files: Vec<Vec<u8>>,
+    patch: Patch,
+    patch_file: Vec<u8>,
+    table: Option<Table>,
+    text: Vec<u8>,
+    text_matches: Vec<u32>,
+    frame_report: [u32; 5],
+    frame_timer: FrameTimer,
+    speed: f32,
+    burst: Burst,
+    watchdog: Watchdog,
+    replay: Replay,
+    save_sync: SaveSync,
+    sync: Vec<u8>,
+    metrics: [f32; 3],
+    view: Vec<u32>,
+    diff_line: Option<usize>,
+    a12_filter: usize,
+    old_irq: bool,
+    save_dirty: bool,
+    seed: Option<u64>,
+    ppu_power_on: PowerOn,
+    alignment: Option<u8>, // None draws one at power on
+    region: Region,
+    region_detection: bool,
+    mapper_fallback: bool,
+    accuracy: Accuracy,
+    overclock_lines: u16,
+    frame_pool: bool,
+}
+
+impl Emulator {
+    pub fn new() -> Self {
+        report::record_panics();
+        Emulator { 
+            cpu: None,
+            rom: Vec::new(),
+            save: Vec::new(),
+            state: Vec::new(),
+            tape: Vec::new(),
+            report: Vec::new(),
+            image: Vec::new(),
+            rects: Vec::new(),
+            rerendered: Vec::new(),
+            game_db: GameDb::new(),
+            db_text: Vec::new(),
+            settings: SettingsDb::new(),
+            settings_text: Vec::new(),
+            macros: Macros::new(),
+            practice: Practice::new(),
+            rules: Rules::new(),
+            stats: Stats::new(),
+            rom_crc: None,
+            resume: Resume::new(),
+            state_rom_check: true,
+            repaired: Vec::new(),
+            hd_file: Vec::new(),
+            hd_files: Vec::new(),
+            patch: Patch::new(),
+            patch_file: Vec::new(),
+            table: None,
+            text: Vec::new(),
+            text_matches: Vec::new(),
+            frame_report: [0; 5],
+            frame_timer: FrameTimer::new(),
+            speed: 1.0,
+            burst: Burst::new(),
+            watchdog: Watchdog::new(),
+            replay: Replay::new(),
+            save_sync: SaveSync::new(),
+            sync: Vec::new(),
+            metrics: [0.0; 3],
+            view: Vec::new(),
+            diff_line: None,
+            a12_filter: A12_FILTER_M2,
+            old_irq: false,
+            save_dirty: false,
+            seed: None,
+            ppu_power_on: PowerOn::Zeroed,
+            alignment: Some(0),
+            region: Region::Ntsc,
+            region_detection: true,
+            mapper_fallback: false,
+            accuracy: Accuracy::BALANCED,
+            overclock_lines: 0,
+            frame_pool: false,
+        }
+    }
+
+    pub fn disassemble(&mut self) {
+        self.rom_crc = header::rom_crc32(&self.rom).ok();
+        if let Err(str) = self.power_on() { panic!("{str}"); }
+    }
+
+    pub fn load_rom(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.rom = bytes.to_vec();
+        self.patch.clear();
+        self.frame_timer.clear();
+        self.save_sync.clear();
+        self.rom_crc = header::rom_crc32(&self.rom).ok();
+        self.power_on()?;
+        self.reset_cpu();
+        if let Some(stats) = self.rom_stats_mut() { stats.sessions += 1; }
+        if self.resume.auto { self.resume(); }
+        Ok(())
+    }
+
+    fn power_on(&mut self) -> Result<(), String> {
+        #[cfg(feature = "rcheevos")]
+        let frame_hook = self.cpu.as_mut().and_then(|cpu| cpu.bus.frame_hook.take());
+        let line_hook = self.cpu.as_mut().and_then(|cpu| cpu.bus.ppu.line_hook.take());
+        self.cpu = None;
+        let mut cpu = self.new_cpu()?;
+        #[cfg(feature = "rcheevos")]
+        { cpu.bus.frame_hook = frame_hook; }
+        cpu.bus.ppu.line_hook = line_hook;
+        self.cpu = Some(cpu);
+        Ok(())
+    }
+
+    // A powered-on machine for the loaded ROM.
+    fn new_cpu(&self) -> Result<CPU, String> {
+        let crc = self.rom_crc;
+        let mut mapper = new(&self.rom, self.mapper_fallback).map_err(|e| {
+            match crc.and_then(|crc| self.game_db.name(crc)) {
+                Some(name) => format!("{name}: {e}"),
+                None => e,
+            }
+        })?;
+        mapper.set_irq_options(self.a12_filter, self.old_irq);
+        let mut cpu = CPU::new(self.rom.as_ptr(), mapper);
+        // Per-game settings win over the emulator-wide ones without replacing them.
+        let game = crc.and_then(|crc| self.settings.get(crc)).copied().unwrap_or_default();
+        let accuracy = game.accuracy.unwrap_or(self.accuracy);
+        let (region, _) = self.detect_region();
+        cpu.bus.clock = Clock::new(region);
+        cpu.bus.ppu.set_region(region);
+        cpu.bus.lockstep = accuracy.contains(Accuracy::PER_DOT_PPU);
+        cpu.bus.ppu.accuracy = accuracy;
+        cpu.bus.overclock_lines = game.overclock.unwrap_or(self.overclock_lines);
+        cpu.bus.ppu.frame.pool = self.frame_pool;
+        for (port, kind) in cpu.bus.ports.iter_mut().zip(game.ports) {
+            if let Some(kind) = kind { *port = new_device(kind); }
+        }
+        if let Some(enabled) = game.flicker_reduction {
+            cpu.bus.ppu.sprite_limit = if enabled { 64 } else { SPRITES_PER_SCANLINE };
+        }
+        cpu.bus.rng = Rng::new(self.seed.unwrap_or(0));
+        if self.seed.is_some() {
+            cpu.bus.randomize_ram();
+        }
+        cpu.bus.ppu.power_on(self.ppu_power_on, &mut cpu.bus.rng);
+        let alignment = match self.alignment {
+            Some(alignment) => alignment as usize,
+            None => (cpu.bus.rng.next_u64() % cpu.bus.clock.alignments() as u64) as usize,
+        };
+        cpu.bus.clock.set_alignment(alignment);
+        Ok(cpu)
+    }
+
+    pub fn get_color(&self, index: usize) -> u32 {
+        match self.cpu.as_ref() {
+            Some(cpu) => COLORS[(cpu.bus.ppu.palette_table[index] & 0x3F) as usize],
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Without a seed RAM powers on zeroed, with one it gets reproducible garbage.
+    // Takes effect on the next power on (disassemble).
+    pub fn set_power_on_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    // PPU memory at power on (a `ppu::PowerOn`), Random draws from the power-on seed (0 without
+    // one). False for an unknown mode. Takes effect on the next power on.
+    pub fn set_ppu_power_on(&mut self, mode: u8) -> bool {
+        match PowerOn::from_u8(mode) {
+            Some(power_on) => { self.ppu_power_on = power_on; true },
+            None => false,
+        }
+    }
+
+    // CPU/PPU clock alignment at power on, 0-3 (NTSC) or 0-4 (PAL, NTSC takes 4 as 0) or
+    // RANDOM_ALIGNMENT to draw one from the power-on seed like hardware does. Movies and TAS
+    // verification want it fixed, it's 0 by default. False for anything else. Takes effect on
+    // the next power on.
+    pub fn set_alignment(&mut self, alignment: u8) -> bool {
+        match alignment {
+            RANDOM_ALIGNMENT => self.alignment = None,
+            alignment if (alignment as usize) < MAX_ALIGNMENTS => self.alignment = Some(alignment),
+            _ => return false,
+        }
+        true
+    }
+
+    // Takes effect on the next power on (disassemble). ROMs known to be PAL still run as PAL
+    // unless detection is off, see `detect_region`.
+    pub fn set_region(&mut self, pal: bool) {
+        self.region = if pal { Region::Pal } else { Region::Ntsc };
+    }
+
+    // Boot ROMs on mappers that aren't implemented as NROM for inspection instead of
+    // refusing them, see `mapper::new`. Takes effect on the next power on.
+    pub fn set_mapper_fallback(&mut self, enabled: bool) {
+        self.mapper_fallback = enabled;
+    }
+
+    // Whether the loaded ROM runs on its own mapper rather than the NROM fallback.
+    pub fn is_mapper_supported(&self) -> bool {
+        Header::parse(&self.rom).is_ok_and(|header| is_supported(header.mapper))
+    }
+
+    // With detection off only per-game settings and `set_region` count. Takes effect on the
+    // next power on.
+    pub fn set_region_detection(&mut self, enabled: bool) {
+        self.region_detection = enabled;
+    }
+
+    // Region for the loaded ROM and what decided it.
+    pub fn detect_region(&self) -> (Region, RegionSource) {
+        let crc = self.rom_crc;
+        if let Some(region) = crc.and_then(|crc| self.settings.get(crc)).and_then(|game| game.region) {
+            return (region, RegionSource::Settings)
+        }
+        if self.region_detection {
+            if let Some(region) = crc.and_then(|crc| self.game_db.get(crc)).and_then(|fix| fix.region) {
+                return (region, RegionSource::GameDb)
+            }
+            if Header::parse(&self.rom).is_ok_and(|header| header.region == Region::Pal) {
+                return (Region::Pal, RegionSource::Header)
+            }
+        }
+        (self.region, RegionSource::Default)
+    }
+
+    // The running region (bit 0 set for PAL) and its `RegionSource` in bits 8-15.
+    pub fn region_info(&self) -> u32 {
+        let pal = match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.clock.region == Region::Pal,
+            None => { panic!("Emulator not initialized."); }
+        };
+        pal as u32 | (self.detect_region().1 as u32) << 8
+    }
+
+    // Lockstep ticks the PPU after every instruction, otherwise it only catches up on
+    // register access or once it's a scanline behind. Faster, but NMI and mapper IRQs
+    // can arrive late.
+    pub fn set_lockstep(&mut self, lockstep: bool) {
+        let mut accuracy = self.accuracy;
+        accuracy.set(Accuracy::PER_DOT_PPU, lockstep);
+        self.set_accuracy(accuracy.bits());
+    }
+
+    // 0 fast, 1 balanced (the default), 2 accurate.
+    pub fn set_accuracy_preset(&mut self, preset: u8) -> bool {
+        match Accuracy::preset(preset) {
+            Ok(accuracy) => { self.set_accuracy(accuracy.bits()); true },
+            Err(_) => false,
+        }
+    }
+
+    // Individual `Accuracy` flags, usually a preset with some bits flipped.
+    pub fn set_accuracy(&mut self, bits: u8) {
+        self.accuracy = Accuracy::from_bits_truncate(bits);
+        if let Some(cpu) = self.cpu.as_mut() {
+            cpu.bus.lockstep = self.accuracy.contains(Accuracy::PER_DOT_PPU);
+            cpu.bus.ppu.accuracy = self.accuracy;
+        }
+    }
+
+    pub fn get_accuracy(&self) -> u8 {
+        self.accuracy.bits()
+    }
+
+    // Extra CPU scanlines per frame (0 is off), run after the vblank line with the PPU
+    // paused. Movies have to be played back with the setting they were recorded with.
+    pub fn set_overclock(&mut self, lines: u16) {
+        self.overclock_lines = lines;
+        if let Some(cpu) = self.cpu.as_mut() {
+            cpu.bus.overclock_lines = lines;
+        }
+    }
+
+    pub fn get_overclock(&self) -> u16 {
+        self.overclock_lines
+    }
+
+    // Emulation speed for the frontend's pacing, 1.0 is the console's own rate. Frames run the
+    // same at any speed, only how often the frontend calls `step` changes.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = if speed.is_finite() { speed.clamp(MIN_SPEED, MAX_SPEED) } else { 1.0 };
+    }
+
+    pub fn get_speed(&self) -> f32 {
+        self.speed
+    }
+
+    // Host milliseconds between frames at the current speed and region.
+    pub fn frame_interval_ms(&self) -> f64 {
+        let region = match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.clock.region,
+            None => { panic!("Emulator not initialized."); }
+        };
+        let frame_us = if region == Region::Pal { PAL_FRAME_US } else { NTSC_FRAME_US };
+        frame_us as f64 / 1000.0 / self.speed as f64
+    }
+
+    pub fn set_mmc3_irq(&mut self, a12_filter: usize, old_revision: bool) {
+        self.a12_filter = a12_filter;
+        self.old_irq = old_revision;
+        if let Some(cpu) = self.cpu.as_mut() {
+            cpu.bus.mapper.set_irq_options(a12_filter, old_revision);
+        }
+    }
+
+    // 0 when the cartridge has nothing to save, otherwise a SaveKind.
+    pub fn save_kind(&mut self) -> u8 {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.mapper.save_media().map_or(0, |m| m.kind() as u8),
+            None => 0,
+        }
+    }
+
+    // PRG-RAM access as the game left it: bit 0 enabled, bit 1 writable, bit 7 set when the
+    // board has protection bits at all (otherwise PRG-RAM is always open).
+    pub fn wram_protection(&self) -> u8 {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.mapper.wram_protection().map_or(0, |p| 0x80 | (p.writable as u8) << 1 | p.enabled as u8),
+            None => 0,
+        }
+    }
+
+    pub fn is_save_dirty(&mut self) -> bool {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.mapper.save_media().is_some_and(|m| m.is_dirty()),
+            None => false,
+        }
+    }
+
+    // Copies the cartridge save into the shared buffer as a raw .sav file (see
+    // `mapper::sav_len`), returns its length.
+    pub fn export_save(&mut self) -> usize {
+        let header = self.fixed_header();
+        let media = match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.mapper.save_media(),
+            None => { panic!("Emulator not initialized."); }
+        };
+        self.save.clear();
+        if let Some(media) = media {
+            let len = header.map_or(media.data().len(), |header| sav_len(media, &header));
+            self.save.extend_from_slice(&media.data()[..len]);
+            media.clear_dirty();
+        }
+        self.save.len()
+    }
+
+    // Header of the loaded ROM with the game database's fixes.
+    fn fixed_header(&self) -> Option<Header> {
+        let mut header = Header::parse(&self.rom).ok()?;
+        if let Some(fix) = self.rom_crc.and_then(|crc| self.game_db.get(crc)) {
+            fix.apply(&mut header);
+        }
+        Some(header)
+    }
+
+    pub fn import_save(&mut self) {
+        match self.cpu.as_mut() {
+            Some(cpu) => if let Some(media) = cpu.bus.mapper.save_media() { media.load(&self.save) },
+            None => { panic!("Emulator not initialized."); }
+        }
+        self.save_sync.mark_synced(SyncKind::Save, &self.save);
+    }
+
+    // Native frontends persist through a `save_sync::SyncStore`, kept across ROM loads.
+    pub fn set_sync_store(&mut self, store: Option<Box<dyn SyncStore>>) {
+        self.save_sync.store = store;
+    }
+
+    // Bytes of the cartridge save changed since the last sync, also passed to the store.
+    pub fn sync_save(&mut self) -> Vec<Diff> {
+        let media = match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.mapper.save_media(),
+            None => { panic!("Emulator not initialized."); }
+        };
+        match media {
+            Some(media) => {
+                media.clear_dirty();
+                self.save_sync.sync(SyncKind::Save, media.data())
+            },
+            None => Vec::new(),
+        }
+    }
+
+    // Bytes of the state buffer changed since the last sync, also passed to the store.
+    pub fn sync_state(&mut self) -> Vec<Diff> {
+        self.save_sync.sync(SyncKind::State, &self.state)
+    }
+
+    // `sync_save` and `sync_state` into the sync buffer (see `save_sync::encode`), return
+    // its length.
+    pub fn export_save_sync(&mut self) -> usize {
+        let diffs = self.sync_save();
+        let len = match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.mapper.save_media().map_or(0, |media| media.data().len()),
+            None => 0,
+        };
+        self.sync = encode(len, &diffs);
+        self.sync.len()
+    }
+
+    pub fn export_state_sync(&mut self) -> usize {
+        let diffs = self.sync_state();
+        self.sync = encode(self.state.len(), &diffs);
+        self.sync.len()
+    }
+
+    pub fn get_sync_pointer(&self) -> *const u8 {
+        self.sync.as_ptr()
+    }
+
+    pub fn set_save_len(&mut self, value: usize) {
+        self.save.resize(value, 0);
+    }
+
+    pub fn get_save_pointer(&mut self) -> *mut u8 {
+        self.save.as_mut_ptr()
+    }
+
+    // Serializes the whole machine into the shared state buffer, returns its length.
+    pub fn save_state(&mut self) -> usize {
+        self.state = self.state_bytes();
+        if let Some(stats) = self.rom_stats_mut() { stats.state_saves += 1; }
+        self.state.len()
+    }
+
+    pub fn load_state(&mut self) -> bool {
+        let state = std::mem::take(&mut self.state);
+        let loaded = self.load_state_bytes(&state);
+        if loaded.is_ok() { self.save_sync.mark_synced(SyncKind::State, &state); }
+        self.state = state;
+        loaded.is_ok()
+    }
+
+    // Like `load_state`, with the reason a state was refused.
+    pub fn load_state_bytes(&mut self, state: &[u8]) -> Result<(), String> {
+        self.restore_state(state, false)?;
+        if let Some(stats) = self.rom_stats_mut() { stats.state_loads += 1; }
+        Ok(())
+    }
+
+    // Loads a state whose chunks don't all pass their checksum, the broken ones are powered
+    // on again instead. Returns the chunks that were.
+    pub fn recover_state_bytes(&mut self, state: &[u8]) -> Result<Vec<Chunk>, String> {
+        let reset = self.restore_state(state, true)?;
+        if let Some(stats) = self.rom_stats_mut() { stats.state_loads += 1; }
+        Ok(reset)
+    }
+
+    // `recover_state_bytes` on the state buffer, returns a mask of the chunks powered on
+    // again (bit n is `Chunk` n) or -1.
+    pub fn recover_state(&mut self) -> i32 {
+        let state = std::mem::take(&mut self.state);
+        let reset = self.recover_state_bytes(&state);
+        self.state = state;
+        reset.map_or(-1, |chunks| chunks.iter().fold(0, |mask, &chunk| mask | 1 << chunk as i32))
+    }
+
+    // CRC-32 of the savestate, for telling whether two machines are in the same state.
+    pub fn state_hash(&mut self) -> u32 {
+        crc32(&self.state_bytes())
+    }
+
+    // On by default: states made with another ROM, or the same one patched, are refused.
+    pub fn set_state_rom_check(&mut self, enabled: bool) {
+        self.state_rom_check = enabled;
+    }
+
+    fn state_bytes(&mut self) -> Vec<u8> {
+        let crc = self.rom_crc.unwrap_or(0);
+        let cpu = match self.cpu.as_mut() {
+            Some(cpu) => cpu,
+            None => { panic!("Emulator not initialized."); }
+        };
+        cpu.bus.catch_up();
+        let mut w = StateWriter::new();
+        w.write_bytes(&STATE_MAGIC);
+        w.write_u8(STATE_VERSION);
+        w.write_u32(crc);
+        cpu.save_state(&mut w);
+        w.into_inner()
+    }
+
+    fn restore_state(&mut self, state: &[u8], recover: bool) -> Result<Vec<Chunk>, String> {
+        let crc = self.rom_crc.unwrap_or(0);
+        let mut r = StateReader::new(state);
+        let mut magic = [0; 4];
+        if r.read_bytes(&mut magic).is_err() || magic != STATE_MAGIC { return Err("Not a savestate.".to_string()) }
+        let version = r.read_u8()?;
+        if !(OLDEST_STATE_VERSION..=STATE_VERSION).contains(&version) {
+            return Err(format!("Savestate version {version} isn't supported, only {OLDEST_STATE_VERSION} to {STATE_VERSION}."))
+        }
+        r.set_version(version);
+        // States before version 9 don't say which ROM they were made with.
+        if version >= 9 {
+            let state_crc = r.read_u32()?;
+            if self.state_rom_check && state_crc != crc {
+                return Err(format!("Savestate was made with ROM CRC {state_crc:08X}, the loaded ROM is {crc:08X} (another game or a patched copy)."))
+            }
+        }
+        let mut reset = Vec::new();
+        let mut body = Vec::new();
+        if version >= 10 {
+            let chunks = read_chunks(r.remaining())?;
+            let broken = chunks.iter().find(|data| !data.valid).map(|data| data.chunk);
+            if let Some(chunk) = broken {
+                if !recover { return Err(format!("Savestate {} data is corrupted.", chunk.name())) }
+                if version != STATE_VERSION { return Err("Only states of the current version can be recovered.".to_string()) }
+            }
+            // Broken chunks are swapped for the same chunk of a machine just powered on.
+            let mut fresh = StateWriter::new();
+            if broken.is_some() { self.new_cpu()?.save_state(&mut fresh); }
+            let fresh = fresh.into_inner();
+            let fresh = if broken.is_some() { read_chunks(&fresh)? } else { Vec::new() };
+            for (i, data) in chunks.iter().enumerate() {
+                if data.valid {
+                    body.extend_from_slice(data.bytes);
+                } else {
+                    body.extend_from_slice(fresh[i].bytes);
+                    reset.push(data.chunk);
+                }
+            }
+            r = StateReader::new(&body);
+            r.set_version(version);
+        }
+        // A state that fails partway (a truncated body, a bad field in a state without checksums)
+        // would leave this machine half restored, so a fresh one reads it first. The running one
+        // keeps its breakpoints, hooks and debug views, which aren't in states.
+        self.new_cpu()?.load_state(&mut r.clone())?;
+        let cpu = match self.cpu.as_mut() {
+            Some(cpu) => cpu,
+            None => { panic!("Emulator not initialized."); }
+        };
+        cpu.load_state(&mut r)?;
+        Ok(reset)
+    }
+
+    // Restoring the resume snapshot of a ROM whenever it loads, see `state::Resume`.
+    pub fn set_auto_resume(&mut self, enabled: bool) {
+        self.resume.auto = enabled;
+    }
+
+    // Snapshot of the running game for the frontend to keep when it closes.
+    pub fn resume_snapshot(&mut self) -> Result<Vec<u8>, String> {
+        let crc = self.rom_crc.ok_or("No ROM loaded.")?;
+        let state = self.state_bytes();
+        let snapshot = Resume::encode(crc, &state);
+        self.resume.insert(crc, state);
+        Ok(snapshot)
+    }
+
+    // Writes the resume snapshot into the state buffer on close, returns its length.
+    pub fn export_resume(&mut self) -> usize {
+        self.state = self.resume_snapshot().unwrap_or_default();
+        self.state.len()
+    }
+
+    pub fn add_resume_snapshot(&mut self, bytes: &[u8]) -> Result<u32, String> {
+        self.resume.load(bytes)
+    }
+
+    // Takes the resume snapshot in the state buffer, before or after loading its ROM.
+    pub fn add_resume(&mut self) -> bool {
+        let state = std::mem::take(&mut self.state);
+        let added = self.add_resume_snapshot(&state).is_ok();
+        self.state = state;
+        added
+    }
+
+    // Whether the loaded ROM has a snapshot to resume, for frontends that ask first.
+    pub fn has_resume(&self) -> bool {
+        self.rom_crc.is_some_and(|crc| self.resume.get(crc).is_some())
+    }
+
+    pub fn resume(&mut self) -> bool {
+        let state = match self.rom_crc.and_then(|crc| self.resume.get(crc)) {
+            Some(state) => state.to_vec(),
+            None => return false,
+        };
+        self.restore_state(&state, false).is_ok()
+    }
+
+    pub fn discard_resume(&mut self) {
+        if let Some(crc) = self.rom_crc { self.resume.remove(crc); }
+    }
+
+    pub fn set_state_len(&mut self, value: usize) {
+        self.state.resize(value, 0);
+    }
+
+    pub fn get_state_pointer(&mut self) -> *mut u8 {
+        self.state.as_mut_ptr()
+    }
+
+    pub fn set_len(&mut self, value: usize) {
+        unsafe { self.rom.set_len(value); }
+    }
+
+    pub fn get_rom_pointer(&self) -> *const u8 {
+        self.rom.as_ptr()
+    }
+
+    // Adds header fixes to the game database, see `header::GameDb` for the format.
+    pub fn load_game_db_text(&mut self, text: &str) -> Result<usize, String> {
+        self.game_db.load(text)
+    }
+
+    // Loads the database text in the shared buffer, returns the number of entries or -1.
+    pub fn load_game_db(&mut self) -> i32 {
+        let text = String::from_utf8_lossy(&self.db_text).into_owned();
+        match self.load_game_db_text(&text) {
+            Ok(count) => count as i32,
+            Err(_) => -1,
+        }
+    }
+
+    fn rom_stats_mut(&mut self) -> Option<&mut RomStats> {
+        self.rom_crc.map(|crc| self.stats.entry(crc))
+    }
+
+    // Play statistics of the loaded ROM, see `stats::Stats`.
+    pub fn rom_stats(&self) -> RomStats {
+        self.rom_crc.and_then(|crc| self.stats.get(crc)).copied().unwrap_or_default()
+    }
+
+    // Merges statistics the frontend kept from earlier runs.
+    pub fn load_stats_text(&mut self, text: &str) -> Result<usize, String> {
+        self.stats.load(text)
+    }
+
+    // Every ROM's statistics, for the frontend to keep with its config.
+    pub fn stats_text(&self) -> String {
+        self.stats.to_text()
+    }
+
+    // Loads statistics from the text buffer, returns the number of ROMs or -1.
+    pub fn load_stats(&mut self) -> i32 {
+        let text = String::from_utf8_lossy(&self.text).into_owned();
+        self.load_stats_text(&text).map_or(-1, |count| count as i32)
+    }
+
+    // Writes every ROM's statistics into the text buffer, returns its length.
+    pub fn export_stats(&mut self) -> usize {
+        self.text = self.stats_text().into_bytes();
+        self.text.len()
+    }
+
+    pub fn play_seconds(&self) -> u64 {
+        self.rom_stats().play_seconds()
+    }
+
+    // Adds per-game settings, see `settings::SettingsDb` for the format. They apply from
+    // the next power on.
+    pub fn load_settings_text(&mut self, text: &str) -> Result<usize, String> {
+        self.settings.load(text)
+    }
+
+    // Loads the settings text in the shared buffer, returns the number of entries or -1.
+    pub fn load_settings(&mut self) -> i32 {
+        let text = String::from_utf8_lossy(&self.settings_text).into_owned();
+        self.load_settings_text(&text).map_or(-1, |count| count as i32)
+    }
+
+    pub fn set_settings_len(&mut self, value: usize) {
+        self.settings_text.resize(value, 0);
+    }
+
+    pub fn get_settings_pointer(&mut self) -> *mut u8 {
+        self.settings_text.as_mut_ptr()
+    }
+
+    pub fn set_game_db_len(&mut self, value: usize) {
+        self.db_text.resize(value, 0);
+    }
+
+    pub fn get_game_db_pointer(&mut self) -> *mut u8 {
+        self.db_text.as_mut_ptr()
+    }
+
+    // The loaded ROM with an NES 2.0 header, corrected from the game database if it's in there.
+    // Works on ROMs that failed to load too.
+    pub fn repaired_rom(&self) -> Result<Vec<u8>, String> {
+        header::repair(&self.rom, &self.game_db)
+    }
+
+    // Copies the repaired ROM into the shared buffer, returns its length (0 if it isn't a NES file).
+    pub fn repair_rom(&mut self) -> usize {
+        self.repaired = self.repaired_rom().unwrap_or_default();
+        self.repaired.len()
+    }
+
+    pub fn get_repaired_pointer(&self) -> *const u8 {
+        self.repaired.as_ptr()
+    }
+
+    // Changes a byte of the ROM file in place, the running game sees it right away.
+    pub fn edit_rom(&mut self, offset: usize, value: u8) -> Result<(), String> {
+        if offset < 16 { return Err("The header can't be edited while running.".to_string()) }
+        match self.rom.get_mut(offset) {
+            Some(byte) => *byte = value,
+            None => return Err("Offset is past the end of the ROM.".to_string()),
+        }
+        self.patch.set(offset, value);
+        Ok(())
+    }
+
+    // Edits the PRG-ROM byte currently mapped at a CPU address.
+    pub fn edit_prg(&mut self, addr: u16, value: u8) -> Result<(), String> {
+        let start = Header::parse(&self.rom)?.prg_rom_start();
+        let offset = match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.mapper.prg_rom_address(addr),
+            None => { panic!("Emulator not initialized."); }
+        };
+        match offset {
+            Some(offset) => self.edit_rom(start + offset, value),
+            None => Err("Address isn't mapped to PRG-ROM.".to_string()),
+        }
+    }
+
+    // Edits the CHR byte currently mapped at a PPU address. CHR-RAM is written directly
+    // and isn't part of the patch.
+    pub fn edit_chr(&mut self, addr: u16, value: u8) -> Result<(), String> {
+        let start = Header::parse(&self.rom)?.chr_rom_start();
+        let addr = addr & 0x1FFF;
+        let offset = match self.cpu.as_mut() {
+            Some(cpu) => match cpu.bus.mapper.chr_rom_address(addr) {
+                Some(offset) => offset,
+                None => { cpu.bus.mapper.write_chr(addr, value); return Ok(()) }
+            },
+            None => { panic!("Emulator not initialized."); }
+        };
+        self.edit_rom(start + offset, value)
+    }
+
+    // Every edit since the ROM was loaded as an IPS patch.
+    pub fn patch_ips(&self) -> Result<Vec<u8>, String> {
+        self.patch.to_ips(&self.rom)
+    }
+
+    // Copies the IPS patch into the patch buffer, returns its length (0 if it can't be made).
+    pub fn export_patch(&mut self) -> usize {
+        self.patch_file = self.patch_ips().unwrap_or_default();
+        self.patch_file.len()
+    }
+
+    pub fn get_patch_pointer(&self) -> *const u8 {
+        self.patch_file.as_ptr()
+    }
+
+    // Sets the character table used by the text functions, see `text::Table` for the format.
+    pub fn load_table_text(&mut self, text: &str) -> Result<usize, String> {
+        let table = Table::parse(text)?;
+        let len = table.len();
+        self.table = Some(table);
+        Ok(len)
+    }
+
+    // `len` bytes of CPU memory from `addr` decoded through the table, read without side effects.
+    pub fn read_text(&self, addr: u16, len: usize) -> Result<String, String> {
+        let table = self.table.as_ref().ok_or("No table loaded.".to_string())?;
+        let bytes: Vec<u8> = match self.cpu.as_ref() {
+            Some(cpu) => (0..len).map(|i| cpu.bus.debug_read(addr.wrapping_add(i as u16))).collect(),
+            None => { panic!("Emulator not initialized."); }
+        };
+        Ok(table.decode(&bytes))
+    }
+
+    // PRG-ROM offsets (from the start of PRG-ROM, not the file) where `text` is stored.
+    pub fn find_text(&self, text: &str) -> Result<Vec<usize>, String> {
+        let table = self.table.as_ref().ok_or("No table loaded.".to_string())?;
+        let header = Header::parse(&self.rom)?;
+        let prg = self.rom.get(header.prg_rom_start()..header.chr_rom_start()).unwrap_or_default();
+        Ok(table.search(prg, text))
+    }
+
+    pub fn set_text_len(&mut self, value: usize) {
+        self.text.resize(value, 0);
+    }
+
+    pub fn get_text_pointer(&mut self) -> *mut u8 {
+        self.text.as_mut_ptr()
+    }
+
+    // Loads the table in the text buffer, returns the number of entries or -1.
+    pub fn load_table(&mut self) -> i32 {
+        let text = String::from_utf8_lossy(&self.text).into_owned();
+        self.load_table_text(&text).map_or(-1, |len| len as i32)
+    }
+
+    // Decodes into the text buffer as UTF-8, returns its length.
+    pub fn extract_text(&mut self, addr: u16, len: usize) -> usize {
+        self.text = self.read_text(addr, len).unwrap_or_default().into_bytes();
+        self.text.len()
+    }
+
+    // Searches PRG-ROM for the text in the text buffer, returns the number of matches.
+    pub fn search_text(&mut self) -> usize {
+        let text = String::from_utf8_lossy(&self.text).into_owned();
+        self.text_matches = self.find_text(&text).unwrap_or_default().into_iter().map(|offset| offset as u32).collect();
+        self.text_matches.len()
+    }
+
+    pub fn get_text_matches_pointer(&self) -> *const u32 {
+        self.text_matches.as_ptr()
+    }
+
+    pub fn toggle_button(&mut self, value: u8) {
+        self.toggle_port_input(0, value as u32);
+    }
+
+    pub fn toggle_button2(&mut self, value: u8) {
+        self.toggle_port_input(1, value as u32);
+    }
+
+    fn toggle_port_input(&mut self, port: usize, value: u32) {
+        match self.cpu.as_mut() {
+            Some(cpu) => {
+                let device = &mut cpu.bus.ports[port];
+                device.set_input(device.input() ^ value);
+            },
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Plugs a device (an input::DeviceKind) into controller port 0, 1 or the expansion
+    // port (2), false if either is unknown.
+    pub fn set_port_device(&mut self, port: usize, kind: u8) -> bool {
+        let cpu = match self.cpu.as_mut() {
+            Some(cpu) => cpu,
+            None => { panic!("Emulator not initialized."); }
+        };
+        match (cpu.bus.ports.get_mut(port), DeviceKind::from_u8(kind)) {
+            (Some(device), Ok(kind)) => { *device = new_device(kind); true },
+            _ => false,
+        }
+    }
+
+    pub fn set_port_input(&mut self, port: usize, value: u32) {
+        match self.cpu.as_mut() {
+            Some(cpu) => if let Some(device) = cpu.bus.ports.get_mut(port) { device.set_input(value) },
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Input macros, see `input::Macros`. A macro advances one input per frame.
+    pub fn define_macro(&mut self, name: &str, inputs: &[u32]) {
+        self.macros.define(name, inputs);
+    }
+
+    pub fn load_macros_text(&mut self, text: &str) -> Result<usize, String> {
+        self.macros.load(text)
+    }
+
+    pub fn play_macro(&mut self, name: &str, port: usize) -> bool {
+        self.macros.play(name, port)
+    }
+
+    pub fn stop_macros(&mut self) {
+        match self.cpu.as_mut() {
+            Some(cpu) => self.macros.stop(&mut cpu.bus.ports),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Loads macro definitions from the text buffer, returns how many or -1.
+    pub fn load_macros(&mut self) -> i32 {
+        let text = String::from_utf8_lossy(&self.text).into_owned();
+        self.load_macros_text(&text).map_or(-1, |count| count as i32)
+    }
+
+    // Plays the macro named in the text buffer.
+    pub fn play_macro_named(&mut self, port: usize) -> bool {
+        let name = String::from_utf8_lossy(&self.text).into_owned();
+        self.play_macro(name.trim(), port)
+    }
+
+    // Family BASIC keyboard key (see input::Keyboard for the numbering).
+    pub fn set_key(&mut self, key: u8, pressed: bool) {
+        self.set_port_input(2, key as u32 | (pressed as u32) << 8);
+    }
+
+    // 0 stops, 1 plays the tape buffer, 2 records into it. Ignored without a data recorder.
+    pub fn set_tape_mode(&mut self, mode: u8) {
+        let cpu = match self.cpu.as_mut() {
+            Some(cpu) => cpu,
+            None => { panic!("Emulator not initialized."); }
+        };
+        let recorder = match cpu.bus.ports[2].data_recorder() {
+            Some(recorder) => recorder,
+            None => return,
+        };
+        if let Ok(mode) = TapeMode::from_u8(mode) {
+            if mode == TapeMode::Play { recorder.load(&self.tape); }
+            recorder.set_mode(mode);
+        }
+    }
+
+    // Copies the recorded tape into the shared buffer, returns its length.
+    pub fn export_tape(&mut self) -> usize {
+        let cpu = match self.cpu.as_mut() {
+            Some(cpu) => cpu,
+            None => { panic!("Emulator not initialized."); }
+        };
+        self.tape.clear();
+        if let Some(recorder) = cpu.bus.ports[2].data_recorder() {
+            self.tape.extend_from_slice(recorder.tape());
+        }
+        self.tape.len()
+    }
+
+    pub fn set_tape_len(&mut self, value: usize) {
+        self.tape.resize(value, 0);
+    }
+
+    pub fn get_tape_pointer(&mut self) -> *mut u8 {
+        self.tape.as_mut_ptr()
+    }
+
+    // Knob position 0-255 for a Vaus paddle plugged into `port`.
+    pub fn set_paddle(&mut self, port: usize, position: u8, fire: bool) {
+        self.set_port_input(port, position as u32 | (fire as u32) << 8);
+    }
+
+    // Level (0-255) on the Famicom player 2 microphone, 0 when nobody is talking.
+    pub fn set_microphone(&mut self, level: u8) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.microphone.level = level,
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.reset_cpu();
+        if let Some(stats) = self.rom_stats_mut() { stats.resets += 1; }
+    }
+
+    fn reset_cpu(&mut self) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.reset(),
+            None => { panic!("Emulator not initialized."); }
+        };
+    }
+
+    pub fn get_frame_pointer(&self) -> *const u32 {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.frame.get_pointer(),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Frames handed over by value instead of read through `get_frame_pointer`, see
+    // `frame::Frame::pool`. Kept across ROM loads.
+    pub fn set_frame_pool(&mut self, enabled: bool) {
+        self.frame_pool = enabled;
+        if let Some(cpu) = self.cpu.as_mut() { cpu.bus.ppu.frame.pool = enabled; }
+    }
+
+    // The last completed frame, once, None if it was taken or nothing completed since.
+    pub fn take_frame(&mut self) -> Option<FrameBuffer> {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.ppu.frame.take_completed(),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Hands a taken frame back so the next one is drawn into it instead of a new buffer.
+    pub fn recycle_frame(&mut self, frame: FrameBuffer) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.ppu.frame.recycle(frame),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // The last completed frame in place, null when there is none. Stays valid until the
+    // next frame completes.
+    pub fn get_completed_frame_pointer(&self) -> *const u32 {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.frame.completed().map_or(std::ptr::null(), |frame| frame.as_ptr()),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn step(&mut self) { 
+        self.advance(false);
+    }
+
+    // Runs like `step`, or only up to the end of the frame in progress, then updates
+    // everything that watches frames go by.
+    fn advance(&mut self, single_frame: bool) {
+        let (frames, region) = match self.cpu.as_mut() {
+            Some(cpu) => {
+                let macros = &mut self.macros;
+                if macros.is_playing() { macros.start(&mut cpu.bus.ports); }
+                let on_frame = |bus: &mut BUS| if macros.is_playing() { macros.apply(&mut bus.ports) };
+                let start = cpu.bus.ppu.frame_count();
+                if single_frame { cpu.run_frame(on_frame) } else { cpu.run(on_frame) }
+                (cpu.bus.ppu.frame_count().wrapping_sub(start), cpu.bus.clock.region)
+            },
+            None => { panic!("Emulator not initialized."); }
+        };
+        if let Some(stats) = self.rom_stats_mut() { stats.add_frames(frames as u64, region); }
+        self.frame_timer.add_frames(frames);
+        if frames > 0 && self.watchdog.is_enabled() {
+            if let Some(cpu) = self.cpu.as_mut() {
+                let registers = cpu.registers();
+                let interrupts_off = registers.p & 0x04 != 0 && !cpu.bus.ppu.nmi_enabled();
+                if self.watchdog.frame(registers.pc, interrupts_off, std::mem::take(&mut cpu.bus.io_accessed)) {
+                    cpu.bus.events.push(Event::Hang);
+                }
+            }
+        }
+        if frames > 0 && (self.burst.is_running() || self.replay.is_enabled()) {
+            if let Some(cpu) = self.cpu.as_mut() {
+                let frame = &cpu.bus.ppu.frame;
+                let pixels = frame.completed().unwrap_or(frame.pixels());
+                if self.replay.is_enabled() { self.replay.frame(pixels); }
+                if self.burst.frame(pixels) { cpu.bus.events.push(Event::BurstDone); }
+            }
+        }
+        let dirty = self.is_save_dirty();
+        if dirty && !self.save_dirty {
+            if let Some(cpu) = self.cpu.as_mut() { cpu.bus.events.push(Event::SaveDirty); }
+        }
+        self.save_dirty = dirty;
+        if !self.rules.is_empty() {
+            if let Some(cpu) = self.cpu.as_mut() {
+                let bus = &cpu.bus;
+                for _ in 0..self.rules.evaluate(|addr| bus.debug_read(addr)) {
+                    cpu.bus.events.push(Event::RuleTriggered);
+                }
+            }
+        }
+        if self.practice.failure.is_some() {
+            let failed = match self.cpu.as_ref() {
+                Some(cpu) => self.practice.check(|addr| cpu.bus.debug_read(addr)),
+                None => false,
+            };
+            if failed { self.retry(); }
+        }
+    }
+
+    // Practice mode: marks the current state as the point `retry` goes back to and
+    // starts counting attempts from 0.
+    pub fn set_savepoint(&mut self) {
+        let state = self.state_bytes();
+        self.practice.set_savepoint(state);
+    }
+
+    // Goes back to the savepoint and counts an attempt, false without one.
+    pub fn retry(&mut self) -> bool {
+        let Some(state) = self.practice.savepoint.take() else { return false };
+        let loaded = self.restore_state(&state, false).is_ok();
+        self.practice.savepoint = Some(state);
+        if loaded {
+            self.practice.retry();
+            if let Some(cpu) = self.cpu.as_mut() { cpu.bus.events.push(Event::PracticeRetry); }
+        }
+        loaded
+    }
+
+    // RAM condition (see `condition::Condition`) checked after every step, retries as
+    // soon as it becomes true. Empty text turns it off.
+    pub fn set_failure_condition(&mut self, condition: &str) -> Result<(), String> {
+        self.practice.failure = if condition.trim().is_empty() { None } else { Some(Condition::parse(condition)?) };
+        Ok(())
+    }
+
+    // Failure condition from the text buffer.
+    pub fn set_failure_condition_text(&mut self) -> bool {
+        let text = String::from_utf8_lossy(&self.text).into_owned();
+        self.set_failure_condition(&text).is_ok()
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.practice.attempts
+    }
+
+    // Memory rules checked after every step, see `condition::Rules` for the syntax.
+    pub fn add_rule(&mut self, id: u32, rule: &str) -> Result<(), String> {
+        self.rules.add(id, rule)
+    }
+
+    pub fn remove_rule(&mut self, id: u32) {
+        self.rules.remove(id);
+    }
+
+    pub fn reset_rule(&mut self, id: u32) {
+        self.rules.reset(id);
+    }
+
+    // Next triggered rule id, oldest first.
+    pub fn poll_rule(&mut self) -> Option<u32> {
+        self.rules.pop_triggered()
+    }
+
+    // Adds the rule in the text buffer.
+    pub fn add_rule_text(&mut self, id: u32) -> bool {
+        let text = String::from_utf8_lossy(&self.text).into_owned();
+        self.add_rule(id, &text).is_ok()
+    }
+
+    // Runs until the PPU completes a frame and reports on it.
+    pub fn run_frame(&mut self) -> FrameReport {
+        let (cycles, interrupts) = match self.cpu.as_mut() {
+            Some(cpu) => {
+                cpu.bus.ppu.sprite_hit_line = None;
+                (cpu.registers().cycles, cpu.interrupts)
+            },
+            None => { panic!("Emulator not initialized."); }
+        };
+        self.advance(true);
+        match self.cpu.as_ref() {
+            Some(cpu) => {
+                let pixels = cpu.bus.ppu.frame.pixels().iter().flat_map(|pixel| pixel.to_le_bytes());
+                FrameReport {
+                    cycles: cpu.registers().cycles.wrapping_sub(cycles),
+                    nmis: cpu.interrupts.0.wrapping_sub(interrupts.0),
+                    irqs: cpu.interrupts.1.wrapping_sub(interrupts.1),
+                    sprite_hit_line: cpu.bus.ppu.sprite_hit_line,
+                    frame_hash: crc32_iter(pixels),
+                }
+            },
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Runs a frame and fills the report buffer with cycles, NMIs, IRQs, sprite 0 hit
+    // line (0xFFFFFFFF for none) and frame hash.
+    pub fn run_frame_report(&mut self) {
+        let report = self.run_frame();
+        self.frame_report = [
+            report.cycles as u32,
+            report.nmis,
+            report.irqs,
+            report.sprite_hit_line.map_or(u32::MAX, |line| line as u32),
+            report.frame_hash,
+        ];
+    }
+
+    pub fn get_frame_report_pointer(&self) -> *const u32 {
+        self.frame_report.as_ptr()
+    }
+
+    // Host timestamps (ms) taken before and after running frames, see `metrics::FrameTimer`.
+    pub fn record_frame_time(&mut self, start_ms: f64, end_ms: f64) {
+        self.frame_timer.record(start_ms, end_ms);
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        match self.cpu.as_ref() {
+            Some(cpu) => self.frame_timer.metrics(cpu.bus.clock.region),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Fills the metrics buffer with fps, host ms per frame and load.
+    pub fn update_metrics(&mut self) {
+        let metrics = self.metrics();
+        self.metrics = [metrics.fps, metrics.host_ms, metrics.load];
+    }
+
+    pub fn get_metrics_pointer(&self) -> *const f32 {
+        self.metrics.as_ptr()
+    }
+
+    // Whether the last completed frame never read the controllers.
+    pub fn is_lag_frame(&self) -> bool {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.lag_frame,
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Lag frames since power on or the last reset of the counter, kept in savestates.
+    pub fn lag_count(&self) -> u32 {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.lag_count,
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn reset_lag_count(&mut self) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.lag_count = 0,
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn subscribe_events(&mut self, mask: u32) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.events.subscribe(mask),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // 0 when the queue is empty, otherwise an Event.
+    pub fn poll_event(&mut self) -> u8 {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.events.pop().map_or(0, |e| e as u8),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, addr: u16) {
+        match self.cpu.as_mut() {
+            Some(cpu) => if !cpu.breakpoints.contains(&addr) { cpu.breakpoints.push(addr) },
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn remove_breakpoint(&mut self, addr: u16) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.breakpoints.retain(|&a| a != addr),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Only breaks with PRG bank `bank` (16KB) mapped at `addr`.
+    pub fn add_bank_breakpoint(&mut self, bank: usize, addr: u16) {
+        match self.cpu.as_mut() {
+            Some(cpu) => if !cpu.bank_breakpoints.contains(&(bank, addr)) { cpu.bank_breakpoints.push((bank, addr)) },
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn remove_bank_breakpoint(&mut self, bank: usize, addr: u16) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bank_breakpoints.retain(|&b| b != (bank, addr)),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn get_pc(&self) -> u16 {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.pc(),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn registers(&self) -> Registers {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.registers(),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn stack_view(&self) -> StackView {
+        match self.cpu.as_ref() {
+            Some(cpu) => StackView::read(cpu.registers().s, |addr| cpu.bus.debug_read(addr)),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn zero_page_view(&self) -> Vec<ZeroPageEntry> {
+        match self.cpu.as_ref() {
+            Some(cpu) => zero_page(|addr| cpu.bus.debug_read(addr)),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Fills the view buffer with 256 words, see `StackView::to_words`.
+    pub fn export_stack_view(&mut self) -> usize {
+        self.view = self.stack_view().to_words();
+        self.view.len()
+    }
+
+    // Fills the view buffer with 256 words: the byte, and the word starting there << 8.
+    pub fn export_zero_page_view(&mut self) -> usize {
+        self.view = self.zero_page_view().iter().map(|entry| entry.value as u32 | (entry.word as u32) << 8).collect();
+        self.view.len()
+    }
+
+    pub fn get_view_pointer(&self) -> *const u32 {
+        self.view.as_ptr()
+    }
+
+    pub fn at_breakpoint(&self) -> bool {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.at_breakpoint(),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn step_instruction(&mut self) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.step_instruction(),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Runs `hook` at the end of every frame, it stays across ROM loads. See `achievements`.
+    #[cfg(feature = "rcheevos")]
+    pub fn set_frame_hook(&mut self, hook: Option<crate::achievements::FrameHook>) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.frame_hook = hook,
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Side-effect free reads and writes, see `BUS::debug_read` and `BUS::debug_write`.
+    pub fn debug_read(&self, addr: u16) -> u8 {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.debug_read(addr),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn debug_write(&mut self, addr: u16, value: u8) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.debug_write(addr, value),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // PPU memory ($0000-$3FFF) and OAM for tile editors, see `ppu::debug`.
+    pub fn debug_read_vram(&self, addr: u16) -> u8 {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.peek_vram(addr, self.rom.as_ptr(), &cpu.bus.mapper),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Pattern writes go through `edit_chr`, so CHR ROM edits end up in the patch.
+    pub fn debug_write_vram(&mut self, addr: u16, value: u8) -> Result<(), String> {
+        if addr & 0x3FFF < 0x2000 { return self.edit_chr(addr, value) }
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.ppu.poke_vram(addr, value, &mut cpu.bus.mapper),
+            None => { panic!("Emulator not initialized."); }
+        }
+        Ok(())
+    }
+
+    pub fn debug_read_oam(&self, index: u8) -> u8 {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.oam()[index as usize],
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn debug_write_oam(&mut self, index: u8, value: u8) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.ppu.oam_mut()[index as usize] = value,
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Keeps an instruction trace of the last `frames` frames for crash reports, 0 turns it off.
+    pub fn set_trace_frames(&mut self, frames: usize) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.trace.set_frames(frames),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Bundles a savestate, the trace and the settings into a zip in the report buffer,
+    // returns its length.
+    pub fn crash_report(&mut self) -> usize {
+        self.save_state();
+        let cpu = match self.cpu.as_ref() {
+            Some(cpu) => cpu,
+            None => { panic!("Emulator not initialized."); }
+        };
+        let mut config = String::new();
+        let (region, source) = self.detect_region();
+        let _ = writeln!(config, "region: {} ({source:?})", if region == Region::Pal { "pal" } else { "ntsc" });
+        let _ = writeln!(config, "accuracy: {:05b}", self.accuracy.bits());
+        let _ = writeln!(config, "mmc3 a12 filter: {}", self.a12_filter);
+        let _ = writeln!(config, "mmc3 old irq: {}", self.old_irq);
+        let _ = writeln!(config, "power on seed: {:?}", self.seed);
+        let _ = writeln!(config, "ppu power on: {:?}", self.ppu_power_on);
+        let _ = writeln!(config, "alignment: {:?}", self.alignment);
+        let _ = writeln!(config, "mapper: {}", cpu.bus.mapper);
+        let _ = writeln!(config, "prg-ram: {:?}", cpu.bus.mapper.wram_protection());
+        for (i, port) in cpu.bus.ports.iter().enumerate() {
+            let _ = writeln!(config, "port {i}: {}", port.kind() as u8);
+        }
+        self.report = report::bundle(&self.state, &cpu.trace.dump(), &self.rom, &config);
+        self.report.len()
+    }
+
+    pub fn get_report_pointer(&self) -> *const u8 {
+        self.report.as_ptr()
+    }
+
+    pub fn frame(&self) -> &[u32] {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.frame.pixels(),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Integer-only output for targets without an FPU.
+    pub fn scale_frame(&self, out: &mut [u32], width: usize, height: usize) {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.frame.scale_into(out, width, height),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn frame_rgb565(&self, out: &mut [u16]) {
+        for (pixel, &color) in out.iter_mut().zip(self.frame()) {
+            *pixel = Frame::to_rgb565(color);
+        }
+    }
+
+    // Straight into an embedded-graphics display:
+    //   display.draw_iter(emulator.frame_pixels().map(|(x, y, c)|
+    //       Pixel(Point::new(x as i32, y as i32), Rgb565::from(RawU16::new(c)))))
+    pub fn frame_pixels(&self) -> impl Iterator<Item = (u16, u16, u16)> + '_ {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.frame.rgb565_pixels(),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn frame_png(&self) -> Vec<u8> {
+        match self.cpu.as_ref() {
+            Some(cpu) => png::encode(Frame::WIDTH, Frame::HEIGHT, cpu.bus.ppu.frame.pixels()),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Number of pixels in the current frame that differ from a PNG baseline.
+    pub fn compare_frame_png(&self, baseline: &[u8]) -> Result<usize, String> {
+        let (width, height, pixels) = png::decode(baseline)?;
+        if (width, height) != (Frame::WIDTH, Frame::HEIGHT) { return Err("Baseline has the wrong size.".to_string()) }
+        let frame = match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.frame.pixels(),
+            None => { panic!("Emulator not initialized."); }
+        };
+        Ok(frame.iter().zip(pixels.iter()).filter(|(a, b)| a != b).count())
+    }
+
+    // The baseline, the current frame and the current frame dimmed with every differing pixel
+    // in red, side by side as one PNG, with the first scanline that differs.
+    pub fn frame_diff_png(&self, baseline: &[u8]) -> Result<(Vec<u8>, Option<usize>), String> {
+        let (width, height, pixels) = png::decode(baseline)?;
+        if (width, height) != (Frame::WIDTH, Frame::HEIGHT) { return Err("Baseline has the wrong size.".to_string()) }
+        let frame = match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.frame.pixels(),
+            None => { panic!("Emulator not initialized."); }
+        };
+        let mut diff = Vec::with_capacity(3 * Frame::WIDTH * Frame::HEIGHT);
+        let mut first_line = None;
+        for (y, (expected, actual)) in pixels.chunks(Frame::WIDTH).zip(frame.chunks(Frame::WIDTH)).enumerate() {
+            diff.extend_from_slice(expected);
+            diff.extend_from_slice(actual);
+            diff.extend(expected.iter().zip(actual).map(|(a, b)| if a == b { (b >> 2) & 0x3F3F3F00 | 0xFF } else { DIFF_COLOR }));
+            if expected != actual { first_line.get_or_insert(y); }
+        }
+        Ok((png::encode(3 * Frame::WIDTH, Frame::HEIGHT, &diff), first_line))
+    }
+
+    // Replaces the baseline PNG in the image buffer with `frame_diff_png`'s, returns its
+    // length or 0 if the baseline can't be read.
+    pub fn export_frame_diff(&mut self) -> usize {
+        let (image, first_line) = self.frame_diff_png(&self.image).unwrap_or_default();
+        self.image = image;
+        self.diff_line = first_line;
+        self.image.len()
+    }
+
+    // First differing scanline of the last `export_frame_diff`, -1 if none.
+    pub fn get_diff_line(&self) -> i32 {
+        self.diff_line.map_or(-1, |line| line as i32)
+    }
+
+    // Encodes the current frame as PNG into the image buffer, returns its length.
+    pub fn screenshot(&mut self) -> usize {
+        self.image = self.frame_png();
+        self.image.len()
+    }
+
+    // Keeps every `every`th frame from the next one on until `count` are kept (at most
+    // `MAX_BURST_FRAMES`), then raises Event::BurstDone. Frames kept by an earlier
+    // burst are dropped.
+    pub fn start_burst(&mut self, every: u32, count: u32) {
+        self.burst.start(every, count);
+    }
+
+    pub fn stop_burst(&mut self) {
+        self.burst.stop();
+    }
+
+    pub fn burst_len(&self) -> usize {
+        self.burst.frames.len()
+    }
+
+    pub fn burst_frame_png(&self, index: usize) -> Option<Vec<u8>> {
+        self.burst.frames.get(index).map(|pixels| png::encode(Frame::WIDTH, Frame::HEIGHT, pixels))
+    }
+
+    // All kept frames as one looping APNG, timed at the region's frame rate.
+    pub fn burst_apng(&self) -> Vec<u8> {
+        let region = match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.clock.region,
+            None => { panic!("Emulator not initialized."); }
+        };
+        let delay = self.burst.delay(if region == Region::Pal { PAL_FRAME_US } else { NTSC_FRAME_US });
+        png::encode_apng(Frame::WIDTH, Frame::HEIGHT, &self.burst.frames, delay)
+    }
+
+    // Kept frame `index` as PNG into the image buffer, returns its length (0 past the end).
+    pub fn export_burst_frame(&mut self, index: usize) -> usize {
+        self.image = self.burst_frame_png(index).unwrap_or_default();
+        self.image.len()
+    }
+
+    pub fn export_burst_apng(&mut self) -> usize {
+        self.image = self.burst_apng();
+        self.image.len()
+    }
+
+    // Keeps the last `seconds` of play (at most `MAX_REPLAY_SECONDS`, 0 turns it off) for
+    // `replay_gif`. Kept across ROM loads.
+    pub fn set_replay(&mut self, seconds: u32) {
+        let seconds = seconds.min(MAX_REPLAY_SECONDS) as u64;
+        self.replay.set_capacity((seconds * 1_000_000 / NTSC_FRAME_US / REPLAY_FRAME_STEP) as usize);
+    }
+
+    // The last `seconds` of the replay as a looping half-size GIF.
+    pub fn replay_gif(&self, seconds: u32) -> Vec<u8> {
+        let frame_us = match self.cpu.as_ref() {
+            Some(cpu) if cpu.bus.clock.region == Region::Pal => PAL_FRAME_US,
+            Some(_) => NTSC_FRAME_US,
+            None => { panic!("Emulator not initialized."); }
+        };
+        let count = (seconds as u64 * 1_000_000 / frame_us / REPLAY_FRAME_STEP) as usize;
+        let skip = self.replay.frames.len().saturating_sub(count);
+        // GIF delays are in hundredths, rounding each frame's end keeps the total right.
+        let step_us = frame_us * REPLAY_FRAME_STEP;
+        let end = |i: u64| ((i * step_us + 5_000) / 10_000) as u16;
+        let frames: Vec<GifFrame> = self.replay.frames.iter().skip(skip).zip(0..).map(|(frame, i)| GifFrame {
+            pixels: &frame.pixels,
+            palette: &frame.palette,
+            delay: end(i + 1) - end(i),
+        }).collect();
+        gif::encode(REPLAY_WIDTH, REPLAY_HEIGHT, &frames)
+    }
+
+    pub fn export_replay_gif(&mut self, seconds: u32) -> usize {
+        self.image = self.replay_gif(seconds);
+        self.image.len()
+    }
+
+    // Both pattern tables as a .chr file.
+    pub fn chr_dump(&self) -> Vec<u8> {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.chr(cpu.bus.rom, &cpu.bus.mapper),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // A 128x128 PNG of pattern table 0/1 in palette 0-7.
+    pub fn pattern_table_png(&self, table: usize, palette: usize) -> Vec<u8> {
+        match self.cpu.as_ref() {
+            Some(cpu) => {
+                let pixels = cpu.bus.ppu.pattern_table_pixels(table, palette, cpu.bus.rom, &cpu.bus.mapper);
+                png::encode(PPU::PATTERN_TABLE_SIZE, PPU::PATTERN_TABLE_SIZE, &pixels)
+            },
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // A 256x240 PNG of nametable 0-3.
+    pub fn nametable_png(&self, nametable: usize) -> Vec<u8> {
+        match self.cpu.as_ref() {
+            Some(cpu) => {
+                let pixels = cpu.bus.ppu.nametable_pixels(nametable, cpu.bus.rom, &cpu.bus.mapper);
+                png::encode(Frame::WIDTH, Frame::HEIGHT, &pixels)
+            },
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // The export_* functions fill the image buffer and return its length.
+    pub fn export_chr(&mut self) -> usize {
+        self.image = self.chr_dump();
+        self.image.len()
+    }
+
+    pub fn export_pattern_table(&mut self, table: usize, palette: usize) -> usize {
+        self.image = self.pattern_table_png(table, palette);
+        self.image.len()
+    }
+
+    pub fn export_nametable(&mut self, nametable: usize) -> usize {
+        self.image = self.nametable_png(nametable);
+        self.image.len()
+    }
+
+    // Compares the current frame with the PNG in the image buffer, -1 if it can't be read.
+    pub fn compare_frame(&mut self) -> i32 {
+        self.compare_frame_png(&self.image).map_or(-1, |diff| diff as i32)
+    }
+
+    pub fn set_image_len(&mut self, value: usize) {
+        self.image.resize(value, 0);
+    }
+
+    pub fn get_image_pointer(&mut self) -> *mut u8 {
+        self.image.as_mut_ptr()
+    }
+
+    // Fills the rect buffer with x, y, width, height quads of what changed in the last
+    // frame, returns the number of rects.
+    pub fn dirty_rects(&mut self) -> usize {
+        let rects = match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.frame.dirty_rects(),
+            None => { panic!("Emulator not initialized."); }
+        };
+        self.rects = rects.concat();
+        rects.len()
+    }
+
+    pub fn get_rects_pointer(&self) -> *const u16 {
+        self.rects.as_ptr()
+    }
+
+    // Draws every sprite on a scanline instead of only the first 8, the overflow flag and
+    // savestates still behave like hardware.
+    pub fn set_flicker_reduction(&mut self, enabled: bool) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.ppu.sprite_limit = if enabled { 64 } else { SPRITES_PER_SCANLINE },
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Records every PPU register and mapper access of each frame for `rerender_frame`.
+    pub fn set_frame_capture(&mut self, enabled: bool) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.capture.enabled = enabled,
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // The last captured frame drawn again by a fresh PPU from the recorded accesses.
+    pub fn rerender(&self) -> Result<Vec<u32>, String> {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.capture.render(&self.rom, cpu.bus.ppu.accuracy),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Re-renders into the shared buffer (Frame::WIDTH * Frame::HEIGHT pixels).
+    pub fn rerender_frame(&mut self) -> bool {
+        match self.rerender() {
+            Ok(pixels) => { self.rerendered = pixels; true },
+            Err(_) => false,
+        }
+    }
+
+    pub fn get_rerendered_pointer(&self) -> *const u32 {
+        self.rerendered.as_ptr()
+    }
+
+    // Replaces tiles with the hi-res graphics of a Mesen HD pack, `images` returns the files
+    // its <img> lines name. Stays until the next ROM is loaded.
+    pub fn load_hd_pack(&mut self, definition: &str, images: impl FnMut(&str) -> Option<Vec<u8>>) -> Result<(), String> {
+        let header = Header::parse(&self.rom)?;
+        let chr = self.rom.get(header.chr_rom_start()..header.chr_rom_start() + header.chr_rom).unwrap_or_default();
+        let pack = HdPack::load(definition, images, chr)?;
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.ppu.hd_pack = Some(Box::new(pack)),
+            None => { panic!("Emulator not initialized."); }
+        }
+        Ok(())
+    }
+
+    pub fn set_hd_pack_file_len(&mut self, value: usize) {
+        self.hd_file.resize(value, 0);
+    }
+
+    pub fn get_hd_pack_file_pointer(&mut self) -> *mut u8 {
+        self.hd_file.as_mut_ptr()
+    }
+
+    // Queues the shared buffer for `load_hd_pack_files`: hires.txt first, then the images
+    // in the order its <img> lines list them.
+    pub fn add_hd_pack_file(&mut self) {
+        self.hd_files.push(std::mem::take(&mut self.hd_file));
+    }
+
+    // Loads the queued files, returns the pack's scale or 0 if it couldn't be loaded.
+    pub fn load_hd_pack_files(&mut self) -> usize {
+        let mut files = std::mem::take(&mut self.hd_files).into_iter();
+        let definition = String::from_utf8_lossy(&files.next().unwrap_or_default()).into_owned();
+        match self.load_hd_pack(&definition, |_| files.next()) {
+            Ok(()) => self.hd_frame_scale(),
+            Err(_) => 0,
+        }
+    }
+
+    // 0 without an HD pack, the HD frame is Frame::WIDTH * scale by Frame::HEIGHT * scale.
+    pub fn hd_frame_scale(&self) -> usize {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.hd_pack.as_ref().map_or(0, |pack| pack.scale()),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn get_hd_frame_pointer(&self) -> *const u32 {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.hd_pack.as_ref().map_or(std::ptr::null(), |pack| pack.frame().as_ptr()),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Off by default, evaluation stops at the 9th sprite otherwise.
+    pub fn set_sprite_log(&mut self, enabled: bool) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.ppu.sprite_log.enabled = enabled,
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // 240 (selected, dropped) OAM masks, one pair per scanline of the last frame.
+    pub fn get_sprite_log_pointer(&self) -> *const u64 {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.sprite_log.get_pointer(),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Off by default, see `ppu::SplitLog`.
+    pub fn set_split_log(&mut self, enabled: bool) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.ppu.split_log.enabled = enabled,
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Mid-frame scroll and nametable writes of the last frame, in order.
+    pub fn splits(&self) -> &[Split] {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.split_log.last(),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn split_lines(&self) -> Vec<u16> {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.split_log.lines(),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn split_count(&self) -> usize {
+        self.splits().len()
+    }
+
+    // `split_count` entries of three u16: scanline, dot and register | value << 8.
+    pub fn get_split_log_pointer(&self) -> *const u16 {
+        self.splits().as_ptr() as *const u16
+    }
+
+    // Kept across ROM loads, see `ppu::LineHook`.
+    pub fn set_line_hook(&mut self, hook: Option<LineHook>) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.ppu.line_hook = hook,
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Frames a game has to spin with interrupts off and no I/O before Event::Hang, 0 turns
+    // the watchdog off. Kept across ROM loads.
+    pub fn set_watchdog(&mut self, frames: u32) {
+        self.watchdog = Watchdog::new();
+        self.watchdog.frames = frames;
+    }
+
+    // Tints pixels by their source instead of their color, see `ppu::debug_colors`.
+    pub fn set_debug_colors(&mut self, enabled: bool) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.ppu.debug_colors = enabled,
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Grid and nametable lines drawn over the frame, a mask of `ppu::Overlay` flags.
+    pub fn set_overlay(&mut self, overlay: u8) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.ppu.overlay = Overlay::from_bits_truncate(overlay),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Off by default, see `cpu::Timeline`.
+    pub fn set_timeline(&mut self, enabled: bool) {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.timeline.enabled = enabled,
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // NMIs, IRQs and DMAs of the last frame, in order.
+    pub fn timeline(&self) -> &[TimelineEntry] {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.timeline.last(),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn timeline_count(&self) -> usize {
+        self.timeline().len()
+    }
+
+    // `timeline_count` entries of three u32: cycle, scanline | dot << 16 and kind.
+    pub fn get_timeline_pointer(&self) -> *const u32 {
+        self.timeline().as_ptr() as *const u32
+    }
+
+    pub fn get_ppu_position(&self) -> (u16, u16) {
+        match self.cpu.as_ref() {
+            Some(cpu) => cpu.bus.ppu.position(),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    pub fn test_status(&mut self) -> Option<u8> {
+        let bus = match self.cpu.as_mut() {
+            Some(cpu) => &mut cpu.bus,
+            None => { panic!("Emulator not initialized."); }
+        };
+        let signature = [bus.read(0x6001), bus.read(0x6002), bus.read(0x6003)];
+        if signature != TEST_SIGNATURE { return None }
+        Some(bus.read(0x6000))
+    }
+
+    pub fn test_output(&mut self, index: u16) -> u8 {
+        match self.cpu.as_mut() {
+            Some(cpu) => cpu.bus.read(0x6004u16.wrapping_add(index)),
+            None => { panic!("Emulator not initialized."); }
+        }
+    }
+
+    // Runs a test ROM headlessly until it reports a result code, 0 means passed.
+    pub fn run_test_rom(&mut self, max_steps: usize) -> u8 {
+        for _ in 0..max_steps {
+            self.step();
+            match self.test_status() {
+                None | Some(TEST_RUNNING) => (),
+                Some(TEST_NEEDS_RESET) => { self.step(); self.reset(); },
+                Some(code) => return code,
+            }
+        }
+        TEST_TIMEOUT
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::VecDeque;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Event {
+    FrameCompleted = 1,
+    Nmi = 2,
+    MapperIrq = 3,
+    Breakpoint = 4,
+    SaveDirty = 5,
+    LagFrame = 6,
+    PracticeRetry = 7,
+    RuleTriggered = 8,
+    BurstDone = 9,
+    Hang = 10,
+}
+
+// Events are only queued once subscribed to (bit n of the mask for Event n), so a
+// frontend that never polls doesn't grow the queue.
+pub struct EventQueue {
+    mask: u32,
+    queue: VecDeque<Event>,
+}
+
+impl EventQueue {
+    pub fn new() -> Self {
+        EventQueue { mask: 0, queue: VecDeque::new() }
+    }
+
+    pub fn subscribe(&mut self, mask: u32) {
+        self.mask = mask;
+        self.queue.retain(|e| mask & (1 << *e as u32) != 0);
+    }
+
+    pub fn push(&mut self, event: Event) {
+        if self.mask & (1 << event as u32) != 0 {
+            self.queue.push_back(event);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Event> {
+        self.queue.pop_front()
+    }
+}
@@ -0,0 +1,47 @@
+// Screenshot bursts and timelapses: every `every`th completed frame is kept until `count`
+// have been, for short clips of a bug without video tooling.
+pub const MAX_BURST_FRAMES: u32 = 600;
+
+pub struct Burst {
+    every: u32,
+    remaining: u32,
+    skipped: u32, // Frames since the last one kept
+    pub frames: Vec<Vec<u32>>,
+}
+
+impl Burst {
+    pub fn new() -> Self {
+        Burst { every: 1, remaining: 0, skipped: 0, frames: Vec::new() }
+    }
+
+    pub fn start(&mut self, every: u32, count: u32) {
+        self.every = every.max(1);
+        self.remaining = count.min(MAX_BURST_FRAMES);
+        self.skipped = self.every - 1; // The next frame is the first one
+        self.frames.clear();
+    }
+
+    pub fn stop(&mut self) {
+        self.remaining = 0;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.remaining > 0
+    }
+
+    // Seconds between kept frames in ten-thousandths, for APNG delays.
+    pub fn delay(&self, frame_us: u64) -> u16 {
+        (self.every as u64 * frame_us / 100).min(u16::MAX as u64) as u16
+    }
+
+    // Called once per completed frame, true when this one finished the burst.
+    pub fn frame(&mut self, pixels: &[u32]) -> bool {
+        if self.remaining == 0 { return false }
+        self.skipped += 1;
+        if self.skipped < self.every { return false }
+        self.skipped = 0;
+        self.frames.push(pixels.to_vec());
+        self.remaining -= 1;
+        self.remaining == 0
+    }
+}
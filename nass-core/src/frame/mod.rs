@@ -0,0 +1,152 @@
+mod burst;
+mod replay;
+
+pub use burst::Burst;
+pub use replay::{Replay, REPLAY_WIDTH, REPLAY_HEIGHT, MAX_REPLAY_SECONDS, REPLAY_FRAME_STEP};
+
+const TILE_ROWS: usize = Frame::HEIGHT / 8;
+const PIXELS: usize = Frame::WIDTH * Frame::HEIGHT;
+
+pub type FrameBuffer = Box<[u32; PIXELS]>;
+
+pub struct Frame {
+    frame: FrameBuffer,
+    index: usize,
+    // One bit per 8x8 tile (bit n is tile column n) that changed in the frame being drawn,
+    // and in the last completed one.
+    dirty: [u32; TILE_ROWS],
+    last_dirty: [u32; TILE_ROWS],
+    // With the pool on, a completed frame's buffer is set aside for the frontend to take
+    // and drawing goes on in a spare one, so frames are handed over without copying.
+    pub pool: bool,
+    completed: Option<FrameBuffer>,
+    spare: Vec<FrameBuffer>,
+}
+
+impl Frame {
+    pub const WIDTH: usize = 256;
+    pub const HEIGHT: usize = 240;
+
+    pub fn new() -> Frame {
+        Frame { 
+            frame: Frame::buffer(),
+            index: 0,
+            dirty: [0; TILE_ROWS],
+            last_dirty: [!0; TILE_ROWS],
+            pool: false,
+            completed: None,
+            spare: Vec::new(),
+        }
+    }
+
+    // Built on the heap, 240kB is too much for the stack on some targets.
+    fn buffer() -> FrameBuffer {
+        vec![0xFF; PIXELS].into_boxed_slice().try_into().unwrap_or_else(|_| unreachable!())
+    }
+
+    pub fn set_pixel(&mut self, color: u32) {
+        if self.frame[self.index] != color {
+            self.dirty[self.index / Frame::WIDTH / 8] |= 1 << (self.index % Frame::WIDTH / 8);
+        }
+        self.frame[self.index] = color;
+        self.index += 1;
+        if self.index == PIXELS {
+            self.index = 0;
+            self.last_dirty = self.dirty;
+            self.dirty = [0; TILE_ROWS];
+            if self.pool { self.hand_off(); }
+        }
+    }
+
+    // The next frame is drawn over whatever the spare buffer held, so it counts as dirty
+    // everywhere.
+    fn hand_off(&mut self) {
+        let next = self.spare.pop().unwrap_or_else(Frame::buffer);
+        let completed = std::mem::replace(&mut self.frame, next);
+        if let Some(untaken) = self.completed.replace(completed) { self.spare.push(untaken); }
+        self.dirty = [!0; TILE_ROWS];
+    }
+
+    // The last completed frame, once, while the pool is on.
+    pub fn take_completed(&mut self) -> Option<FrameBuffer> {
+        self.completed.take()
+    }
+
+    pub fn completed(&self) -> Option<&[u32]> {
+        self.completed.as_deref().map(|frame| frame.as_slice())
+    }
+
+    // Gives a taken buffer back to be drawn into again.
+    pub fn recycle(&mut self, frame: FrameBuffer) {
+        self.spare.push(frame);
+    }
+
+    // Rectangles (x, y, width, height in pixels) covering the tiles the last completed
+    // frame changed. Runs of tiles in a row are joined, and so are identical runs on
+    // consecutive rows.
+    pub fn dirty_rects(&self) -> Vec<[u16; 4]> {
+        let mut rects: Vec<[u16; 4]> = Vec::new();
+        let mut open: Vec<usize> = Vec::new(); // Rects that reach down to the previous row
+        for (row, &bits) in self.last_dirty.iter().enumerate() {
+            let mut reaching = Vec::new();
+            let mut column = 0;
+            while column < 32 {
+                if bits & (1 << column) == 0 { column += 1; continue }
+                let start = column;
+                while column < 32 && bits & (1 << column) != 0 { column += 1; }
+                let (x, width) = (start as u16 * 8, (column - start) as u16 * 8);
+                match open.iter().find(|&&i| rects[i][0] == x && rects[i][2] == width) {
+                    Some(&i) => { rects[i][3] += 8; reaching.push(i); },
+                    None => { rects.push([x, row as u16 * 8, width, 8]); reaching.push(rects.len() - 1); },
+                }
+            }
+            open = reaching;
+        }
+        rects
+    }
+
+    // Nearest-neighbour resize into `out` (width * height), stepping in 16.16 fixed point.
+    pub fn scale_into(&self, out: &mut [u32], width: usize, height: usize) {
+        if width == 0 || height == 0 { return }
+        let step_x = (Frame::WIDTH << 16) / width;
+        let step_y = (Frame::HEIGHT << 16) / height;
+        for (y, row) in out.chunks_exact_mut(width).take(height).enumerate() {
+            let source = &self.frame[((y * step_y) >> 16) * Frame::WIDTH..][..Frame::WIDTH];
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = source[(x * step_x) >> 16];
+            }
+        }
+    }
+
+    // RR-GG-BB-AA to the RGB565 most small LCDs take.
+    pub fn to_rgb565(color: u32) -> u16 {
+        (((color >> 16) & 0xF800) | ((color >> 13) & 0x07E0) | ((color >> 11) & 0x001F)) as u16
+    }
+
+    // (x, y, RGB565) for every pixel in row order, the shape display drivers take.
+    pub fn rgb565_pixels(&self) -> impl Iterator<Item = (u16, u16, u16)> + '_ {
+        self.frame.iter().enumerate().map(|(i, &color)| {
+            ((i % Frame::WIDTH) as u16, (i / Frame::WIDTH) as u16, Frame::to_rgb565(color))
+        })
+    }
+
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn set_index(&mut self, index: usize) {
+        self.index = index % PIXELS;
+    }
+
+    pub fn pixels(&self) -> &[u32] {
+        self.frame.as_slice()
+    }
+
+    pub fn row(&self, y: usize) -> &[u32] {
+        &self.frame[y * Frame::WIDTH..(y + 1) * Frame::WIDTH]
+    }
+
+    pub fn get_pointer(&self) -> *const u32 {
+        self.frame.as_ptr()
+    }
+}
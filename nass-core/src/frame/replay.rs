@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+use super::Frame;
+
+// Instant replay: the last few seconds of play kept small enough to hold on to, every
+// other frame at half size, palettized (a frame rarely has more than a few dozen colors).
+pub const REPLAY_WIDTH: usize = Frame::WIDTH / 2;
+pub const REPLAY_HEIGHT: usize = Frame::HEIGHT / 2;
+pub const MAX_REPLAY_SECONDS: u32 = 60;
+// Frames go by in pairs, one is kept.
+pub const REPLAY_FRAME_STEP: u64 = 2;
+
+pub struct ReplayFrame {
+    pub pixels: Vec<u8>,
+    pub palette: Vec<u32>,
+}
+
+pub struct Replay {
+    capacity: usize, // 0 is off
+    skipped: bool,
+    pub frames: VecDeque<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn new() -> Self {
+        Replay { capacity: 0, skipped: false, frames: VecDeque::new() }
+    }
+
+    pub fn set_capacity(&mut self, frames: usize) {
+        self.capacity = frames;
+        while self.frames.len() > frames { self.frames.pop_front(); }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub fn frame(&mut self, pixels: &[u32]) {
+        self.skipped = !self.skipped;
+        if !self.skipped { return }
+        if self.frames.len() == self.capacity { self.frames.pop_front(); }
+        self.frames.push_back(palettize(pixels));
+    }
+}
+
+fn palettize(pixels: &[u32]) -> ReplayFrame {
+    let mut palette: Vec<u32> = Vec::new();
+    let mut indices = Vec::with_capacity(REPLAY_WIDTH * REPLAY_HEIGHT);
+    for row in pixels.chunks(Frame::WIDTH).step_by(2) {
+        for &color in row.iter().step_by(2) {
+            let index = match palette.iter().position(|&c| c == color) {
+                Some(index) => index,
+                None if palette.len() < 256 => { palette.push(color); palette.len() - 1 },
+                None => nearest(&palette, color),
+            };
+            indices.push(index as u8);
+        }
+    }
+    ReplayFrame { pixels: indices, palette }
+}
+
+// Only when a frame has over 256 colors (HD packs, debug overlays).
+fn nearest(palette: &[u32], color: u32) -> usize {
+    let distance = |c: u32| (0..3).map(|i| {
+        let shift = 8 + 8 * i;
+        let d = ((c >> shift) & 0xFF) as i32 - ((color >> shift) & 0xFF) as i32;
+        d * d
+    }).sum::<i32>();
+    (0..palette.len()).min_by_key(|&i| distance(palette[i])).unwrap_or(0)
+}
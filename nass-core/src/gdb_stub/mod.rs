@@ -0,0 +1,147 @@
+use std::{io::{self, Read, Write}, net::{TcpListener, TcpStream, ToSocketAddrs}, fmt::Write as _};
+use crate::emulator::Emulator;
+
+// GDB remote serial protocol for the 6502, so gdb-style tools and IDE debug adapters can
+// attach. Call `poll` once per frame and only step the emulator while `paused` is false.
+// Registers ('g') are A, X, Y, P, S as bytes then PC little endian.
+// https://sourceware.org/gdb/current/onlinedocs/gdb.html/Remote-Protocol.html
+pub struct GdbStub {
+    listener: TcpListener,
+    client: Option<TcpStream>,
+    buffer: Vec<u8>,
+    paused: bool,
+}
+
+impl GdbStub {
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<GdbStub> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(GdbStub { listener, client: None, buffer: Vec::new(), paused: false })
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn poll(&mut self, emulator: &mut Emulator) {
+        if let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                self.client = Some(stream);
+                self.buffer.clear();
+                // gdb expects the target to be halted when it attaches.
+                self.paused = true;
+            }
+        }
+        if self.client.is_none() { return }
+        if !self.paused && emulator.at_breakpoint() {
+            self.paused = true;
+            self.send("S05");
+        }
+
+        let mut chunk = [0; 1024];
+        while let Some(client) = self.client.as_mut() {
+            match client.read(&mut chunk) {
+                Ok(0) => { self.client = None; self.paused = false; },
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(_) => { self.client = None; self.paused = false; },
+            }
+        }
+        while let Some(packet) = self.next_packet() {
+            match packet {
+                Some(packet) => {
+                    self.ack();
+                    let reply = self.handle(&packet, emulator);
+                    if let Some(reply) = reply { self.send(&reply); }
+                },
+                // Ctrl-C from the debugger
+                None => if !self.paused {
+                    self.paused = true;
+                    self.send("S02");
+                },
+            }
+        }
+    }
+
+    // Some(packet) for "$packet#checksum", None for an interrupt, acks are skipped.
+    fn next_packet(&mut self) -> Option<Option<String>> {
+        loop {
+            match *self.buffer.first()? {
+                0x03 => { self.buffer.remove(0); return Some(None) },
+                b'$' => break,
+                _ => { self.buffer.remove(0); },
+            }
+        }
+        let end = self.buffer.iter().position(|&b| b == b'#')?;
+        if self.buffer.len() < end + 3 { return None }
+        let packet = String::from_utf8_lossy(&self.buffer[1..end]).into_owned();
+        self.buffer.drain(..end + 3);
+        Some(Some(packet))
+    }
+
+    fn handle(&mut self, packet: &str, emulator: &mut Emulator) -> Option<String> {
+        let reply = match packet.as_bytes().first() {
+            Some(b'?') => "S05".to_string(),
+            Some(b'g') => {
+                let r = emulator.registers();
+                let mut reply = String::new();
+                for byte in [r.a, r.x, r.y, r.p, r.s, r.pc as u8, (r.pc >> 8) as u8] {
+                    let _ = write!(reply, "{byte:02x}");
+                }
+                reply
+            },
+            Some(b'm') => match parse_pair(&packet[1..], ',') {
+                Some((addr, len)) => {
+                    let mut reply = String::new();
+                    for i in 0..len.min(0x1000) {
+                        let _ = write!(reply, "{:02x}", emulator.debug_read((addr + i) as u16));
+                    }
+                    reply
+                },
+                None => "E01".to_string(),
+            },
+            Some(b'Z') | Some(b'z') => match packet.get(1..3).zip(packet.get(3..).and_then(|rest| parse_pair(rest, ','))) {
+                // Software and hardware breakpoints behave the same here.
+                Some(("0,", (addr, _))) | Some(("1,", (addr, _))) => {
+                    if packet.starts_with('Z') { emulator.add_breakpoint(addr as u16) } else { emulator.remove_breakpoint(addr as u16) }
+                    "OK".to_string()
+                },
+                _ => String::new(),
+            },
+            Some(b's') => { emulator.step_instruction(); "S05".to_string() },
+            // Answered with a stop reply once a breakpoint is hit.
+            Some(b'c') => { self.paused = false; return None },
+            Some(b'D') => { self.paused = false; self.send("OK"); self.client = None; return None },
+            Some(b'k') => { self.paused = false; self.client = None; return None },
+            Some(b'H') => "OK".to_string(),
+            _ => match packet {
+                p if p.starts_with("qSupported") => "PacketSize=4000".to_string(),
+                "qAttached" => "1".to_string(),
+                "qC" => "QC1".to_string(),
+                "qfThreadInfo" => "m1".to_string(),
+                "qsThreadInfo" => "l".to_string(),
+                _ => String::new(),
+            },
+        };
+        Some(reply)
+    }
+
+    fn ack(&mut self) {
+        if let Some(client) = self.client.as_mut() {
+            let _ = client.write_all(b"+");
+        }
+    }
+
+    fn send(&mut self, data: &str) {
+        let checksum = data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        if let Some(client) = self.client.as_mut() {
+            if write!(client, "${data}#{checksum:02x}").is_err() { self.client = None; }
+        }
+    }
+}
+
+// "addr,len" in hex.
+fn parse_pair(text: &str, separator: char) -> Option<(usize, usize)> {
+    let (a, b) = text.split_once(separator)?;
+    Some((usize::from_str_radix(a, 16).ok()?, usize::from_str_radix(b, 16).ok()?))
+}
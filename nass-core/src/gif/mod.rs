@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+// GIF89a out for replays: palettized frames, each with its own color table, looping.
+// Colors are RR-GG-BB-AA like the PPU colors, alpha is dropped.
+// https://www.w3.org/Graphics/GIF/spec-gif89a.txt
+const MAX_CODES: u16 = 4096;
+
+pub struct GifFrame<'a> {
+    pub pixels: &'a [u8], // Indices into `palette`
+    pub palette: &'a [u32], // At most 256 colors
+    pub delay: u16, // Hundredths of a second
+}
+
+pub fn encode(width: usize, height: usize, frames: &[GifFrame]) -> Vec<u8> {
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend_from_slice(&(width as u16).to_le_bytes());
+    gif.extend_from_slice(&(height as u16).to_le_bytes());
+    gif.extend_from_slice(&[0, 0, 0]); // No global color table
+    // NETSCAPE2.0 application extension, loop forever.
+    gif.extend_from_slice(&[0x21, 0xFF, 11]);
+    gif.extend_from_slice(b"NETSCAPE2.0");
+    gif.extend_from_slice(&[3, 1, 0, 0, 0]);
+    for frame in frames {
+        gif.extend_from_slice(&[0x21, 0xF9, 4, 0]);
+        gif.extend_from_slice(&frame.delay.to_le_bytes());
+        gif.extend_from_slice(&[0, 0]);
+        // Local color table of 2^(bits) entries.
+        let bits = (usize::BITS - frame.palette.len().saturating_sub(1).leading_zeros()).max(1) as u8;
+        gif.push(0x2C);
+        gif.extend_from_slice(&[0, 0, 0, 0]);
+        gif.extend_from_slice(&(width as u16).to_le_bytes());
+        gif.extend_from_slice(&(height as u16).to_le_bytes());
+        gif.push(0x80 | (bits - 1));
+        for i in 0..1 << bits {
+            let color = frame.palette.get(i).copied().unwrap_or(0);
+            gif.extend_from_slice(&color.to_be_bytes()[..3]);
+        }
+        let min_size = bits.max(2);
+        gif.push(min_size);
+        for block in lzw(frame.pixels, min_size).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend_from_slice(block);
+        }
+        gif.push(0);
+    }
+    gif.push(0x3B);
+    gif
+}
+
+// Variable-length LZW, codes packed least significant bit first.
+fn lzw(indices: &[u8], min_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_size;
+    let end = clear + 1;
+    let mut out = Vec::new();
+    let (mut bits, mut pending) = (0u32, 0u32);
+    let mut write = |code: u16, size: u8| {
+        pending |= (code as u32) << bits;
+        bits += size as u32;
+        while bits >= 8 {
+            out.push(pending as u8);
+            pending >>= 8;
+            bits -= 8;
+        }
+    };
+
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut size = min_size + 1;
+    let mut next = end + 1;
+    write(clear, size);
+    let mut prefix = None;
+    for &index in indices {
+        let code = match prefix {
+            None => { prefix = Some(index as u16); continue },
+            Some(code) => code,
+        };
+        if let Some(&longer) = table.get(&(code, index)) {
+            prefix = Some(longer);
+            continue
+        }
+        write(code, size);
+        if next < MAX_CODES {
+            table.insert((code, index), next);
+            next += 1;
+            if next > 1 << size && size < 12 { size += 1; }
+        } else {
+            write(clear, size);
+            table.clear();
+            size = min_size + 1;
+            next = end + 1;
+        }
+        prefix = Some(index as u16);
+    }
+    if let Some(code) = prefix { write(code, size); }
+    write(end, size);
+    if bits > 0 { out.push(pending as u8); }
+    out
+}
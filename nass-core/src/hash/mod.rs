@@ -0,0 +1,16 @@
+// CRC-32 (IEEE, reflected), the checksum ROM databases and zip files use.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    crc32_iter(bytes.iter().copied())
+}
+
+// `crc32` of bytes that aren't in one slice, like a frame's pixels.
+pub fn crc32_iter(bytes: impl IntoIterator<Item = u8>) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
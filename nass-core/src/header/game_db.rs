@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use crate::{mapper::Mirroring, clock::Region};
+use super::Header;
+
+// Corrections for one ROM, only the fields the database sets are changed.
+#[derive(Default, Clone, Copy)]
+pub struct HeaderFix {
+    pub mapper: Option<u16>,
+    pub submapper: Option<u8>,
+    pub mirroring: Option<Mirroring>,
+    pub battery: Option<bool>,
+    pub prg_ram: Option<usize>,
+    pub prg_nvram: Option<usize>,
+    pub chr_ram: Option<usize>,
+    pub chr_nvram: Option<usize>,
+    pub region: Option<Region>,
+}
+
+impl HeaderFix {
+    pub fn apply(&self, header: &mut Header) {
+        if let Some(mapper) = self.mapper { header.mapper = mapper }
+        if let Some(submapper) = self.submapper { header.submapper = submapper }
+        if let Some(mirroring) = self.mirroring { header.mirroring = mirroring }
+        if let Some(battery) = self.battery { header.battery = battery }
+        if let Some(size) = self.prg_ram { header.prg_ram = size }
+        if let Some(size) = self.prg_nvram { header.prg_nvram = size }
+        if let Some(size) = self.chr_ram { header.chr_ram = size }
+        if let Some(size) = self.chr_nvram { header.chr_nvram = size }
+        if let Some(region) = self.region { header.region = region }
+    }
+}
+
+// Header fixes keyed by the CRC-32 of the ROM without its header. Not bundled, it's
+// loaded from text with one ROM per line:
+//   <crc32 hex> [mapper=4] [submapper=1] [mirroring=h|v|4] [battery=0|1]
+//               [prg_ram=8192] [prg_nvram=..] [chr_ram=..] [chr_nvram=..] [region=ntsc|pal]
+//               [name=<title, the rest of the line>]
+// Empty lines and lines starting with # are skipped.
+#[derive(Default)]
+pub struct GameDb {
+    fixes: HashMap<u32, HeaderFix>,
+    names: HashMap<u32, String>,
+}
+
+impl GameDb {
+    pub fn new() -> Self {
+        GameDb { fixes: HashMap::new(), names: HashMap::new() }
+    }
+
+    // Adds the entries in `text`, returns how many there were.
+    pub fn load(&mut self, text: &str) -> Result<usize, String> {
+        let mut count = 0;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+            let (line, name) = match line.split_once("name=") {
+                Some((line, name)) => (line, Some(name.trim())),
+                None => (line, None),
+            };
+            let (crc, fix) = parse_line(line).map_err(|e| format!("Game database line {}: {e}", number + 1))?;
+            self.fixes.insert(crc, fix);
+            if let Some(name) = name { self.names.insert(crc, name.to_string()); }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    pub fn get(&self, crc: u32) -> Option<&HeaderFix> {
+        self.fixes.get(&crc)
+    }
+
+    pub fn name(&self, crc: u32) -> Option<&str> {
+        self.names.get(&crc).map(String::as_str)
+    }
+}
+
+fn parse_line(line: &str) -> Result<(u32, HeaderFix), String> {
+    let mut fields = line.split_whitespace();
+    let crc = fields.next().unwrap_or("");
+    let crc = u32::from_str_radix(crc, 16).map_err(|_| format!("bad checksum {crc}"))?;
+    let mut fix = HeaderFix::default();
+    for field in fields {
+        let (key, value) = field.split_once('=').ok_or(format!("expected key=value, got {field}"))?;
+        let bad_value = || format!("bad value for {key}: {value}");
+        let number = || value.parse::<usize>().map_err(|_| bad_value());
+        match key {
+            "mapper" => fix.mapper = Some(value.parse().map_err(|_| bad_value())?),
+            "submapper" => fix.submapper = Some(value.parse().map_err(|_| bad_value())?),
+            "mirroring" => fix.mirroring = Some(match value {
+                "h" => Mirroring::Horizontal,
+                "v" => Mirroring::Vertical,
+                "4" => Mirroring::FourScreen,
+                _ => return Err(bad_value()),
+            }),
+            "battery" => fix.battery = Some(match value { "0" => false, "1" => true, _ => return Err(bad_value()) }),
+            "prg_ram" => fix.prg_ram = Some(number()?),
+            "prg_nvram" => fix.prg_nvram = Some(number()?),
+            "chr_ram" => fix.chr_ram = Some(number()?),
+            "chr_nvram" => fix.chr_nvram = Some(number()?),
+            "region" => fix.region = Some(match value {
+                "ntsc" => Region::Ntsc,
+                "pal" => Region::Pal,
+                _ => return Err(bad_value()),
+            }),
+            _ => return Err(format!("unknown key {key}")),
+        }
+    }
+    Ok((crc, fix))
+}
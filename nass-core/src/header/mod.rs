@@ -0,0 +1,181 @@
+mod game_db;
+
+pub use self::game_db::GameDb;
+
+use crate::{mapper::Mirroring, clock::Region, hash::crc32};
+
+const MAGIC: [u8; 4] = [0x4E, 0x45, 0x53, 0x1A];
+const TRAINER_SIZE: usize = 512;
+const ROM_SIZE_ERROR: &str = "ROM size in the header is too large.";
+
+// iNES and NES 2.0 headers. Sizes are in bytes, RAM sizes are only known for NES 2.0.
+// https://www.nesdev.org/wiki/INES
+// https://www.nesdev.org/wiki/NES_2.0
+#[derive(Clone, Copy)]
+pub struct Header {
+    pub mapper: u16,
+    pub submapper: u8,
+    pub mirroring: Mirroring,
+    pub battery: bool,
+    pub trainer: bool,
+    pub prg_rom: usize,
+    pub chr_rom: usize,
+    pub prg_ram: usize,
+    pub prg_nvram: usize,
+    pub chr_ram: usize,
+    pub chr_nvram: usize,
+    pub region: Region,
+}
+
+impl Header {
+    pub fn parse(bytes: &[u8]) -> Result<Header, String> {
+        if bytes.len() < 16 { return Err("File too small to be a NES ROM.".to_string()) }
+        if bytes[..4] != MAGIC { return Err("Only NES files supported.".to_string()) }
+
+        let mirroring = match (bytes[6] & 0x8 != 0, bytes[6] & 0x1 != 0) {
+            (true, _) => Mirroring::FourScreen,
+            (false, true) => Mirroring::Vertical,
+            (false, false) => Mirroring::Horizontal,
+        };
+        let mut header = Header {
+            mapper: (bytes[6] >> 4) as u16,
+            submapper: 0,
+            mirroring,
+            battery: bytes[6] & 0x2 != 0,
+            trainer: bytes[6] & 0x4 != 0,
+            prg_rom: bytes[4] as usize * 0x4000,
+            chr_rom: bytes[5] as usize * 0x2000,
+            prg_ram: 0,
+            prg_nvram: 0,
+            chr_ram: 0,
+            chr_nvram: 0,
+            region: Region::Ntsc,
+        };
+
+        if bytes[7] & 0x0C == 0x08 {
+            header.mapper |= (bytes[7] & 0xF0) as u16 | ((bytes[8] & 0x0F) as u16) << 8;
+            header.submapper = bytes[8] >> 4;
+            header.prg_rom = rom_size(bytes[4], bytes[9] & 0x0F, 0x4000)?;
+            header.chr_rom = rom_size(bytes[5], bytes[9] >> 4, 0x2000)?;
+            // Offsets into the file add the sizes up, they have to fit too.
+            header.prg_rom_start().checked_add(header.prg_rom).and_then(|start| start.checked_add(header.chr_rom))
+                .ok_or(ROM_SIZE_ERROR.to_string())?;
+            header.prg_ram = ram_size(bytes[10] & 0x0F);
+            header.prg_nvram = ram_size(bytes[10] >> 4);
+            header.chr_ram = ram_size(bytes[11] & 0x0F);
+            header.chr_nvram = ram_size(bytes[11] >> 4);
+            // Multi-region carts run as NTSC, Dendy is closest to PAL.
+            header.region = if bytes[12] & 0x3 == 1 || bytes[12] & 0x3 == 3 { Region::Pal } else { Region::Ntsc };
+        } else if bytes[12..16].iter().all(|&b| b == 0) {
+            // Old dumps have junk like "DiskDude!" from byte 7 on, then the upper mapper
+            // nibble can't be trusted.
+            header.mapper |= (bytes[7] & 0xF0) as u16;
+            if bytes[9] & 0x1 != 0 { header.region = Region::Pal }
+        }
+        Ok(header)
+    }
+
+    // Always written as NES 2.0.
+    // Where PRG-ROM and CHR-ROM start in the file.
+    pub fn prg_rom_start(&self) -> usize {
+        16 + if self.trainer { TRAINER_SIZE } else { 0 }
+    }
+
+    pub fn chr_rom_start(&self) -> usize {
+        self.prg_rom_start() + self.prg_rom
+    }
+
+    pub fn to_bytes(self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[..4].copy_from_slice(&MAGIC);
+        let (prg_lsb, prg_msb) = encode_rom_size(self.prg_rom, 0x4000);
+        let (chr_lsb, chr_msb) = encode_rom_size(self.chr_rom, 0x2000);
+        bytes[4] = prg_lsb;
+        bytes[5] = chr_lsb;
+        bytes[6] = (((self.mapper & 0x0F) as u8) << 4)
+            | ((self.mirroring == Mirroring::FourScreen) as u8 * 0x8)
+            | (self.trainer as u8 * 0x4)
+            | (self.battery as u8 * 0x2)
+            | (self.mirroring == Mirroring::Vertical) as u8;
+        bytes[7] = (self.mapper & 0xF0) as u8 | 0x08;
+        bytes[8] = (self.submapper << 4) | ((self.mapper >> 8) & 0x0F) as u8;
+        bytes[9] = (chr_msb << 4) | prg_msb;
+        bytes[10] = (encode_ram_size(self.prg_nvram) << 4) | encode_ram_size(self.prg_ram);
+        bytes[11] = (encode_ram_size(self.chr_nvram) << 4) | encode_ram_size(self.chr_ram);
+        bytes[12] = match self.region { Region::Ntsc => 0, Region::Pal => 1 };
+        bytes
+    }
+}
+
+// Checksum of everything after the header and trainer, what ROM databases key on.
+pub fn rom_crc32(bytes: &[u8]) -> Result<u32, String> {
+    let header = Header::parse(bytes)?;
+    let start = 16 + if header.trainer { TRAINER_SIZE } else { 0 };
+    Ok(crc32(bytes.get(start..).unwrap_or(&[])))
+}
+
+// Rewrites the header as NES 2.0, with the database's fixes applied when it knows the ROM.
+pub fn repair(bytes: &[u8], db: &GameDb) -> Result<Vec<u8>, String> {
+    let mut header = Header::parse(bytes)?;
+    if let Some(fix) = db.get(rom_crc32(bytes)?) {
+        fix.apply(&mut header);
+    }
+    let mut repaired = header.to_bytes().to_vec();
+    repaired.extend_from_slice(&bytes[16..]);
+    Ok(repaired)
+}
+
+// With the MSB nibble at $F the size is 2^E * (MM*2+1), otherwise a count of units.
+// Exponents go up to 2^63, past what fits in memory (or a usize on wasm32).
+fn rom_size(lsb: u8, msb: u8, unit: usize) -> Result<usize, String> {
+    let size = if msb == 0x0F {
+        1usize.checked_shl((lsb >> 2) as u32).and_then(|size| size.checked_mul((lsb & 0x3) as usize * 2 + 1))
+    } else {
+        Some((((msb as usize) << 8) | lsb as usize) * unit)
+    };
+    size.ok_or(ROM_SIZE_ERROR.to_string())
+}
+
+fn encode_rom_size(size: usize, unit: usize) -> (u8, u8) {
+    let units = size / unit;
+    if units * unit == size && units <= 0xEFF {
+        return (units as u8, (units >> 8) as u8)
+    }
+    let exponent = size.trailing_zeros().min(63);
+    let multiplier = (size >> exponent).min(7);
+    (((exponent as u8) << 2) | ((multiplier as u8 - 1) / 2), 0x0F)
+}
+
+// 64 << shift bytes, 0 means none.
+fn ram_size(shift: u8) -> usize {
+    if shift == 0 { 0 } else { 64 << shift }
+}
+
+fn encode_ram_size(size: usize) -> u8 {
+    if size == 0 { 0 } else { ((size / 64).max(2).ilog2() as u8).min(15) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nes2(prg_lsb: u8, chr_lsb: u8, msb: u8) -> Vec<u8> {
+        let mut bytes = b"NES\x1A".to_vec();
+        bytes.extend_from_slice(&[prg_lsb, chr_lsb, 0x00, 0x08, 0x00, msb]);
+        bytes.resize(16, 0);
+        bytes
+    }
+
+    #[test]
+    fn exponent_sizes() {
+        let header = Header::parse(&nes2(20 << 2 | 1, 14 << 2, 0xFF)).unwrap();
+        assert_eq!((header.prg_rom, header.chr_rom), (3 << 20, 1 << 14));
+    }
+
+    #[test]
+    fn oversized_exponent_is_an_error() {
+        assert_eq!(Header::parse(&nes2(0xFF, 0x00, 0x0F)).err(), Some(ROM_SIZE_ERROR.to_string()));
+        // Each size fits on its own but not the file they add up to.
+        assert_eq!(Header::parse(&nes2(63 << 2, 63 << 2, 0xFF)).err(), Some(ROM_SIZE_ERROR.to_string()));
+    }
+}
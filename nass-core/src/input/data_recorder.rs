@@ -0,0 +1,100 @@
+use crate::state::{StateWriter, StateReader};
+
+// Sampling the tape line every 32 CPU cycles (~56kHz) is plenty for the ~1-2kHz tones
+// Family BASIC writes.
+const CYCLES_PER_SAMPLE: usize = 32;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum TapeMode {
+    Stop = 0,
+    Play = 1,
+    Record = 2,
+}
+
+impl TapeMode {
+    pub fn from_u8(value: u8) -> Result<TapeMode, String> {
+        match value {
+            0 => Ok(TapeMode::Stop),
+            1 => Ok(TapeMode::Play),
+            2 => Ok(TapeMode::Record),
+            _ => Err("Unknown tape mode.".to_string())
+        }
+    }
+}
+
+// Famicom Data Recorder. The tape is kept as 1-bit samples of the line level, packed LSB
+// first, recording appends at the current position.
+pub struct DataRecorder {
+    tape: Vec<u8>,
+    mode: TapeMode,
+    position: usize, // In samples
+    cycles: usize,
+    out: bool,
+}
+
+impl DataRecorder {
+    pub fn new() -> Self {
+        DataRecorder { tape: Vec::new(), mode: TapeMode::Stop, position: 0, cycles: 0, out: false }
+    }
+
+    pub fn tape(&self) -> &[u8] { &self.tape }
+
+    pub fn load(&mut self, tape: &[u8]) {
+        self.tape = tape.to_vec();
+        self.position = 0;
+    }
+
+    // Switching mode rewinds the tape.
+    pub fn set_mode(&mut self, mode: TapeMode) {
+        self.mode = mode;
+        self.position = 0;
+        self.cycles = 0;
+        if mode == TapeMode::Record { self.tape.clear(); }
+    }
+
+    pub fn write(&mut self, out: bool) {
+        self.out = out;
+    }
+
+    pub fn read(&self) -> bool {
+        self.mode == TapeMode::Play && self.sample(self.position)
+    }
+
+    fn sample(&self, position: usize) -> bool {
+        self.tape.get(position / 8).is_some_and(|byte| byte >> (position % 8) & 1 == 1)
+    }
+
+    pub fn tick(&mut self, cycles: usize) {
+        if self.mode == TapeMode::Stop { return }
+        self.cycles += cycles;
+        while self.cycles >= CYCLES_PER_SAMPLE {
+            self.cycles -= CYCLES_PER_SAMPLE;
+            if self.mode == TapeMode::Record {
+                if self.position / 8 == self.tape.len() { self.tape.push(0); }
+                let bit = 1 << (self.position % 8);
+                if self.out { self.tape[self.position / 8] |= bit } else { self.tape[self.position / 8] &= !bit }
+            } else if self.position >= self.tape.len() * 8 {
+                self.mode = TapeMode::Stop;
+                return
+            }
+            self.position += 1;
+        }
+    }
+
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.mode as u8);
+        w.write_usize(self.position);
+        w.write_usize(self.cycles);
+        w.write_bool(self.out);
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.mode = TapeMode::from_u8(r.read_u8()?)?;
+        self.position = r.read_usize()?;
+        self.cycles = r.read_usize()? % CYCLES_PER_SAMPLE;
+        self.out = r.read_bool()?;
+        // The tape itself isn't part of the state, recording continues from what's loaded.
+        if self.mode == TapeMode::Record { self.position = self.position.min(self.tape.len() * 8); }
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+use super::{InputDevice, DeviceKind};
+use crate::state::{StateWriter, StateReader};
+
+// Nothing plugged in, all data lines read low.
+pub struct Empty;
+
+impl InputDevice for Empty {
+    fn strobe(&mut self, _: u8) {}
+    fn read(&mut self, _: usize) -> u8 { 0 }
+    fn input(&self) -> u32 { 0 }
+    fn set_input(&mut self, _: u32) {}
+    fn kind(&self) -> DeviceKind { DeviceKind::Empty }
+    fn save_state(&self, _: &mut StateWriter) {}
+    fn load_state(&mut self, _: &mut StateReader) -> Result<(), String> { Ok(()) }
+}
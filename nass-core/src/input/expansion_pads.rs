@@ -0,0 +1,52 @@
+use super::{InputDevice, DeviceKind, Joypad};
+use crate::state::{StateWriter, StateReader};
+
+// Famicom expansion controllers (the Hori 4 Players Adapter in Famicom mode, or plain
+// expansion port pads), players 3 and 4 next to the hardwired ones. They share the strobe
+// and shift out on D1: player 3 on $4016, player 4 on $4017.
+// Input: bits 0-7 player 3, bits 8-15 player 4 (`JoypadButton` bits).
+// https://www.nesdev.org/wiki/Four_player_adapters
+pub struct ExpansionPads {
+    pads: [Joypad; 2],
+}
+
+impl ExpansionPads {
+    pub fn new() -> Self {
+        ExpansionPads { pads: [Joypad::new(), Joypad::new()] }
+    }
+}
+
+impl InputDevice for ExpansionPads {
+    fn strobe(&mut self, value: u8) {
+        self.pads.iter_mut().for_each(|pad| pad.strobe(value));
+    }
+
+    fn read(&mut self, port: usize) -> u8 {
+        match self.pads.get_mut(port) {
+            Some(pad) => (pad.read(port) & 1) << 1,
+            None => 0,
+        }
+    }
+
+    fn input(&self) -> u32 {
+        self.pads[0].input() | self.pads[1].input() << 8
+    }
+
+    fn set_input(&mut self, value: u32) {
+        self.pads[0].set_input(value & 0xFF);
+        self.pads[1].set_input(value >> 8 & 0xFF);
+    }
+
+    fn kind(&self) -> DeviceKind { DeviceKind::ExpansionPads }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        self.pads.iter().for_each(|pad| pad.save_state(w));
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        for pad in self.pads.iter_mut() {
+            pad.load_state(r)?;
+        }
+        Ok(())
+    }
+}
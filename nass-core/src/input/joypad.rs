@@ -1,4 +1,6 @@
 use bitflags::bitflags;
+use super::{InputDevice, DeviceKind};
+use crate::state::{StateWriter, StateReader};
 
 bitflags! {
        pub struct JoypadButton: u8 {
@@ -16,7 +18,7 @@ bitflags! {
 pub struct Joypad {
    strobe: bool,
    button_index: u8,
-   pub button_status: JoypadButton,
+   button_status: JoypadButton,
 }
 
 impl Joypad {
@@ -27,19 +29,17 @@ impl Joypad {
             button_status: JoypadButton::from_bits_truncate(0),
         }
     }
+}
 
-    pub fn write(&mut self, data: u8) {
+impl InputDevice for Joypad {
+    fn strobe(&mut self, data: u8) {
       self.strobe = data & 1 == 1;
       if self.strobe {
           self.button_index = 0
       }
     }
 
-    pub fn set_button(&mut self, value: u8) {
-        self.button_status.toggle(JoypadButton::from_bits_truncate(value));
-    }
-
-    pub fn read(&mut self) -> u8 {
+    fn read(&mut self, _: usize) -> u8 {
         if self.button_index > 7 {
             return 1;
         }
@@ -49,4 +49,27 @@ impl Joypad {
         }
         response
     }
+
+    fn input(&self) -> u32 {
+        self.button_status.bits() as u32
+    }
+
+    fn set_input(&mut self, value: u32) {
+        self.button_status = JoypadButton::from_bits_truncate(value as u8);
+    }
+
+    fn kind(&self) -> DeviceKind { DeviceKind::Joypad }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bool(self.strobe);
+        w.write_u8(self.button_index);
+        w.write_u8(self.button_status.bits());
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.strobe = r.read_bool()?;
+        self.button_index = r.read_u8()?;
+        self.button_status = JoypadButton::from_bits_truncate(r.read_u8()?);
+        Ok(())
+    }
 }
@@ -0,0 +1,79 @@
+use super::{InputDevice, DeviceKind, DataRecorder};
+use crate::state::{StateWriter, StateReader};
+
+const ROWS: usize = 9;
+
+// Family BASIC keyboard on the expansion port, with the data recorder hanging off it.
+// $4016 writes: bit 0 resets to row 0, bit 1 selects the column (a 1 -> 0 transition
+// moves to the next row), bit 2 enables the matrix and doubles as the tape output.
+// $4017 reads return the 4 keys of the selected row/column on D1-D4 (0 = pressed),
+// $4016 reads the tape input on D1.
+// Input: bits 0-6 key (row * 8 + column * 4 + data line), bit 8 pressed.
+// https://www.nesdev.org/wiki/Family_BASIC_Keyboard
+pub struct Keyboard {
+    keys: [u8; ROWS], // Column 0 in the low nibble, column 1 in the high one
+    row: usize,
+    column: bool,
+    enabled: bool,
+    recorder: DataRecorder,
+}
+
+impl Keyboard {
+    pub fn new() -> Self {
+        Keyboard { keys: [0; ROWS], row: 0, column: false, enabled: false, recorder: DataRecorder::new() }
+    }
+}
+
+impl InputDevice for Keyboard {
+    fn strobe(&mut self, value: u8) {
+        let column = value & 0x02 != 0;
+        if self.column && !column { self.row += 1; }
+        if value & 0x01 != 0 { self.row = 0; }
+        self.column = column;
+        self.enabled = value & 0x04 != 0;
+        self.recorder.write(self.enabled);
+    }
+
+    fn read(&mut self, port: usize) -> u8 {
+        if port == 0 { return (self.recorder.read() as u8) << 1 }
+        if !self.enabled { return 0 }
+        let keys = match self.keys.get(self.row) {
+            Some(keys) => if self.column { keys >> 4 } else { keys & 0x0F },
+            None => 0,
+        };
+        (!keys & 0x0F) << 1
+    }
+
+    fn input(&self) -> u32 { 0 }
+
+    fn set_input(&mut self, value: u32) {
+        let key = (value & 0x7F) as usize;
+        if key / 8 >= ROWS { return }
+        let bit = 1 << (key % 8);
+        if value & 0x100 != 0 { self.keys[key / 8] |= bit } else { self.keys[key / 8] &= !bit }
+    }
+
+    fn cpu_tick(&mut self, cycles: usize) {
+        self.recorder.tick(cycles);
+    }
+
+    fn data_recorder(&mut self) -> Option<&mut DataRecorder> { Some(&mut self.recorder) }
+
+    fn kind(&self) -> DeviceKind { DeviceKind::Keyboard }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.keys);
+        w.write_usize(self.row);
+        w.write_bool(self.column);
+        w.write_bool(self.enabled);
+        self.recorder.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        r.read_bytes(&mut self.keys)?;
+        self.row = r.read_usize()?;
+        self.column = r.read_bool()?;
+        self.enabled = r.read_bool()?;
+        self.recorder.load_state(r)
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use super::InputDevice;
+
+struct Playback {
+    port: usize,
+    inputs: Vec<u32>,
+    frame: usize,
+    held: Option<u32>, // Port input from before the macro, put back when it ends
+}
+
+// Named sequences of per-frame port inputs. While one plays it owns the port's input, one
+// entry per frame, then whatever was held before comes back. Defined from text, one per line:
+//   name=08,08,00,01*20
+// with inputs in hex (the device's `set_input` value) and *n repeating one n times.
+// Empty lines and lines starting with # are skipped.
+pub struct Macros {
+    defined: HashMap<String, Vec<u32>>,
+    playing: Vec<Playback>,
+}
+
+impl Macros {
+    pub fn new() -> Self {
+        Macros { defined: HashMap::new(), playing: Vec::new() }
+    }
+
+    pub fn define(&mut self, name: &str, inputs: &[u32]) {
+        self.defined.insert(name.to_string(), inputs.to_vec());
+    }
+
+    // Adds the macros in `text`, returns how many there were.
+    pub fn load(&mut self, text: &str) -> Result<usize, String> {
+        let mut count = 0;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+            let bad_line = || format!("Macro line {}: expected name=inputs", number + 1);
+            let (name, inputs) = line.split_once('=').ok_or_else(bad_line)?;
+            let mut frames = Vec::new();
+            for input in inputs.split(',').map(str::trim) {
+                let (input, repeat) = input.split_once('*').unwrap_or((input, "1"));
+                let input = u32::from_str_radix(input.trim(), 16).map_err(|_| bad_line())?;
+                let repeat: usize = repeat.trim().parse().map_err(|_| bad_line())?;
+                frames.resize(frames.len() + repeat, input);
+            }
+            self.define(name.trim(), &frames);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    // Starts a macro on port 0, 1 or the expansion port (2), replacing one already playing there.
+    pub fn play(&mut self, name: &str, port: usize) -> bool {
+        let inputs = match self.defined.get(name) {
+            Some(inputs) if port < 3 => inputs.clone(),
+            _ => return false,
+        };
+        let held = self.playing.iter().position(|p| p.port == port).and_then(|i| self.playing.remove(i).held);
+        self.playing.push(Playback { port, inputs, frame: 0, held });
+        true
+    }
+
+    pub fn stop(&mut self, ports: &mut [Box<dyn InputDevice>; 3]) {
+        for playback in self.playing.drain(..) {
+            if let Some(held) = playback.held { ports[playback.port].set_input(held); }
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        !self.playing.is_empty()
+    }
+
+    // Sets the first input of macros started since the last frame, before running any.
+    pub fn start(&mut self, ports: &mut [Box<dyn InputDevice>; 3]) {
+        self.advance(ports, true);
+    }
+
+    // Sets the next frame's inputs, called as every frame completes.
+    pub fn apply(&mut self, ports: &mut [Box<dyn InputDevice>; 3]) {
+        self.advance(ports, false);
+    }
+
+    fn advance(&mut self, ports: &mut [Box<dyn InputDevice>; 3], new_only: bool) {
+        self.playing.retain_mut(|playback| {
+            if new_only && playback.frame > 0 { return true }
+            let port = &mut ports[playback.port];
+            let held = *playback.held.get_or_insert_with(|| port.input());
+            match playback.inputs.get(playback.frame) {
+                Some(&input) => { port.set_input(input); playback.frame += 1; true },
+                None => { port.set_input(held); false },
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{new_device, DeviceKind};
+
+    #[test]
+    fn one_input_per_frame() {
+        let mut ports = [new_device(DeviceKind::Joypad), new_device(DeviceKind::Joypad), new_device(DeviceKind::Empty)];
+        ports[0].set_input(0x80);
+        let mut macros = Macros::new();
+        macros.define("walk", &[0x01, 0x02, 0x03]);
+        macros.play("walk", 0);
+        macros.start(&mut ports);
+        macros.start(&mut ports);
+        assert_eq!(ports[0].input(), 0x01);
+        macros.apply(&mut ports);
+        assert_eq!(ports[0].input(), 0x02);
+        macros.apply(&mut ports);
+        assert_eq!(ports[0].input(), 0x03);
+        macros.apply(&mut ports);
+        assert_eq!(ports[0].input(), 0x80);
+        assert!(!macros.is_playing());
+    }
+
+    #[test]
+    fn replacing_starts_at_once() {
+        let mut ports = [new_device(DeviceKind::Joypad), new_device(DeviceKind::Joypad), new_device(DeviceKind::Empty)];
+        let mut macros = Macros::new();
+        macros.define("walk", &[0x01, 0x01]);
+        macros.define("jump", &[0x08]);
+        macros.play("walk", 0);
+        macros.start(&mut ports);
+        macros.play("jump", 0);
+        macros.start(&mut ports);
+        assert_eq!(ports[0].input(), 0x08);
+        macros.apply(&mut ports);
+        assert_eq!(ports[0].input(), 0x00);
+    }
+}
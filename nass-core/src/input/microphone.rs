@@ -0,0 +1,39 @@
+use crate::state::{StateWriter, StateReader};
+
+// The microphone on the Famicom's hardwired player 2 controller, read on $4016 D2. The line
+// follows the raw audio signal, so instead of a fixed high the bit is set on a share of reads
+// proportional to the level: quiet input flickers now and then, a shout reads high nearly
+// every time. Games either look for any high read (Pols Voice) or count them (Takeshi).
+// https://www.nesdev.org/wiki/Standard_controller#Famicom
+pub struct Microphone {
+    pub level: u8,
+    phase: u8,
+}
+
+impl Microphone {
+    pub fn new() -> Self {
+        Microphone { level: 0, phase: 0 }
+    }
+
+    pub fn read(&mut self) -> u8 {
+        if self.level == 0 { return 0 }
+        let (phase, high) = self.phase.overflowing_add(self.level);
+        self.phase = phase;
+        ((high || self.level == 0xFF) as u8) << 2
+    }
+
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.level);
+        w.write_u8(self.phase);
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        if r.version() < 11 {
+            *self = Microphone::new();
+            return Ok(())
+        }
+        self.level = r.read_u8()?;
+        self.phase = r.read_u8()?;
+        Ok(())
+    }
+}
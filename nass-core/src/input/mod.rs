@@ -0,0 +1,74 @@
+mod joypad;
+mod vaus;
+mod empty;
+mod keyboard;
+mod data_recorder;
+mod macros;
+mod expansion_pads;
+mod microphone;
+
+pub use crate::input::{
+    joypad::Joypad,
+    vaus::Vaus,
+    empty::Empty,
+    keyboard::Keyboard,
+    data_recorder::{DataRecorder, TapeMode},
+    macros::Macros,
+    expansion_pads::ExpansionPads,
+    microphone::Microphone,
+};
+
+use crate::state::{StateWriter, StateReader};
+
+// Anything that plugs into a controller port or the Famicom expansion port. The CPU strobes
+// every port through $4016 and reads them serially from $4016 (port 0) and $4017 (port 1),
+// each device decides which data lines (D0-D4) it drives. Controller port devices only see
+// reads of their own port, expansion port devices see both.
+// https://www.nesdev.org/wiki/Input_devices
+pub trait InputDevice {
+    fn strobe(&mut self, value: u8);
+    fn read(&mut self, port: usize) -> u8;
+    // Frontend input, what the bits mean is up to the device.
+    fn input(&self) -> u32;
+    fn set_input(&mut self, value: u32);
+    fn kind(&self) -> DeviceKind;
+    fn cpu_tick(&mut self, _: usize) {}
+    fn data_recorder(&mut self) -> Option<&mut DataRecorder> { None }
+    fn save_state(&self, w: &mut StateWriter);
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String>;
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum DeviceKind {
+    Empty = 0,
+    Joypad = 1,
+    Vaus = 2,
+    VausFamicom = 3,
+    Keyboard = 4,
+    ExpansionPads = 5,
+}
+
+impl DeviceKind {
+    pub fn from_u8(value: u8) -> Result<DeviceKind, String> {
+        match value {
+            0 => Ok(DeviceKind::Empty),
+            1 => Ok(DeviceKind::Joypad),
+            2 => Ok(DeviceKind::Vaus),
+            3 => Ok(DeviceKind::VausFamicom),
+            4 => Ok(DeviceKind::Keyboard),
+            5 => Ok(DeviceKind::ExpansionPads),
+            _ => Err("Unknown input device.".to_string())
+        }
+    }
+}
+
+pub fn new_device(kind: DeviceKind) -> Box<dyn InputDevice> {
+    match kind {
+        DeviceKind::Empty => Box::new(Empty),
+        DeviceKind::Joypad => Box::new(Joypad::new()),
+        DeviceKind::Vaus => Box::new(Vaus::new(false)),
+        DeviceKind::VausFamicom => Box::new(Vaus::new(true)),
+        DeviceKind::Keyboard => Box::new(Keyboard::new()),
+        DeviceKind::ExpansionPads => Box::new(ExpansionPads::new()),
+    }
+}
@@ -0,0 +1,57 @@
+use super::{InputDevice, DeviceKind};
+use crate::state::{StateWriter, StateReader};
+
+// Arkanoid "Vaus" paddle. Strobing latches the knob position, reads shift it out inverted
+// and MSB first. The NES version sits on a controller port with data on D4 and fire on D3,
+// the Famicom one on the expansion port with fire on $4016 D1 and data on $4017 D1.
+// Input: bits 0-7 knob position (roughly 98-242 on real units), bit 8 fire.
+// https://www.nesdev.org/wiki/Arkanoid_controller
+pub struct Vaus {
+    position: u8,
+    fire: bool,
+    shift: u8,
+    famicom: bool,
+}
+
+impl Vaus {
+    pub fn new(famicom: bool) -> Self {
+        Vaus { position: 0x62, fire: false, shift: 0, famicom }
+    }
+}
+
+impl InputDevice for Vaus {
+    fn strobe(&mut self, value: u8) {
+        if value & 1 == 1 { self.shift = !self.position; }
+    }
+
+    fn read(&mut self, port: usize) -> u8 {
+        if self.famicom && port == 0 { return (self.fire as u8) << 1 }
+        let data = self.shift >> 7;
+        self.shift <<= 1;
+        if self.famicom { data << 1 } else { data << 4 | (self.fire as u8) << 3 }
+    }
+
+    fn input(&self) -> u32 {
+        self.position as u32 | (self.fire as u32) << 8
+    }
+
+    fn set_input(&mut self, value: u32) {
+        self.position = value as u8;
+        self.fire = value & 0x100 != 0;
+    }
+
+    fn kind(&self) -> DeviceKind { if self.famicom { DeviceKind::VausFamicom } else { DeviceKind::Vaus } }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.position);
+        w.write_bool(self.fire);
+        w.write_u8(self.shift);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.position = r.read_u8()?;
+        self.fire = r.read_bool()?;
+        self.shift = r.read_u8()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+#![feature(bigint_helper_methods)]
+#![cfg_attr(test, feature(test))]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
+#[macro_use]
+mod logging;
+mod ppu;
+mod cpu;
+mod emulator;
+mod mapper;
+mod frame;
+mod state;
+mod event;
+mod rng;
+mod clock;
+mod input;
+mod hash;
+mod zip;
+mod trace;
+mod report;
+mod png;
+mod gif;
+mod multiverse;
+mod header;
+mod accuracy;
+mod patch;
+mod text;
+mod settings;
+mod condition;
+mod practice;
+mod stats;
+mod metrics;
+mod save_sync;
+#[cfg(feature = "debug-server")]
+mod debug_server;
+#[cfg(feature = "gdb-stub")]
+mod gdb_stub;
+#[cfg(feature = "rcheevos")]
+pub mod achievements;
+
+pub use crate::{emulator::{Emulator, FrameReport}, multiverse::{Multiverse, Divergence}, ppu::LineHook, state::Chunk, frame::FrameBuffer, metrics::Metrics, save_sync::{SyncStore, SyncKind, Diff}};
+
+// Types for debuggers and test harnesses rather than frontends.
+pub mod debug {
+    pub use crate::{cpu::Registers, ppu::Split};
+    #[cfg(feature = "debug-server")]
+    pub use crate::debug_server::DebugServer;
+    #[cfg(feature = "gdb-stub")]
+    pub use crate::gdb_stub::GdbStub;
+    #[cfg(feature = "single-step")]
+    pub use crate::cpu::FlatCpu;
+}
@@ -0,0 +1,17 @@
+// Wrappers around the tracing macros that compile to nothing without the "tracing"
+// feature, arguments aren't even evaluated then. Targets follow the module layout:
+// nass::cpu, nass::bus, nass::ppu, nass::ppu::reg, nass::mapper, nass::mapper::reg and
+// nass::input, so a subscriber can filter with e.g. RUST_LOG=nass::ppu::reg=trace.
+macro_rules! log_trace {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        tracing::trace!($($arg)+);
+    }};
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)+);
+    }};
+}
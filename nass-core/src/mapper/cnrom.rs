@@ -0,0 +1,135 @@
+use std::fmt;
+use super::*;
+
+// The board decodes 2 bits of CHR bank (32KB), oversized images (NES 2.0, up to 2MB) use
+// the whole byte. Banks wrap at the CHR-ROM size.
+pub struct CNROM {
+    chr_bank: u8,
+    mirroring: Mirroring,
+    prg_offset: usize,
+    prg_len: usize,
+    chr_offset: usize,
+    chr_len: usize,
+}
+
+impl CNROM {
+    pub fn new(prg_len: usize, chr_len: usize, prg_offset: usize, chr_offset: usize,  mirroring: Mirroring) -> Self { 
+        CNROM {
+            prg_offset,
+            prg_len,
+            chr_offset,
+            chr_len: chr_len.max(0x2000),
+            chr_bank: 0,
+            mirroring,
+        } 
+    }
+}
+
+impl fmt::Display for CNROM {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CNROM")
+    }
+}
+
+impl Mapper for CNROM {
+    fn get_mirroring(&self) -> Mirroring { self.mirroring }
+
+    fn read_chr(&self, rom: *const u8, addr: u16) -> u8 { 
+        match self.chr_rom_address(addr) {
+            Some(addr) => unsafe { *(rom.wrapping_add(self.chr_offset + addr)) },
+            None => 0
+        }
+    }
+
+    fn chr_rom_address(&self, addr: u16) -> Option<usize> {
+        Some((self.chr_bank as usize * 0x2000 + addr as usize) % self.chr_len)
+    }
+
+    fn read_prg(&self, rom: *const u8, addr: u16) -> u8 { 
+        match addr {
+            0x8000..=0xFFFF => match self.prg_rom_address(addr) {
+                Some(addr) => unsafe { *(rom.wrapping_add(self.prg_offset + addr)) },
+                None => 0
+            },
+            _ => 0
+        }
+    }
+
+    fn prg_rom_address(&self, addr: u16) -> Option<usize> {
+        if addr < 0x8000 { return None }
+        let mut addr = addr - 0x8000;
+        if self.prg_len == 0x4000 && addr >= 0x4000 { 
+            addr = addr % 0x4000; 
+        }
+        Some(addr as usize)
+    }
+
+    fn write_prg(&mut self, addr: u16, val: u8) { 
+        match addr {
+            0x8000..=0xFFFF => {
+                self.chr_bank = val;
+            },
+            _ => () 
+        }
+    }
+
+    fn write_chr(&mut self, _: u16, _: u8) {}
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        // Before version 14 the bank was stored as its CHR offset.
+        self.chr_bank = if r.version() >= 14 { r.read_u8()? } else { ((r.read_u16()? & 0x6000) >> 13) as u8 };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_round_trip() {
+        let mut cnrom = CNROM::new(0x8000, 0x8000, 0x10, 0x8010, Mirroring::Vertical);
+        cnrom.write_prg(0x8000, 2);
+        let mut w = StateWriter::new();
+        cnrom.save_state(&mut w);
+        let state = w.into_inner();
+
+        cnrom.write_prg(0x8000, 1);
+        cnrom.load_state(&mut StateReader::new(&state)).unwrap();
+        assert_eq!(cnrom.chr_bank, 2);
+        assert_eq!(cnrom.chr_rom_address(0x0123), Some(0x4123));
+    }
+
+    #[test]
+    fn oversized_chr() {
+        let mut cnrom = CNROM::new(0x8000, 0x200000, 0x10, 0x8010, Mirroring::Vertical);
+        cnrom.write_prg(0x8000, 0xFF);
+        assert_eq!(cnrom.chr_rom_address(0x1FFF), Some(0x1FFFFF));
+        cnrom.write_prg(0x8000, 0x80);
+        assert_eq!(cnrom.chr_rom_address(0x0000), Some(0x100000));
+    }
+
+    #[test]
+    fn banks_wrap_at_chr_size() {
+        let mut cnrom = CNROM::new(0x8000, 0x8000, 0x10, 0x8010, Mirroring::Vertical);
+        cnrom.write_prg(0x8000, 5);
+        assert_eq!(cnrom.chr_rom_address(0x0123), Some(0x2123));
+    }
+
+    #[test]
+    fn bank_from_version_13_offset() {
+        let mut w = StateWriter::new();
+        w.write_u16(0x6000);
+        let state = w.into_inner();
+        let mut r = StateReader::new(&state);
+        r.set_version(13);
+        let mut cnrom = CNROM::new(0x8000, 0x8000, 0x10, 0x8010, Mirroring::Vertical);
+        cnrom.load_state(&mut r).unwrap();
+        assert_eq!(cnrom.chr_bank, 3);
+        assert_eq!(cnrom.chr_rom_address(0x0000), Some(0x6000));
+    }
+}
@@ -0,0 +1,174 @@
+use super::{SaveMedia, SaveKind};
+use crate::state::{StateWriter, StateReader};
+
+// Serial EEPROMs found on Bandai boards (Datach, Famicom Jump II, ...).
+// 24C02 talks regular I2C (device byte, word address, MSB first), the X24C01 skips
+// the device byte and sends a 7 bit address plus R/W, LSB first.
+// https://www.nesdev.org/wiki/Bandai_FCG_board#Serial_EEPROM
+#[derive(PartialEq, Clone, Copy)]
+pub enum EepromKind {
+    C01,
+    C02,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum Phase {
+    Idle,
+    Device,
+    Word,
+    Write,
+    Read,
+}
+
+pub struct Eeprom {
+    kind: EepromKind,
+    data: Vec<u8>,
+    dirty: bool,
+    phase: Phase,
+    shift: u8,
+    bit: u8,
+    addr: usize,
+    slave_ack: bool,
+    scl: bool,
+    sda: bool,
+    out: bool,
+}
+
+impl Eeprom {
+    pub fn new(kind: EepromKind) -> Self {
+        let size = match kind { EepromKind::C01 => 0x80, EepromKind::C02 => 0x100 };
+        Eeprom {
+            kind,
+            data: vec![0xFF; size],
+            dirty: false,
+            phase: Phase::Idle,
+            shift: 0,
+            bit: 0,
+            addr: 0,
+            slave_ack: false,
+            scl: false,
+            sda: false,
+            out: true,
+        }
+    }
+
+    pub fn read(&self) -> bool { self.out }
+
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.data);
+        w.write_u8(self.phase as u8);
+        w.write_u8(self.shift);
+        w.write_u8(self.bit);
+        w.write_usize(self.addr);
+        w.write_bool(self.slave_ack);
+        w.write_bool(self.scl);
+        w.write_bool(self.sda);
+        w.write_bool(self.out);
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        r.read_bytes(&mut self.data)?;
+        self.phase = match r.read_u8()? {
+            0 => Phase::Idle,
+            1 => Phase::Device,
+            2 => Phase::Word,
+            3 => Phase::Write,
+            _ => Phase::Read,
+        };
+        self.shift = r.read_u8()?;
+        self.bit = r.read_u8()?;
+        self.addr = r.read_usize()? % self.data.len();
+        self.slave_ack = r.read_bool()?;
+        self.scl = r.read_bool()?;
+        self.sda = r.read_bool()?;
+        self.out = r.read_bool()?;
+        Ok(())
+    }
+
+    pub fn write(&mut self, scl: bool, sda: bool) {
+        if self.scl && scl && self.sda && !sda {
+            self.phase = if self.kind == EepromKind::C01 { Phase::Word } else { Phase::Device };
+            self.bit = 0;
+            self.shift = 0;
+            self.slave_ack = false;
+        } else if self.scl && scl && !self.sda && sda {
+            self.phase = Phase::Idle;
+        } else if !self.scl && scl {
+            self.rise(sda);
+        } else if self.scl && !scl {
+            self.fall();
+        }
+        self.scl = scl;
+        self.sda = sda;
+    }
+
+    fn rise(&mut self, sda: bool) {
+        if self.phase == Phase::Idle { return }
+        if self.bit == 8 {
+            self.bit = 0;
+            if self.slave_ack {
+                self.slave_ack = false;
+                if self.phase == Phase::Read { self.shift = self.data[self.addr]; }
+            } else if sda {
+                self.phase = Phase::Idle;
+            } else {
+                self.addr = (self.addr + 1) % self.data.len();
+                self.shift = self.data[self.addr];
+            }
+            return
+        }
+        self.bit += 1;
+        if self.phase == Phase::Read { return }
+        self.shift = match self.kind {
+            EepromKind::C01 => (self.shift >> 1) | (sda as u8) << 7,
+            EepromKind::C02 => (self.shift << 1) | sda as u8,
+        };
+        if self.bit == 8 { self.received(); }
+    }
+
+    fn fall(&mut self) {
+        self.out = if self.bit == 8 && self.slave_ack {
+            false
+        } else if self.phase == Phase::Read && self.bit < 8 && !self.slave_ack {
+            let bit = match self.kind { EepromKind::C01 => self.bit, EepromKind::C02 => 7 - self.bit };
+            (self.shift >> bit) & 1 == 1
+        } else {
+            true
+        };
+    }
+
+    fn received(&mut self) {
+        let byte = self.shift;
+        self.slave_ack = true;
+        self.phase = match (self.kind, self.phase) {
+            (EepromKind::C02, Phase::Device) if byte & 0xF0 != 0xA0 => { self.slave_ack = false; Phase::Idle },
+            (EepromKind::C02, Phase::Device) => if byte & 1 == 1 { Phase::Read } else { Phase::Word },
+            (EepromKind::C01, Phase::Word) => {
+                self.addr = (byte & 0x7F) as usize;
+                if byte & 0x80 != 0 { Phase::Read } else { Phase::Write }
+            },
+            (_, Phase::Word) => { self.addr = byte as usize; Phase::Write },
+            (_, Phase::Write) => {
+                if self.data[self.addr] != byte { self.dirty = true; }
+                self.data[self.addr] = byte;
+                self.addr = (self.addr + 1) % self.data.len();
+                Phase::Write
+            },
+            (_, phase) => phase,
+        };
+    }
+}
+
+impl SaveMedia for Eeprom {
+    fn kind(&self) -> SaveKind { SaveKind::Eeprom }
+    fn data(&self) -> &[u8] { &self.data }
+
+    fn load(&mut self, data: &[u8]) {
+        let len = data.len().min(self.data.len());
+        self.data[..len].copy_from_slice(&data[..len]);
+        self.dirty = false;
+    }
+
+    fn is_dirty(&self) -> bool { self.dirty }
+    fn clear_dirty(&mut self) { self.dirty = false; }
+}
@@ -0,0 +1,234 @@
+use std::fmt;
+use super::*;
+
+const PRG_CHIP_SIZE: usize = 0x20000;
+const PRG_BANK_SIZE_32: usize = 0x8000;
+const PRG_BANK_SIZE_16: usize = 0x4000;
+
+// Nintendo World Championships 1990, an MMC1 with two 128KB PRG chips and a timer.
+// The timer counts CPU cycles while bit 4 of CHR register 0 is clear and fires an IRQ at
+// $20000000 + (DIP << 25), every DIP step adds ~18.75 seconds to the 5 minutes.
+// https://www.nesdev.org/wiki/NES-EVENT
+pub struct EVENT {
+    sr: u8,
+    control: u8,
+    chr_reg: u8,
+    prg_reg: u8,
+    init: u8,
+    prg_ram: Wram,
+    chr_ram: [u8; 0x2000],
+    prg_offset: usize,
+    prg_len: usize,
+    mirroring: Mirroring,
+    counter: usize,
+    dip: u8,
+    irq: bool,
+}
+
+impl EVENT {
+    pub fn new(prg_len: usize, _: usize, prg_offset: usize, _: usize, mirroring: Mirroring) -> Self {
+        EVENT {
+            sr: 0x10,
+            control: 0x0C,
+            chr_reg: 0,
+            prg_reg: 0,
+            init: 0,
+            prg_ram: Wram::new(0x2000),
+            chr_ram: [0; 0x2000],
+            prg_offset,
+            prg_len,
+            mirroring,
+            counter: 0,
+            dip: 0,
+            irq: false,
+        }
+    }
+
+    fn target(&self) -> usize {
+        0x20000000 + ((self.dip as usize) << 25)
+    }
+
+    fn set_reg(&mut self, reg: u16, value: u8) {
+        match reg {
+            0 => {
+                self.control = value;
+                self.mirroring = match value & 0x03 {
+                    0 => Mirroring::OneScreenLower,
+                    1 => Mirroring::OneScreenUpper,
+                    2 => Mirroring::Vertical,
+                    _ => Mirroring::Horizontal,
+                };
+            },
+            1 => {
+                self.chr_reg = value;
+                // PRG stays on the first 32KB until the timer bit has been set and cleared once.
+                match (self.init, value & 0x10 != 0) {
+                    (0, true) => self.init = 1,
+                    (1, false) => self.init = 2,
+                    _ => ()
+                }
+                if value & 0x10 != 0 {
+                    self.counter = 0;
+                    self.irq = false;
+                }
+            },
+            2 => (),
+            _ => self.prg_reg = value & 0x1F,
+        }
+    }
+
+    fn update_sr(&mut self, value: u8, addr: u16) {
+        if value & 0x80 != 0 {
+            self.sr = 0x10;
+            self.control |= 0x0C;
+            return
+        }
+        let full = self.sr & 1 == 1;
+        self.sr = (self.sr >> 1) | (value & 1) << 4;
+        if full {
+            self.set_reg((addr - 0x8000) / 0x2000, self.sr);
+            self.sr = 0x10;
+        }
+    }
+
+    fn prg_addr(&self, addr: usize) -> usize {
+        if self.init < 2 { return addr }
+        if self.chr_reg & 0x08 == 0 {
+            return ((self.chr_reg as usize >> 1) & 0x03) * PRG_BANK_SIZE_32 + addr
+        }
+        let bank = (self.prg_reg & 0x07) as usize;
+        PRG_CHIP_SIZE + match ((self.control >> 2) & 0x03, addr >= PRG_BANK_SIZE_16) {
+            (0 | 1, _) => (bank & 0x06) * PRG_BANK_SIZE_16 + addr,
+            (2, false) => addr,
+            (2, true) => bank * PRG_BANK_SIZE_16 + addr - PRG_BANK_SIZE_16,
+            (_, false) => bank * PRG_BANK_SIZE_16 + addr,
+            (_, true) => 7 * PRG_BANK_SIZE_16 + addr - PRG_BANK_SIZE_16,
+        }
+    }
+}
+
+impl fmt::Display for EVENT {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NES-EVENT")
+    }
+}
+
+impl Mapper for EVENT {
+    fn get_mirroring(&self) -> Mirroring { self.mirroring }
+
+    fn read_prg(&self, rom: *const u8, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF if self.prg_reg & 0x10 == 0 => self.prg_ram.read((addr - 0x6000) as usize),
+            0x8000..=0xFFFF => match self.prg_rom_address(addr) {
+                Some(addr) => unsafe { *(rom.wrapping_add(self.prg_offset + addr)) },
+                None => 0
+            },
+            _ => 0
+        }
+    }
+
+    fn prg_rom_address(&self, addr: u16) -> Option<usize> {
+        if addr < 0x8000 { return None }
+        Some(self.prg_addr(addr as usize - 0x8000) % self.prg_len)
+    }
+
+    fn write_prg(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x6000..=0x7FFF if self.prg_reg & 0x10 == 0 => self.prg_ram.write((addr - 0x6000) as usize, val),
+            0x8000..=0xFFFF => self.update_sr(val, addr),
+            _ => ()
+        }
+    }
+
+    fn read_chr(&self, _: *const u8, addr: u16) -> u8 { self.chr_ram[addr as usize] }
+
+    fn write_chr(&mut self, addr: u16, val: u8) { self.chr_ram[addr as usize] = val; }
+
+    fn cpu_tick(&mut self, cycles: usize) {
+        if self.chr_reg & 0x10 != 0 { return }
+        self.counter += cycles;
+        if self.counter >= self.target() { self.irq = true; }
+    }
+
+    fn irq(&self) -> bool { self.irq }
+
+    fn save_media(&mut self) -> Option<&mut dyn SaveMedia> { Some(&mut self.prg_ram) }
+
+    // Bit 4 of the PRG register disables PRG-RAM, like on the MMC1B.
+    fn wram_protection(&self) -> Option<WramProtection> {
+        let enabled = self.prg_reg & 0x10 == 0;
+        Some(WramProtection { enabled, writable: enabled })
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.sr);
+        w.write_u8(self.control);
+        w.write_u8(self.chr_reg);
+        w.write_u8(self.prg_reg);
+        w.write_u8(self.init);
+        self.prg_ram.save_state(w);
+        w.write_bytes(&self.chr_ram);
+        w.write_u8(self.mirroring as u8);
+        w.write_usize(self.counter);
+        w.write_bool(self.irq);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.sr = r.read_u8()?;
+        self.control = r.read_u8()?;
+        self.chr_reg = r.read_u8()?;
+        self.prg_reg = r.read_u8()?;
+        self.init = r.read_u8()?;
+        self.prg_ram.load_state(r)?;
+        r.read_bytes(&mut self.chr_ram)?;
+        self.mirroring = Mirroring::from_u8(r.read_u8()?)?;
+        self.counter = r.read_usize()?;
+        self.irq = r.read_bool()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_reg(event: &mut EVENT, addr: u16, value: u8) {
+        for bit in 0..5 {
+            event.write_prg(addr, value >> bit & 1);
+        }
+    }
+
+    #[test]
+    fn state_round_trip() {
+        let mut event = EVENT::new(0x40000, 0, 0x10, 0, Mirroring::Vertical);
+        write_reg(&mut event, 0x8000, 0x0E);
+        write_reg(&mut event, 0xA000, 0x10);
+        write_reg(&mut event, 0xA000, 0x08); // Timer running, second PRG chip
+        write_reg(&mut event, 0xE000, 0x03);
+        event.write_prg(0x6000, 0x5A);
+        event.cpu_tick(0x1234);
+        event.write_prg(0x8000, 1); // Shift register half full
+        event.write_prg(0x8000, 0);
+        let mut w = StateWriter::new();
+        event.save_state(&mut w);
+        let state = w.into_inner();
+        let snapshot = |event: &EVENT| (
+            (event.sr, event.control, event.chr_reg, event.prg_reg, event.init),
+            event.prg_rom_address(0x8000),
+            event.get_mirroring() as u8,
+            event.prg_ram.read(0),
+            (event.counter, event.irq),
+        );
+        let saved = snapshot(&event);
+        assert_eq!(saved.0, (0x0C, 0x0E, 0x08, 0x03, 2));
+        assert_eq!(saved.1, Some(0x2C000));
+        assert_eq!(saved.4, (0x1234, false));
+
+        event.write_prg(0x8000, 0x80);
+        write_reg(&mut event, 0xA000, 0x10);
+        write_reg(&mut event, 0xE000, 0x00);
+        event.write_prg(0x6000, 0);
+        event.load_state(&mut StateReader::new(&state)).unwrap();
+        assert_eq!(snapshot(&event), saved);
+    }
+}
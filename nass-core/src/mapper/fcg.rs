@@ -0,0 +1,194 @@
+use std::fmt;
+use super::*;
+
+const PRG_BANK_SIZE_16: usize = 0x4000;
+const CHR_BANK_SIZE_1: usize = 0x400;
+
+// Bandai FCG-1/FCG-2 (registers at $6000) and LZ93D50 (registers at $8000, serial EEPROM).
+// https://www.nesdev.org/wiki/Bandai_FCG_board
+pub struct FCG {
+    chr_banks: [u8; 8],
+    prg_bank: u8,
+    chr_ram: [u8; 0x2000],
+    prg_offset: usize,
+    prg_len: usize,
+    chr_offset: usize,
+    chr_len: usize,
+    mirroring: Mirroring,
+    irq_enabled: bool,
+    irq_counter: u16,
+    irq_latch: u16,
+    irq: bool,
+    eeprom: Eeprom,
+}
+
+impl FCG {
+    pub fn new(prg_len: usize, chr_len: usize, prg_offset: usize, chr_offset: usize, mirroring: Mirroring, eeprom: EepromKind) -> Self {
+        FCG {
+            chr_banks: [0; 8],
+            prg_bank: 0,
+            chr_ram: [0; 0x2000],
+            prg_offset,
+            prg_len,
+            chr_offset,
+            chr_len,
+            mirroring,
+            irq_enabled: false,
+            irq_counter: 0,
+            irq_latch: 0,
+            irq: false,
+            eeprom: Eeprom::new(eeprom),
+        }
+    }
+
+    fn set_reg(&mut self, reg: u16, val: u8) {
+        match reg {
+            0x0..=0x7 => self.chr_banks[reg as usize] = val,
+            0x8 => self.prg_bank = val & 0x0F,
+            0x9 => self.mirroring = match val & 0x03 {
+                0 => Mirroring::Vertical,
+                1 => Mirroring::Horizontal,
+                2 => Mirroring::OneScreenLower,
+                _ => Mirroring::OneScreenUpper,
+            },
+            0xA => {
+                self.irq_enabled = val & 1 == 1;
+                self.irq_counter = self.irq_latch;
+                self.irq = false;
+            },
+            // FCG-1/2 write the counter directly, the LZ93D50 goes through the latch.
+            0xB => {
+                self.irq_latch = (self.irq_latch & 0xFF00) | val as u16;
+                self.irq_counter = (self.irq_counter & 0xFF00) | val as u16;
+            },
+            0xC => {
+                self.irq_latch = (self.irq_latch & 0x00FF) | (val as u16) << 8;
+                self.irq_counter = (self.irq_counter & 0x00FF) | (val as u16) << 8;
+            },
+            0xD => self.eeprom.write(val & 0x20 != 0, val & 0x40 != 0),
+            _ => ()
+        }
+    }
+}
+
+impl fmt::Display for FCG {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FCG")
+    }
+}
+
+impl Mapper for FCG {
+    fn get_mirroring(&self) -> Mirroring { self.mirroring }
+
+    fn read_prg(&self, rom: *const u8, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF => (self.eeprom.read() as u8) << 4,
+            0x8000..=0xFFFF => match self.prg_rom_address(addr) {
+                Some(addr) => unsafe { *(rom.wrapping_add(self.prg_offset + addr)) },
+                None => 0
+            },
+            _ => 0
+        }
+    }
+
+    fn prg_rom_address(&self, addr: u16) -> Option<usize> {
+        if addr < 0x8000 { return None }
+        let bank = if addr < 0xC000 { self.prg_bank as usize } else { (self.prg_len / PRG_BANK_SIZE_16).saturating_sub(1) };
+        Some((bank * PRG_BANK_SIZE_16 + (addr as usize) % PRG_BANK_SIZE_16) % self.prg_len)
+    }
+
+    fn write_prg(&mut self, addr: u16, val: u8) {
+        if addr >= 0x6000 { self.set_reg(addr & 0x0F, val); }
+    }
+
+    fn read_chr(&self, rom: *const u8, addr: u16) -> u8 {
+        match self.chr_rom_address(addr) {
+            Some(addr) => unsafe { *(rom.wrapping_add(self.chr_offset + addr)) },
+            None => self.chr_ram[addr as usize]
+        }
+    }
+
+    fn chr_rom_address(&self, addr: u16) -> Option<usize> {
+        if self.chr_len == 0 { return None }
+        let bank = self.chr_banks[addr as usize / CHR_BANK_SIZE_1] as usize;
+        Some((bank * CHR_BANK_SIZE_1 + (addr as usize) % CHR_BANK_SIZE_1) % self.chr_len)
+    }
+
+    fn write_chr(&mut self, addr: u16, val: u8) {
+        if self.chr_len == 0 { self.chr_ram[addr as usize] = val; }
+    }
+
+    fn cpu_tick(&mut self, cycles: usize) {
+        if !self.irq_enabled { return }
+        let (counter, wrapped) = self.irq_counter.overflowing_sub(cycles as u16);
+        if wrapped || counter == 0 { self.irq = true; }
+        self.irq_counter = counter;
+    }
+
+    fn irq(&self) -> bool { self.irq }
+
+    fn save_media(&mut self) -> Option<&mut dyn SaveMedia> { Some(&mut self.eeprom) }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.chr_banks);
+        w.write_u8(self.prg_bank);
+        w.write_bytes(&self.chr_ram);
+        w.write_u8(self.mirroring as u8);
+        w.write_bool(self.irq_enabled);
+        w.write_u16(self.irq_counter);
+        w.write_u16(self.irq_latch);
+        w.write_bool(self.irq);
+        self.eeprom.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        r.read_bytes(&mut self.chr_banks)?;
+        self.prg_bank = r.read_u8()?;
+        r.read_bytes(&mut self.chr_ram)?;
+        self.mirroring = Mirroring::from_u8(r.read_u8()?)?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_counter = r.read_u16()?;
+        self.irq_latch = r.read_u16()?;
+        self.irq = r.read_bool()?;
+        self.eeprom.load_state(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_round_trip() {
+        let mut fcg = FCG::new(0x40000, 0x40000, 0x10, 0x40010, Mirroring::Vertical, EepromKind::C02);
+        for reg in 0..8 {
+            fcg.write_prg(0x8000 + reg, 0x20 + reg as u8);
+        }
+        fcg.write_prg(0x8008, 0x07);
+        fcg.write_prg(0x8009, 0x01);
+        fcg.write_prg(0x800B, 0x34);
+        fcg.write_prg(0x800C, 0x12);
+        fcg.write_prg(0x800A, 0x01);
+        fcg.cpu_tick(0x30);
+        let mut w = StateWriter::new();
+        fcg.save_state(&mut w);
+        let state = w.into_inner();
+        let snapshot = |fcg: &FCG| (
+            fcg.chr_banks,
+            fcg.prg_rom_address(0x8000),
+            fcg.get_mirroring() as u8,
+            (fcg.irq_enabled, fcg.irq_counter, fcg.irq_latch, fcg.irq),
+        );
+        let saved = snapshot(&fcg);
+        assert_eq!(saved.1, Some(0x1C000));
+        assert_eq!(saved.3, (true, 0x1204, 0x1234, false));
+
+        fcg.write_prg(0x8000, 0);
+        fcg.write_prg(0x8008, 0);
+        fcg.write_prg(0x8009, 0x03);
+        fcg.cpu_tick(0x2000);
+        fcg.write_prg(0x800A, 0);
+        fcg.load_state(&mut StateReader::new(&state)).unwrap();
+        assert_eq!(snapshot(&fcg), saved);
+    }
+}
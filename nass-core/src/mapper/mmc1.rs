@@ -0,0 +1,436 @@
+use super::Mapper;
+use std::fmt;
+use crate::mapper::{Mirroring, SaveMedia, Wram, WramProtection};
+use crate::state::{StateWriter, StateReader};
+
+const PRG_BANK_SIZE_256: usize = 0x40000;
+const PRG_BANK_SIZE_32: usize = 0x8000;
+const PRG_BANK_SIZE_16: usize = 0x4000;
+const PRG_BANK_SIZE_8: usize = 0x2000; // PRG RAM bank size
+const CHR_BANK_SIZE_8: usize = 0x2000;
+const CHR_BANK_SIZE_4: usize = 0x1000;
+
+use ChrBanks::*;
+use BankType::*;
+
+#[derive(Clone, Copy, Debug)]
+enum BankType {
+    Fixed,
+    Switch(usize),
+    Null
+}
+
+impl BankType {
+    fn save_state(&self, w: &mut StateWriter) {
+        let (tag, bank) = match *self { Fixed => (0, 0), Switch(x) => (1, x), Null => (2, 0) };
+        w.write_u8(tag);
+        w.write_usize(bank);
+    }
+
+    fn load_state(r: &mut StateReader) -> Result<BankType, String> {
+        let tag = r.read_u8()?;
+        let bank = r.read_usize()?;
+        match tag {
+            0 => Ok(Fixed),
+            1 => Ok(Switch(bank)),
+            2 => Ok(Null),
+            _ => Err("MMC1: Unknown PRG bank in savestate.".to_string())
+        }
+    }
+}
+
+type PrgBanks = (BankType, BankType);
+
+#[derive(Clone, Copy, Debug)]
+enum ChrBanks {
+    Ram(usize, Option<usize>),
+    Rom(usize, Option<usize>) 
+}
+
+// MMC1 with 512K(PRG-ROM) was supported by re-using a line from the CHR banking controls. 
+// https://www.nesdev.org/wiki/MMC1
+pub struct MMC1 {
+    sr: u8,
+    is_variant: bool,
+    chr_addr: ChrBanks,
+    prg_rom_addr: PrgBanks,
+    prg_ram_addr: usize,
+    prg_area: usize,
+    prg_ram: Wram,
+    chr_ram: [u8; 0x20000], // 128KB (2**10 * 128)
+    prg_offset: usize, 
+    prg_rom_len: usize, 
+    chr_offset: usize, 
+    chr_rom_len: usize, 
+    mirroring: Mirroring,
+    written: bool, // A register write this CPU instruction, cleared by `cpu_tick`
+    wram_enabled: bool, // Bit 4 of the PRG bank register clear (MMC1B and later)
+}
+
+impl fmt::Display for MMC1 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MMC1")
+    }
+}
+
+impl MMC1 {
+    pub fn new(prg_rom_len: usize, chr_rom_len: usize, prg_offset: usize, chr_offset: usize, mirroring: Mirroring) -> Self { 
+        // (No CHR_ROM) or (CHR_ROM == 8) => Using 8KB variant
+        let is_rom = chr_rom_len != 0;
+        let chr_addr = if is_rom { Rom(0, None) } else { Ram(0, None) };
+        MMC1 {
+            sr: 0x10,
+            // SUROM/SXROM: 512KB of PRG-ROM needs the 256KB select line on the CHR registers.
+            is_variant: prg_rom_len > PRG_BANK_SIZE_256,
+            chr_addr,
+            prg_rom_addr: (Switch(0), Fixed),
+            prg_ram_addr: 0,
+            prg_area: 0,
+            mirroring,
+            prg_ram: Wram::new(0x8000),
+            chr_ram: [0; 0x20000], 
+            prg_offset,
+            prg_rom_len, 
+            chr_offset,
+            chr_rom_len, 
+            written: false,
+            wram_enabled: true,
+        } 
+    }
+
+    fn set_variant(&mut self, value: u8, ignore: bool, register: usize) {
+        let bank = (value & 1) as usize;
+
+        if register == 1 && !ignore {
+            match self.chr_addr {
+                Ram(_, b) => self.chr_addr = Ram(bank * CHR_BANK_SIZE_4, b),
+                Rom(_, b) => self.chr_addr = Rom(bank * CHR_BANK_SIZE_4, b),
+            } 
+        }
+        if register == 2 {
+            match self.chr_addr {
+                Ram(a, Some(_)) => self.chr_addr = Ram(a, Some(bank * CHR_BANK_SIZE_4)),
+                Rom(a, Some(_)) => self.chr_addr = Rom(a, Some(bank * CHR_BANK_SIZE_4)),
+                _ => (),
+            } 
+        }
+
+        let prg_bank = ((value & 0x0C) >> 2) as usize;
+        self.prg_ram_addr = prg_bank * PRG_BANK_SIZE_8;
+
+        // The 256 KB PRG bank selection applies to all the PRG area(0x6000 - 0xFFFF), including the supposedly "fixed" bank.
+        let prg_bank = ((value & 0x10) >> 4) as usize;
+        self.prg_area = prg_bank * PRG_BANK_SIZE_256;
+    }
+
+    fn set_reg(&mut self, reg: u16, value: u8) {
+        match reg {
+            0 => { // Control register
+                match value & 0x03 {
+                    0 => self.mirroring = Mirroring::OneScreenLower,
+                    1 => self.mirroring = Mirroring::OneScreenUpper,
+                    2 => self.mirroring = Mirroring::Vertical,
+                    3 => self.mirroring = Mirroring::Horizontal,
+                    _ => ()
+                };
+                // The PRG bank register keeps its value across mode changes.
+                let bank = match self.prg_rom_addr {
+                    (Switch(x), _) | (_, Switch(x)) => x,
+                    _ => 0,
+                };
+                match (value & 0x0C) >> 2 {
+                    0 | 1 => self.prg_rom_addr = (Switch(bank & !(PRG_BANK_SIZE_32 - 1)), Null),
+                    2 => self.prg_rom_addr = (Fixed, Switch(bank)),
+                    3 => self.prg_rom_addr = (Switch(bank), Fixed),
+                    _ => ()
+                }
+                match (value & 0x10) >> 4 {
+                    0 => self.chr_addr = {
+                        if self.chr_rom_len != 0 {
+                            Rom(0, None)
+                        } else {
+                            Ram(0, None)
+                        }
+                    },
+                    1 => self.chr_addr = {
+                        if self.chr_rom_len != 0 {
+                            Rom(0, Some(0x1000))
+                        } else {
+                            Ram(0, Some(0x1000))
+                        }
+                    },
+                    _ => ()
+                }
+            },
+            1 => { // CHR bank 0 register
+                if !self.is_variant {
+                    match self.chr_addr {
+                        Rom(_, None) => {
+                            let bank = (value & 0x1E) as usize;
+                            self.chr_addr = Rom(bank * CHR_BANK_SIZE_8, None);
+                        },
+                        Ram(_, None) => {
+                            let bank = (value & 0x1E) as usize;
+                            self.chr_addr = Ram(bank * CHR_BANK_SIZE_8, None);
+                        },
+                        Rom(_, b) => {
+                            let bank  = (value & 0x1F) as usize;
+                            self.chr_addr = Rom(bank * CHR_BANK_SIZE_4, b);
+                        },
+                        Ram(_, b) => {
+                            let bank  = (value & 0x1F) as usize;
+                            self.chr_addr = Ram(bank * CHR_BANK_SIZE_4, b);
+                        },
+                    }
+                } else { // High Address lines are used for different purposes
+                    match self.chr_addr {
+                        Ram(_, None) | Rom(_, None) => self.set_variant(value, true, 1),
+                        _ => self.set_variant(value, false, 1),
+                    }
+                }
+            },
+            2 => { // CHR bank 1 register
+                if !self.is_variant {
+                    match self.chr_addr {
+                        Rom(a, Some(_)) => {
+                            let bank  = (value & 0x1F) as usize;
+                            self.chr_addr = Rom(a, Some(bank * CHR_BANK_SIZE_4));
+                        },
+                        Ram(a, Some(_)) => {
+                            let bank  = (value & 0x1F) as usize;
+                            self.chr_addr = Ram(a, Some(bank * CHR_BANK_SIZE_4));
+                        },
+                        _ => ()
+                    }
+                } else {
+                    match self.chr_addr {
+                        Ram(_, Some(_)) | Rom(_, Some(_)) => self.set_variant(value, false, 2),
+                        _ => (),
+                    }
+                }
+            }, 
+            3 => { // PRG bank register
+                self.wram_enabled = value & 0x10 == 0;
+                match self.prg_rom_addr {
+                    (_, Null) => {
+                        let bank = (value & 0x0E) as usize;
+                        self.prg_rom_addr = (Switch(bank * PRG_BANK_SIZE_32), Null);
+                    },
+                    (a, Switch(_)) => {
+                        let bank  = (value & 0x0F) as usize;
+                        self.prg_rom_addr = (a, Switch(bank * PRG_BANK_SIZE_16));
+                    },
+                    (Switch(_), b) => {
+                        let bank  = (value & 0x0F) as usize;
+                        self.prg_rom_addr = (Switch(bank * PRG_BANK_SIZE_16), b);
+                    }
+                    _ => ()
+                }
+            },
+            _ => { panic!("MMC1: Unknown register."); }
+        };
+    }
+
+    // Bit 7 resets the shift register (and goes back to PRG mode 3), otherwise bit 0 is shifted
+    // in from the top and the fifth write, when the marker bit reaches bit 0, loads a register.
+    fn update_sr(&mut self, value: u8, addr: u16) {
+        if value & 0x80 != 0 {
+            self.sr = 0x10;
+            self.prg_rom_addr = match self.prg_rom_addr {
+                (Switch(x), _) | (_, Switch(x)) => (Switch(x), Fixed),
+                _ => (Switch(0), Fixed),
+            };
+            return
+        }
+        let full = self.sr & 1 == 1;
+        self.sr = (self.sr >> 1) | (value & 0x1) << 4;
+        if full {
+            let reg = ((addr & 0xF000) >> 13) - 4;
+            self.set_reg(reg, self.sr);
+            self.sr = 0x10;
+        }
+    }
+}
+
+impl Mapper for MMC1 {
+    fn get_mirroring(&self) -> Mirroring { self.mirroring }
+
+    fn read_prg(&self, rom: *const u8, addr: u16) -> u8 { 
+        if addr < 0x6000 { return 0 }
+        if (0x6000..=0x7FFF).contains(&addr) {
+            if !self.wram_enabled { return 0 }
+            return self.prg_ram.read((addr -  0x6000) as usize + self.prg_ram_addr)
+        }
+        match self.prg_rom_address(addr) {
+            Some(addr) => unsafe { *(rom.wrapping_add(self.prg_offset + addr)) },
+            None => 0
+        }
+    }
+
+    fn prg_rom_address(&self, addr: u16) -> Option<usize> {
+        if addr < 0x8000 { return None }
+        let mut addr = addr as usize - 0x8000;
+        if self.prg_rom_len == 0x4000 && addr >= 0x4000 { return Some(addr % 0x4000) }
+
+        // The "fixed" bank is the last one of the selected 256KB area.
+        let area_len = self.prg_rom_len.min(PRG_BANK_SIZE_256);
+        match self.prg_rom_addr {
+            (_, Switch(x)) if addr >= 0x4000 => addr = addr - PRG_BANK_SIZE_16 + x + self.prg_area,
+            (_, Fixed) if addr >= 0x4000 => addr += area_len - 2*PRG_BANK_SIZE_16 + self.prg_area,
+            (Switch(x), _) => addr += x + self.prg_area,
+            (Fixed,     _) => addr += self.prg_area,
+            _  => panic!("MMC1: (Null, Null)")
+        }
+        // Bank numbers past the end of smaller ROMs wrap, like the unconnected lines do.
+        Some(addr % self.prg_rom_len)
+    }
+
+    fn write_prg(&mut self, addr: u16, val: u8) { 
+        match addr {
+            0x6000..=0x7FFF if self.wram_enabled => self.prg_ram.write((addr -  0x6000) as usize + self.prg_ram_addr, val),
+            // Writes on back-to-back cycles only take the first, the second write of a
+            // read-modify-write instruction is ignored. https://www.nesdev.org/wiki/MMC1#Consecutive-cycle_writes
+            0x8000..=0xFFFF if !std::mem::replace(&mut self.written, true) => self.update_sr(val, addr),
+            _ => ()
+        }
+    }
+
+    fn cpu_tick(&mut self, _: usize) {
+        self.written = false;
+    }
+
+    fn wram_protection(&self) -> Option<WramProtection> {
+        Some(WramProtection { enabled: self.wram_enabled, writable: self.wram_enabled })
+    }
+
+    fn read_chr(&self, rom: *const u8, addr: u16) -> u8 {
+        match self.chr_addr {
+            Ram(_, Some(x)) if addr >= 0x1000 => self.chr_ram[addr as usize + x - CHR_BANK_SIZE_4],
+            Ram(x, _) => self.chr_ram[addr as usize + x],
+            Rom(_, _) => match self.chr_rom_address(addr) {
+                Some(addr) => unsafe { *(rom.wrapping_add(self.chr_offset + addr)) },
+                None => 0
+            },
+        }
+    }
+
+    fn chr_rom_address(&self, addr: u16) -> Option<usize> {
+        match self.chr_addr {
+            Rom(_, Some(x)) if addr >= 0x1000 => Some((addr as usize + x - CHR_BANK_SIZE_4) % self.chr_rom_len),
+            Rom(x, _) => Some((addr as usize + x) % self.chr_rom_len),
+            Ram(_, _) => None,
+        }
+    }
+
+    fn write_chr(&mut self, addr: u16, val: u8) { 
+        match self.chr_addr {
+            Ram(_, Some(x)) if addr >= 0x1000 => self.chr_ram[addr as usize + x - CHR_BANK_SIZE_4] = val,
+            Ram(x, _) => self.chr_ram[addr as usize + x] = val,
+            _ => (),
+        }
+    }
+
+    fn save_media(&mut self) -> Option<&mut dyn SaveMedia> { Some(&mut self.prg_ram) }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.sr);
+        let (is_rom, low, high) = match self.chr_addr { Ram(a, b) => (false, a, b), Rom(a, b) => (true, a, b) };
+        w.write_bool(is_rom);
+        w.write_usize(low);
+        w.write_bool(high.is_some());
+        w.write_usize(high.unwrap_or(0));
+        self.prg_rom_addr.0.save_state(w);
+        self.prg_rom_addr.1.save_state(w);
+        w.write_usize(self.prg_ram_addr);
+        w.write_usize(self.prg_area);
+        self.prg_ram.save_state(w);
+        w.write_bytes(&self.chr_ram);
+        w.write_u8(self.mirroring as u8);
+        w.write_bool(self.wram_enabled);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.sr = r.read_u8()?;
+        let is_rom = r.read_bool()?;
+        let low = r.read_usize()?;
+        let high = if r.read_bool()? { Some(r.read_usize()?) } else { r.read_usize()?; None };
+        self.chr_addr = if is_rom { Rom(low, high) } else { Ram(low, high) };
+        self.prg_rom_addr = (BankType::load_state(r)?, BankType::load_state(r)?);
+        self.prg_ram_addr = r.read_usize()?;
+        self.prg_area = r.read_usize()?;
+        self.prg_ram.load_state(r)?;
+        r.read_bytes(&mut self.chr_ram)?;
+        self.mirroring = Mirroring::from_u8(r.read_u8()?)?;
+        self.wram_enabled = if r.version() >= 13 { r.read_bool()? } else { true };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_reg(mmc1: &mut MMC1, addr: u16, value: u8) {
+        for bit in 0..5 {
+            mmc1.write_prg(addr, value >> bit & 1);
+            mmc1.cpu_tick(1);
+        }
+    }
+
+    #[test]
+    fn state_round_trip() {
+        let mut mmc1 = MMC1::new(0x40000, 0x20000, 0x10, 0x40010, Mirroring::Horizontal);
+        write_reg(&mut mmc1, 0x8000, 0x1E); // Vertical, PRG mode 3, 4KB CHR banks
+        write_reg(&mut mmc1, 0xA000, 0x03);
+        write_reg(&mut mmc1, 0xC000, 0x11);
+        mmc1.write_prg(0x6000, 0x5A);
+        write_reg(&mut mmc1, 0xE000, 0x15); // PRG bank 5, WRAM disabled
+        mmc1.write_prg(0x8000, 1); // Shift register half full
+        mmc1.cpu_tick(1);
+        let mut w = StateWriter::new();
+        mmc1.save_state(&mut w);
+        let state = w.into_inner();
+        let snapshot = |mmc1: &MMC1| (
+            mmc1.sr,
+            mmc1.prg_rom_address(0x8000),
+            mmc1.prg_rom_address(0xC000),
+            mmc1.chr_rom_address(0x0000),
+            mmc1.chr_rom_address(0x1000),
+            mmc1.get_mirroring() as u8,
+            mmc1.wram_enabled,
+            mmc1.prg_ram.read(0),
+        );
+        let saved = snapshot(&mmc1);
+        assert_eq!(saved, (0x18, Some(0x14000), Some(0x3C000), Some(0x3000), Some(0x11000), Mirroring::Vertical as u8, false, 0x5A));
+
+        mmc1.write_prg(0x8000, 0x80);
+        mmc1.cpu_tick(1);
+        write_reg(&mut mmc1, 0x8000, 0x03);
+        write_reg(&mut mmc1, 0xA000, 0x00);
+        write_reg(&mut mmc1, 0xE000, 0x02);
+        mmc1.write_prg(0x6000, 0);
+        mmc1.load_state(&mut StateReader::new(&state)).unwrap();
+        assert_eq!(snapshot(&mmc1), saved);
+    }
+
+    // SUROM: bit 4 of the CHR bank register picks the 256KB half, fixed bank included.
+    #[test]
+    fn surom_prg_halves() {
+        let mut mmc1 = MMC1::new(0x80000, 0, 0x10, 0x80010, Mirroring::Horizontal);
+        write_reg(&mut mmc1, 0x8000, 0x0C); // PRG mode 3, 8KB CHR bank
+        write_reg(&mut mmc1, 0xE000, 0x03);
+        assert_eq!((mmc1.prg_rom_address(0x8000), mmc1.prg_rom_address(0xFFFF)), (Some(0x0C000), Some(0x3FFFF)));
+        write_reg(&mut mmc1, 0xA000, 0x10);
+        assert_eq!((mmc1.prg_rom_address(0x8000), mmc1.prg_rom_address(0xFFFF)), (Some(0x4C000), Some(0x7FFFF)));
+        write_reg(&mut mmc1, 0x8000, 0x08); // PRG mode 2, the first bank of the half fixed at $8000
+        assert_eq!((mmc1.prg_rom_address(0x8000), mmc1.prg_rom_address(0xC000)), (Some(0x40000), Some(0x4C000)));
+    }
+
+    #[test]
+    fn prg_banks_wrap_at_prg_size() {
+        let mut mmc1 = MMC1::new(0x20000, 0x2000, 0x10, 0x20010, Mirroring::Horizontal);
+        write_reg(&mut mmc1, 0xE000, 0x0F);
+        assert_eq!(mmc1.prg_rom_address(0x8000), Some(0x1C000));
+        assert_eq!(mmc1.prg_rom_address(0xC000), Some(0x1C000));
+    }
+}
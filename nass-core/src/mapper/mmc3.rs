@@ -0,0 +1,283 @@
+use std::fmt;
+use super::*;
+
+const PRG_BANK_SIZE_8: usize = 0x2000;
+const CHR_BANK_SIZE_1: usize = 0x400;
+// A12 has to stay low for this many M2 cycles before a rise clocks the counter.
+pub const A12_FILTER_M2: usize = 3;
+
+// Sharp MMC3B/MMC3C reload and clock on the same edge, NEC MMC3A (and MMC3B variants)
+// only fire when the counter got to 0 by decrementing or by an explicit reload.
+#[derive(PartialEq, Clone, Copy)]
+pub enum IrqRevision {
+    New,
+    Old,
+}
+
+// https://www.nesdev.org/wiki/MMC3
+pub struct MMC3 {
+    registers: [u8; 8],
+    bank_select: u8,
+    prg_ram: Wram,
+    prg_ram_control: u8, // $A001: bit 7 enables the chip, bit 6 protects it from writes
+    chr_ram: [u8; 0x2000],
+    prg_offset: usize,
+    prg_len: usize,
+    chr_offset: usize,
+    chr_len: usize,
+    mirroring: Mirroring,
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq: bool,
+    a12: bool,
+    a12_low_dots: usize,
+    a12_filter: usize,
+    revision: IrqRevision,
+}
+
+impl MMC3 {
+    pub fn new(prg_len: usize, chr_len: usize, prg_offset: usize, chr_offset: usize, mirroring: Mirroring) -> Self {
+        MMC3 {
+            registers: [0, 2, 4, 5, 6, 7, 0, 1],
+            bank_select: 0,
+            prg_ram: Wram::new(0x2000),
+            prg_ram_control: 0x80,
+            chr_ram: [0; 0x2000],
+            prg_offset,
+            prg_len,
+            chr_offset,
+            chr_len,
+            mirroring,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq: false,
+            a12: false,
+            a12_low_dots: 0,
+            a12_filter: A12_FILTER_M2,
+            revision: IrqRevision::New,
+        }
+    }
+
+    fn prg_bank(&self, addr: u16) -> usize {
+        // NES 2.0 sizes can leave less than two banks, the fixed ones then fold onto bank 0.
+        let banks = (self.prg_len / PRG_BANK_SIZE_8).max(1);
+        let last = banks - 1;
+        let swap = self.bank_select & 0x40 != 0;
+        let bank = match (addr - 0x8000) / 0x2000 {
+            0 => if swap { last.saturating_sub(1) } else { self.registers[6] as usize },
+            1 => self.registers[7] as usize,
+            2 => if swap { self.registers[6] as usize } else { last.saturating_sub(1) },
+            _ => last,
+        };
+        bank % banks
+    }
+
+    fn chr_addr(&self, addr: u16) -> usize {
+        let addr = (addr as usize) ^ if self.bank_select & 0x80 != 0 { 0x1000 } else { 0 };
+        let bank = match addr / CHR_BANK_SIZE_1 {
+            0 | 1 => (self.registers[0] & 0xFE) as usize + addr / CHR_BANK_SIZE_1,
+            2 | 3 => (self.registers[1] & 0xFE) as usize + addr / CHR_BANK_SIZE_1 - 2,
+            n => self.registers[n - 2] as usize,
+        };
+        bank * CHR_BANK_SIZE_1 + addr % CHR_BANK_SIZE_1
+    }
+
+    fn clock_irq(&mut self) {
+        let before = self.irq_counter;
+        let reload = self.irq_reload;
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        } else {
+            self.irq_counter -= 1;
+        }
+        let fire = match self.revision {
+            IrqRevision::New => self.irq_counter == 0,
+            IrqRevision::Old => self.irq_counter == 0 && (before != 0 || reload),
+        };
+        if fire && self.irq_enabled {
+            self.irq = true;
+        }
+    }
+}
+
+impl fmt::Display for MMC3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MMC3")
+    }
+}
+
+impl Mapper for MMC3 {
+    fn get_mirroring(&self) -> Mirroring { self.mirroring }
+
+    fn read_prg(&self, rom: *const u8, addr: u16) -> u8 {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_control & 0x80 != 0 => self.prg_ram.read((addr - 0x6000) as usize),
+            0x8000..=0xFFFF => match self.prg_rom_address(addr) {
+                Some(addr) => unsafe { *(rom.wrapping_add(self.prg_offset + addr)) },
+                None => 0
+            },
+            _ => 0
+        }
+    }
+
+    fn prg_rom_address(&self, addr: u16) -> Option<usize> {
+        if addr < 0x8000 { return None }
+        Some((self.prg_bank(addr) * PRG_BANK_SIZE_8 + (addr as usize) % PRG_BANK_SIZE_8) % self.prg_len)
+    }
+
+    fn write_prg(&mut self, addr: u16, val: u8) {
+        match (addr, addr & 1) {
+            (0x6000..=0x7FFF, _) => if self.prg_ram_control & 0xC0 == 0x80 { self.prg_ram.write((addr - 0x6000) as usize, val) },
+            (0x8000..=0x9FFF, 0) => self.bank_select = val,
+            (0x8000..=0x9FFF, _) => self.registers[(self.bank_select & 0x07) as usize] = val,
+            (0xA000..=0xBFFF, 0) => if self.mirroring != Mirroring::FourScreen {
+                self.mirroring = if val & 1 == 0 { Mirroring::Vertical } else { Mirroring::Horizontal };
+            },
+            (0xA000..=0xBFFF, _) => self.prg_ram_control = val,
+            (0xC000..=0xDFFF, 0) => self.irq_latch = val,
+            (0xC000..=0xDFFF, _) => { self.irq_counter = 0; self.irq_reload = true; },
+            (0xE000..=0xFFFF, 0) => { self.irq_enabled = false; self.irq = false; },
+            (0xE000..=0xFFFF, _) => self.irq_enabled = true,
+            _ => ()
+        }
+    }
+
+    fn read_chr(&self, rom: *const u8, addr: u16) -> u8 {
+        match self.chr_rom_address(addr) {
+            Some(addr) => unsafe { *(rom.wrapping_add(self.chr_offset + addr)) },
+            None => self.chr_ram[addr as usize]
+        }
+    }
+
+    fn chr_rom_address(&self, addr: u16) -> Option<usize> {
+        if self.chr_len == 0 { None } else { Some(self.chr_addr(addr) % self.chr_len) }
+    }
+
+    fn write_chr(&mut self, addr: u16, val: u8) {
+        if self.chr_len == 0 {
+            self.chr_ram[addr as usize] = val;
+        }
+    }
+
+    fn ppu_bus(&mut self, addr: u16) {
+        let a12 = addr & 0x1000 != 0;
+        if a12 && !self.a12 && self.a12_low_dots >= 3 * self.a12_filter {
+            self.clock_irq();
+        }
+        self.a12_low_dots = if a12 { 0 } else { self.a12_low_dots.saturating_add(1) };
+        self.a12 = a12;
+    }
+
+    fn irq(&self) -> bool { self.irq }
+
+    fn set_irq_options(&mut self, a12_filter: usize, old_revision: bool) {
+        self.a12_filter = a12_filter;
+        self.revision = if old_revision { IrqRevision::Old } else { IrqRevision::New };
+    }
+
+    fn save_media(&mut self) -> Option<&mut dyn SaveMedia> { Some(&mut self.prg_ram) }
+
+    fn wram_protection(&self) -> Option<WramProtection> {
+        let enabled = self.prg_ram_control & 0x80 != 0;
+        Some(WramProtection { enabled, writable: enabled && self.prg_ram_control & 0x40 == 0 })
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.registers);
+        w.write_u8(self.bank_select);
+        self.prg_ram.save_state(w);
+        w.write_bytes(&self.chr_ram);
+        w.write_u8(self.mirroring as u8);
+        w.write_u8(self.irq_latch);
+        w.write_u8(self.irq_counter);
+        w.write_bool(self.irq_reload);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq);
+        w.write_bool(self.a12);
+        w.write_usize(self.a12_low_dots);
+        w.write_u8(self.prg_ram_control);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        r.read_bytes(&mut self.registers)?;
+        self.bank_select = r.read_u8()?;
+        self.prg_ram.load_state(r)?;
+        r.read_bytes(&mut self.chr_ram)?;
+        self.mirroring = Mirroring::from_u8(r.read_u8()?)?;
+        self.irq_latch = r.read_u8()?;
+        self.irq_counter = r.read_u8()?;
+        self.irq_reload = r.read_bool()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq = r.read_bool()?;
+        self.a12 = r.read_bool()?;
+        self.a12_low_dots = r.read_usize()?;
+        self.prg_ram_control = if r.version() >= 13 { r.read_u8()? } else { 0x80 };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_round_trip() {
+        let mut mmc3 = MMC3::new(0x20000, 0x20000, 0x10, 0x20010, Mirroring::Vertical);
+        for (reg, bank) in [0x08, 0x0A, 0x10, 0x11, 0x12, 0x13, 0x05, 0x06].into_iter().enumerate() {
+            mmc3.write_prg(0x8000, 0x40 | reg as u8);
+            mmc3.write_prg(0x8001, bank);
+        }
+        mmc3.write_prg(0xA000, 1);
+        mmc3.write_prg(0x6000, 0x5A);
+        mmc3.write_prg(0xA001, 0xC0);
+        mmc3.write_prg(0xC000, 0x20);
+        mmc3.write_prg(0xC001, 0);
+        mmc3.write_prg(0xE001, 0);
+        mmc3.clock_irq();
+        mmc3.clock_irq();
+        let mut w = StateWriter::new();
+        mmc3.save_state(&mut w);
+        let state = w.into_inner();
+        let snapshot = |mmc3: &MMC3| (
+            mmc3.registers,
+            mmc3.bank_select,
+            mmc3.prg_rom_address(0x8000),
+            mmc3.chr_rom_address(0x1C00),
+            mmc3.get_mirroring() as u8,
+            mmc3.wram_protection(),
+            mmc3.prg_ram.read(0),
+            (mmc3.irq_latch, mmc3.irq_counter, mmc3.irq_reload, mmc3.irq_enabled),
+        );
+        let saved = snapshot(&mmc3);
+        assert_eq!(saved.2, Some(0x1C000));
+        assert_eq!(saved.7, (0x20, 0x1F, false, true));
+
+        mmc3.write_prg(0x8000, 0);
+        mmc3.write_prg(0x8001, 0);
+        mmc3.write_prg(0xA000, 0);
+        mmc3.write_prg(0xA001, 0x80);
+        mmc3.write_prg(0x6000, 0);
+        mmc3.write_prg(0xC000, 0x08);
+        mmc3.write_prg(0xE000, 0);
+        mmc3.clock_irq();
+        mmc3.load_state(&mut StateReader::new(&state)).unwrap();
+        assert_eq!(snapshot(&mmc3), saved);
+    }
+
+    #[test]
+    fn oversized_banks() {
+        let mut mmc3 = MMC3::new(0x200000, 0x40000, 0x10, 0x200010, Mirroring::Vertical);
+        mmc3.write_prg(0x8000, 0x06);
+        mmc3.write_prg(0x8001, 0xFE);
+        mmc3.write_prg(0x8000, 0x02);
+        mmc3.write_prg(0x8001, 0xFF);
+        assert_eq!(mmc3.prg_rom_address(0x8000), Some(0x1FC000));
+        assert_eq!(mmc3.prg_rom_address(0xFFFF), Some(0x1FFFFF));
+        assert_eq!(mmc3.chr_rom_address(0x1000), Some(0x3FC00));
+    }
+}
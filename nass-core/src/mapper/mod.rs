@@ -0,0 +1,168 @@
+mod nrom;
+mod cnrom;
+mod mmc1;
+mod mmc3;
+mod event;
+mod fcg;
+mod eeprom;
+mod save_media;
+
+pub use crate::mapper::{
+    nrom::NROM,
+    cnrom::CNROM,
+    mmc1::MMC1,
+    mmc3::{MMC3, A12_FILTER_M2},
+    event::EVENT,
+    fcg::FCG,
+    eeprom::{Eeprom, EepromKind},
+    save_media::{SaveMedia, SaveKind, Wram, sav_len},
+};
+
+use std::fmt::Display;
+use crate::state::{StateWriter, StateReader};
+use crate::header::Header;
+
+// PRG-RAM chip enable and write protect as last written to the mapper.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct WramProtection {
+    pub enabled: bool,
+    pub writable: bool,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Mirroring {
+    OneScreenUpper,
+    OneScreenLower,
+    Vertical,
+    Horizontal,
+    FourScreen
+}
+
+impl Mirroring {
+    pub fn from_u8(value: u8) -> Result<Mirroring, String> {
+        match value {
+            0 => Ok(Mirroring::OneScreenUpper),
+            1 => Ok(Mirroring::OneScreenLower),
+            2 => Ok(Mirroring::Vertical),
+            3 => Ok(Mirroring::Horizontal),
+            4 => Ok(Mirroring::FourScreen),
+            _ => Err("Unknown mirroring in savestate.".to_string())
+        }
+    }
+}
+
+pub type Mapper_ = Box<dyn Mapper>;
+
+pub trait Mapper: Display {
+    fn read_prg(&self, rom: *const u8, addr: u16) -> u8;
+    fn read_chr(&self, rom: *const u8, addr: u16) -> u8;
+    fn write_prg(&mut self, addr: u16, val: u8);
+    fn write_chr(&mut self, addr: u16, val: u8);
+    fn get_mirroring(&self) -> Mirroring;
+    fn save_state(&self, w: &mut StateWriter);
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String>;
+
+    // Called every PPU dot with the address currently on the PPU bus.
+    fn ppu_bus(&mut self, _: u16) {}
+    fn cpu_tick(&mut self, _: usize) {}
+    fn irq(&self) -> bool { false }
+    fn set_irq_options(&mut self, _a12_filter: usize, _old_revision: bool) {}
+    fn save_media(&mut self) -> Option<&mut dyn SaveMedia> { None }
+    // None for boards without PRG-RAM enable/protect bits.
+    fn wram_protection(&self) -> Option<WramProtection> { None }
+    // Offset into PRG-ROM of the byte mapped at a CPU address, None outside ROM.
+    fn prg_rom_address(&self, _: u16) -> Option<usize> { None }
+    // Offset into CHR-ROM of the byte mapped at a PPU address, None for CHR-RAM.
+    fn chr_rom_address(&self, _: u16) -> Option<usize> { None }
+
+    fn mirror(&self, addr: u16) -> u16 {
+        let mirrored_vram = addr & 0x2FFF;
+        let vram_index = mirrored_vram - 0x2000;
+        let name_table = vram_index / 0x400;
+        let mirroring = self.get_mirroring();
+        // 00b - 1-screen mirroring (nametable 0)
+        // 01b - 1-screen mirroring (nametable 1)
+        match (mirroring, name_table) {
+            (Mirroring::Vertical, 2) | (Mirroring::Vertical, 3) => vram_index - 0x800,
+            (Mirroring::Horizontal, 1) => vram_index - 0x400,
+            (Mirroring::Horizontal, 2) => vram_index - 0x400,
+            (Mirroring::Horizontal, 3) => vram_index - 0x800,
+            (Mirroring::OneScreenLower, 1) | (Mirroring::OneScreenLower, 2) | (Mirroring::OneScreenLower, 3) => vram_index & 0x23FF,
+            (Mirroring::OneScreenUpper, 0) => vram_index + 0x400,
+            (Mirroring::OneScreenUpper, 2) => vram_index - 0x400,
+            (Mirroring::OneScreenUpper, 3) => vram_index - 0x800,
+            _ => vram_index
+        }
+    }
+}
+
+pub fn get_mapper(prg_len: usize, chr_len: usize, prg_offset: usize, chr_offset: usize, mapper: u8, mirroring: Mirroring) -> Result<Box<dyn Mapper>, String> { 
+    match mapper {
+        0 => Ok(Box::new(NROM::new(prg_len, chr_len, prg_offset, chr_offset, mirroring))),
+        1 => Ok(Box::new(MMC1::new(prg_len, chr_len, prg_offset, chr_offset, mirroring))),
+        3 => Ok(Box::new(CNROM::new(prg_len, chr_len, prg_offset, chr_offset, mirroring))),
+        4 => Ok(Box::new(MMC3::new(prg_len, chr_len, prg_offset, chr_offset, mirroring))),
+        16 => Ok(Box::new(FCG::new(prg_len, chr_len, prg_offset, chr_offset, mirroring, EepromKind::C02))),
+        105 => Ok(Box::new(EVENT::new(prg_len, chr_len, prg_offset, chr_offset, mirroring))),
+        159 => Ok(Box::new(FCG::new(prg_len, chr_len, prg_offset, chr_offset, mirroring, EepromKind::C01))),
+        _ => Err(format!("Mapper {mapper} not implemented."))
+    }
+}
+
+pub fn is_supported(mapper: u16) -> bool {
+    matches!(mapper, 0 | 1 | 3 | 4 | 16 | 105 | 159)
+}
+
+// With `fallback` a ROM on a mapper that isn't implemented still boots as NROM, with the last
+// 32KB of PRG-ROM (where most boards keep the reset vector) and the first 8KB of CHR, enough
+// to look at its code and graphics.
+pub fn new(bytes: &Vec<u8>, fallback: bool) -> Result<Mapper_, String> {
+    let header = Header::parse(bytes)?;
+
+    let prg_rom_start = header.prg_rom_start();
+    let chr_rom_start = header.chr_rom_start();
+    if header.prg_rom == 0 { return Err("ROM has no PRG-ROM.".to_string()) }
+    // Mappers read PRG/CHR straight from the file, it has to be as long as the header says.
+    if bytes.len() < chr_rom_start + header.chr_rom { return Err("ROM is truncated.".to_string()) }
+
+    if fallback && !is_supported(header.mapper) {
+        log_debug!(target: "nass::mapper", mapper = header.mapper, "unsupported mapper, booting as NROM");
+        let prg_len = header.prg_rom.min(0x8000);
+        let prg_start = prg_rom_start + header.prg_rom - prg_len;
+        return Ok(Box::new(NROM::new(prg_len, header.chr_rom.min(0x2000), prg_start, chr_rom_start, header.mirroring)))
+    }
+    let mapper_id = u8::try_from(header.mapper).map_err(|_| format!("Mapper {} not implemented.", header.mapper))?;
+    log_debug!(target: "nass::mapper", mapper_id, prg_rom = header.prg_rom, chr_rom = header.chr_rom, "loading ROM");
+
+    get_mapper(header.prg_rom, header.chr_rom, prg_rom_start, chr_rom_start, mapper_id, header.mirroring)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // NES 2.0 image with 2^exponent bytes of PRG-ROM, no CHR, each byte its own offset's low bits.
+    fn nes2(mapper: u8, exponent: u8) -> Vec<u8> {
+        let mut bytes = b"NES\x1A".to_vec();
+        bytes.extend_from_slice(&[exponent << 2, 0x00, mapper << 4, (mapper & 0xF0) | 0x08, 0x00, 0x0F]);
+        bytes.resize(16, 0);
+        bytes.extend((0..1usize << exponent).map(|i| i as u8));
+        bytes
+    }
+
+    #[test]
+    fn prg_smaller_than_the_fixed_banks() {
+        for mapper in [4, 16] {
+            for exponent in [11, 12, 13, 14] {
+                let rom = nes2(mapper, exponent);
+                let mut board = new(&rom, false).unwrap();
+                board.write_prg(0x8000, 0x46);
+                for addr in [0x8000, 0xA000, 0xC000, 0xE000, 0xFFFC, 0xFFFF] {
+                    let offset = board.prg_rom_address(addr).unwrap();
+                    assert!(offset < 1 << exponent, "mapper {mapper}, 2^{exponent}, ${addr:04X}");
+                    assert_eq!(board.read_prg(rom.as_ptr(), addr), offset as u8);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt;
+use super::*;
+
+pub struct NROM {
+    prg_ram: Wram,
+    chr_ram: [u8; 0x2000],
+    prg_offset: usize,
+    prg_len: usize,
+    chr_offset: usize,
+    chr_len: usize,
+    mirroring: Mirroring,
+}
+
+impl NROM {
+    pub fn new(prg_len: usize, chr_len: usize, prg_offset: usize, chr_offset: usize,  mirroring: Mirroring) -> Self { 
+        NROM {
+            prg_ram: Wram::new(0x2000),
+            chr_ram: [0; 0x2000],
+            prg_offset,
+            prg_len,
+            chr_offset,
+            chr_len,
+            mirroring,
+        } 
+    }
+}
+
+impl fmt::Display for NROM {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NROM")
+    }
+}
+
+impl Mapper for NROM {
+    fn get_mirroring(&self) -> Mirroring { self.mirroring }
+
+    fn read_chr(&self, rom: *const u8, addr: u16) -> u8 { 
+        match self.chr_rom_address(addr) {
+            Some(addr) => unsafe { *(rom.wrapping_add(self.chr_offset + addr)) },
+            None => self.chr_ram[addr as usize]
+        }
+    }
+
+    fn chr_rom_address(&self, addr: u16) -> Option<usize> {
+        if self.chr_len == 0 { None } else { Some(addr as usize) }
+    }
+
+    fn read_prg(&self, rom: *const u8, addr: u16) -> u8 { 
+        match addr {
+            0x6000..=0x7FFF => self.prg_ram.read((addr - 0x6000) as usize),
+            0x8000..=0xFFFF => match self.prg_rom_address(addr) {
+                Some(addr) => unsafe { *(rom.wrapping_add(self.prg_offset + addr)) },
+                None => 0
+            },
+            _ => 0
+        }
+    }
+
+    fn prg_rom_address(&self, addr: u16) -> Option<usize> {
+        if addr < 0x8000 { return None }
+        let mut addr = addr - 0x8000;
+        if self.prg_len == 0x4000 && addr >= 0x4000 { addr = addr % 0x4000; }
+        Some(addr as usize)
+    }
+    fn write_prg(&mut self, addr: u16, val: u8) { 
+        match addr {
+            0x6000..=0x7FFF => self.prg_ram.write((addr - 0x6000) as usize, val),
+            _ => ()
+        }
+    }
+
+    fn write_chr(&mut self, addr: u16, val: u8) { 
+        self.chr_ram[(addr) as usize] = val;
+    }
+
+    fn save_media(&mut self) -> Option<&mut dyn SaveMedia> { Some(&mut self.prg_ram) }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        self.prg_ram.save_state(w);
+        w.write_bytes(&self.chr_ram);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.prg_ram.load_state(r)?;
+        r.read_bytes(&mut self.chr_ram)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_round_trip() {
+        let mut nrom = NROM::new(0x8000, 0, 0x10, 0x8010, Mirroring::Vertical);
+        nrom.write_prg(0x6000, 0x12);
+        nrom.write_chr(0x1FFF, 0x34);
+        let mut w = StateWriter::new();
+        nrom.save_state(&mut w);
+        let state = w.into_inner();
+
+        nrom.write_prg(0x6000, 0);
+        nrom.write_chr(0x1FFF, 0);
+        nrom.load_state(&mut StateReader::new(&state)).unwrap();
+        assert_eq!(nrom.read_prg(std::ptr::null(), 0x6000), 0x12);
+        assert_eq!(nrom.read_chr(std::ptr::null(), 0x1FFF), 0x34);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::state::{StateWriter, StateReader};
+use crate::header::Header;
+
+// Battery RAM most boards have, and what iNES ROMs get since their headers can't say.
+const DEFAULT_BATTERY_SIZE: usize = 0x2000;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum SaveKind {
+    Battery = 1,
+    Eeprom = 2,
+}
+
+// Anything a cartridge keeps across power cycles, frontends only see this interface.
+pub trait SaveMedia {
+    fn kind(&self) -> SaveKind;
+    fn data(&self) -> &[u8];
+    fn load(&mut self, data: &[u8]);
+    fn is_dirty(&self) -> bool;
+    fn clear_dirty(&mut self);
+}
+
+// Length of the raw .sav file other emulators (FCEUX, Nestopia, Mesen) read and write: just
+// the battery RAM, sized by the NES 2.0 header or 8KB, even where the board maps more
+// (MMC1 always has 32KB here). EEPROMs are saved whole.
+pub fn sav_len(media: &dyn SaveMedia, header: &Header) -> usize {
+    let len = media.data().len();
+    match media.kind() {
+        SaveKind::Battery if header.prg_nvram > 0 => header.prg_nvram.min(len),
+        SaveKind::Battery => DEFAULT_BATTERY_SIZE.min(len),
+        SaveKind::Eeprom => len,
+    }
+}
+
+// PRG RAM at $6000-$7FFF, battery backed or not.
+pub struct Wram {
+    data: Vec<u8>,
+    dirty: bool,
+}
+
+impl Wram {
+    pub fn new(size: usize) -> Self {
+        Wram { data: vec![0; size], dirty: false }
+    }
+
+    pub fn read(&self, addr: usize) -> u8 {
+        self.data[addr]
+    }
+
+    pub fn write(&mut self, addr: usize, val: u8) {
+        if self.data[addr] != val { self.dirty = true; }
+        self.data[addr] = val;
+    }
+
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.data);
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        r.read_bytes(&mut self.data)
+    }
+}
+
+impl SaveMedia for Wram {
+    fn kind(&self) -> SaveKind { SaveKind::Battery }
+    fn data(&self) -> &[u8] { &self.data }
+
+    // Short files leave the rest cleared, padding past the RAM is dropped.
+    fn load(&mut self, data: &[u8]) {
+        let len = data.len().min(self.data.len());
+        self.data[..len].copy_from_slice(&data[..len]);
+        self.data[len..].fill(0);
+        self.dirty = false;
+    }
+
+    fn is_dirty(&self) -> bool { self.dirty }
+    fn clear_dirty(&mut self) { self.dirty = false; }
+}
@@ -0,0 +1,65 @@
+use std::collections::VecDeque;
+use crate::clock::Region;
+use crate::stats::{NTSC_FRAME_US, PAL_FRAME_US};
+
+// Host calls kept for the rolling figures, about a second of play.
+const WINDOW: usize = 60;
+
+// Performance figures for HUDs, averaged over the last `WINDOW` host calls.
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct Metrics {
+    pub fps: f32, // Emulated frames per host second
+    pub host_ms: f32, // Host time spent emulating one frame
+    pub load: f32, // `host_ms` over the region's frame time, above 1 the host can't keep up
+}
+
+struct Sample {
+    start_ms: f64,
+    end_ms: f64,
+    frames: u32,
+}
+
+// The core has no clock of its own (wasm has none), the frontend passes host timestamps in
+// milliseconds (`performance.now()`, an `Instant`) around each call that runs frames.
+pub struct FrameTimer {
+    samples: VecDeque<Sample>,
+    frames: u32, // Emulated since the last sample
+}
+
+impl FrameTimer {
+    pub fn new() -> Self {
+        FrameTimer { samples: VecDeque::with_capacity(WINDOW), frames: 0 }
+    }
+
+    pub fn add_frames(&mut self, frames: u32) {
+        self.frames = self.frames.wrapping_add(frames);
+    }
+
+    pub fn record(&mut self, start_ms: f64, end_ms: f64) {
+        if self.samples.len() == WINDOW { self.samples.pop_front(); }
+        self.samples.push_back(Sample { start_ms, end_ms, frames: std::mem::take(&mut self.frames) });
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.frames = 0;
+    }
+
+    pub fn metrics(&self, region: Region) -> Metrics {
+        let (first, last) = match (self.samples.front(), self.samples.back()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Metrics::default(),
+        };
+        let frames: u32 = self.samples.iter().map(|sample| sample.frames).sum();
+        if frames == 0 { return Metrics::default() }
+        let busy: f64 = self.samples.iter().map(|sample| sample.end_ms - sample.start_ms).sum();
+        let elapsed = last.end_ms - first.start_ms;
+        let host_ms = (busy / frames as f64) as f32;
+        let frame_ms = if region == Region::Pal { PAL_FRAME_US } else { NTSC_FRAME_US } as f32 / 1000.0;
+        Metrics {
+            fps: if elapsed > 0.0 { (frames as f64 * 1000.0 / elapsed) as f32 } else { 0.0 },
+            host_ms,
+            load: host_ms / frame_ms,
+        }
+    }
+}
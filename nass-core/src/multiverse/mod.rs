@@ -0,0 +1,116 @@
+use crate::{emulator::Emulator, frame::Frame};
+
+const WIDTH: usize = Frame::WIDTH * 2;
+
+// Where two instances first stopped agreeing, see `Multiverse::set_diff`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    pub step: u64, // Steps since diffing started, 1 for the first
+    pub frame: bool, // The pictures differ
+    pub state: bool, // The savestates differ
+}
+
+// Two independent emulators stepped together, their frames composited side by side
+// (instance 0 on the left). With shared input every button goes to both.
+pub struct Multiverse {
+    instances: [Emulator; 2],
+    shared_input: bool,
+    rom: Vec<u8>,
+    frame: Vec<u32>,
+    diff: bool,
+    steps: u64,
+    divergence: Option<Divergence>,
+}
+
+impl Default for Multiverse {
+    fn default() -> Self {
+        Multiverse::new()
+    }
+}
+
+impl Multiverse {
+    pub fn new() -> Self {
+        Multiverse {
+            instances: [Emulator::new(), Emulator::new()],
+            shared_input: true,
+            rom: Vec::new(),
+            frame: vec![0xFF; WIDTH * Frame::HEIGHT],
+            diff: false,
+            steps: 0,
+            divergence: None,
+        }
+    }
+
+    pub fn load_rom(&mut self, index: usize, bytes: &[u8]) -> Result<(), String> {
+        match self.instances.get_mut(index) {
+            Some(emulator) => emulator.load_rom(bytes),
+            None => Err("No such instance.".to_string())
+        }
+    }
+
+    // Loads the ROM buffer into one instance.
+    pub fn load(&mut self, index: usize) -> bool {
+        let rom = std::mem::take(&mut self.rom);
+        let loaded = self.load_rom(index, &rom).is_ok();
+        self.rom = rom;
+        loaded
+    }
+
+    pub fn instance(&mut self, index: usize) -> &mut Emulator {
+        &mut self.instances[index]
+    }
+
+    pub fn set_shared_input(&mut self, shared: bool) {
+        self.shared_input = shared;
+    }
+
+    // Same ROM and input on two accuracy profiles (`instance(i).set_accuracy`): after every
+    // step the frames and savestates are compared, and the first step where they differ kept.
+    // Turning it on starts counting again.
+    pub fn set_diff(&mut self, enabled: bool) {
+        self.diff = enabled;
+        self.steps = 0;
+        self.divergence = None;
+    }
+
+    pub fn divergence(&self) -> Option<Divergence> {
+        self.divergence
+    }
+
+    pub fn toggle_button(&mut self, index: usize, value: u8) {
+        if self.shared_input {
+            self.instances.iter_mut().for_each(|e| e.toggle_button(value));
+        } else if let Some(emulator) = self.instances.get_mut(index) {
+            emulator.toggle_button(value);
+        }
+    }
+
+    pub fn step(&mut self) {
+        for (i, emulator) in self.instances.iter_mut().enumerate() {
+            emulator.step();
+            for (y, row) in emulator.frame().chunks(Frame::WIDTH).enumerate() {
+                let start = y * WIDTH + i * Frame::WIDTH;
+                self.frame[start..start + Frame::WIDTH].copy_from_slice(row);
+            }
+        }
+        if self.diff && self.divergence.is_none() {
+            self.steps += 1;
+            let [a, b] = &mut self.instances;
+            let frame = a.frame() != b.frame();
+            let state = a.state_hash() != b.state_hash();
+            if frame || state { self.divergence = Some(Divergence { step: self.steps, frame, state }); }
+        }
+    }
+
+    pub fn set_rom_len(&mut self, value: usize) {
+        self.rom.resize(value, 0);
+    }
+
+    pub fn get_rom_pointer(&mut self) -> *mut u8 {
+        self.rom.as_mut_ptr()
+    }
+
+    pub fn get_frame_pointer(&self) -> *const u32 {
+        self.frame.as_ptr()
+    }
+}
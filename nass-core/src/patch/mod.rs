@@ -0,0 +1,55 @@
+use std::collections::BTreeMap;
+
+const MAGIC: &[u8] = b"PATCH";
+const EOF_MARKER: &[u8] = b"EOF";
+const MAX_OFFSET: usize = 0xFFFFFF;
+const MAX_RECORD: usize = 0xFFFF;
+
+// Bytes edited in the ROM file while it runs, exported as an IPS patch against the file
+// as it was loaded. Offsets include the 16 byte header, like every IPS patcher expects.
+// https://zerosoft.zophar.net/ips.php
+pub struct Patch {
+    edits: BTreeMap<usize, u8>,
+}
+
+impl Patch {
+    pub fn new() -> Self {
+        Patch { edits: BTreeMap::new() }
+    }
+
+    pub fn set(&mut self, offset: usize, value: u8) {
+        self.edits.insert(offset, value);
+    }
+
+    pub fn clear(&mut self) {
+        self.edits.clear();
+    }
+
+    // Runs of consecutive edits become one record each. `rom` is the edited file, a record
+    // can't start at 0x454F46 ("EOF") so one of those starts a byte early.
+    pub fn to_ips(&self, rom: &[u8]) -> Result<Vec<u8>, String> {
+        let mut records: Vec<(usize, Vec<u8>)> = Vec::new();
+        for (&offset, &value) in &self.edits {
+            if offset > MAX_OFFSET { return Err("Edit is past the 16MB an IPS patch can reach.".to_string()) }
+            match records.last_mut() {
+                Some((start, bytes)) if *start + bytes.len() == offset && bytes.len() < MAX_RECORD => bytes.push(value),
+                _ => records.push((offset, vec![value])),
+            }
+        }
+        let mut ips = MAGIC.to_vec();
+        for (mut start, mut bytes) in records {
+            if (start as u32).to_be_bytes()[1..] == *EOF_MARKER {
+                start -= 1;
+                bytes.insert(0, rom.get(start).copied().unwrap_or(0));
+            }
+            for chunk in bytes.chunks(MAX_RECORD) {
+                ips.extend_from_slice(&(start as u32).to_be_bytes()[1..]);
+                ips.extend_from_slice(&(chunk.len() as u16).to_be_bytes());
+                ips.extend_from_slice(chunk);
+                start += chunk.len();
+            }
+        }
+        ips.extend_from_slice(EOF_MARKER);
+        Ok(ips)
+    }
+}
@@ -0,0 +1,168 @@
+mod inflate;
+
+use crate::hash::crc32;
+use self::inflate::inflate;
+
+// PNG in and out for frames: 8 bit RGB/RGBA, no interlacing. Pixels are RR-GG-BB-AA
+// like the PPU colors. Encoding stores the image data uncompressed.
+// https://www.w3.org/TR/png/
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+const STORED_BLOCK: usize = 0xFFFF;
+
+pub fn encode(width: usize, height: usize, pixels: &[u32]) -> Vec<u8> {
+    let mut png = SIGNATURE.to_vec();
+    chunk(&mut png, b"IHDR", &header(width, height));
+    chunk(&mut png, b"IDAT", &image_data(width, height, pixels));
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+// Animated PNG, every frame shown for `delay` ten-thousandths of a second, looping.
+// https://www.w3.org/TR/png/#apng-frame-control
+pub fn encode_apng(width: usize, height: usize, frames: &[Vec<u32>], delay: u16) -> Vec<u8> {
+    let mut png = SIGNATURE.to_vec();
+    chunk(&mut png, b"IHDR", &header(width, height));
+    let mut control = (frames.len() as u32).to_be_bytes().to_vec();
+    control.extend_from_slice(&0u32.to_be_bytes()); // Plays forever
+    chunk(&mut png, b"acTL", &control);
+    let mut sequence = 0u32;
+    for (i, pixels) in frames.iter().enumerate() {
+        let mut frame = sequence.to_be_bytes().to_vec();
+        frame.extend_from_slice(&(width as u32).to_be_bytes());
+        frame.extend_from_slice(&(height as u32).to_be_bytes());
+        frame.extend_from_slice(&[0; 8]); // x and y offsets
+        frame.extend_from_slice(&delay.to_be_bytes());
+        frame.extend_from_slice(&10000u16.to_be_bytes());
+        frame.extend_from_slice(&[0, 0]); // No disposal, no blending
+        chunk(&mut png, b"fcTL", &frame);
+        sequence += 1;
+        if i == 0 {
+            chunk(&mut png, b"IDAT", &image_data(width, height, pixels));
+        } else {
+            let mut data = sequence.to_be_bytes().to_vec();
+            data.extend_from_slice(&image_data(width, height, pixels));
+            chunk(&mut png, b"fdAT", &data);
+            sequence += 1;
+        }
+    }
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn header(width: usize, height: usize) -> Vec<u8> {
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]); // Depth, RGB, compression, filter, interlace
+    header
+}
+
+// The zlib stream of an IDAT (or fdAT) chunk.
+fn image_data(width: usize, height: usize, pixels: &[u32]) -> Vec<u8> {
+    let mut raw = Vec::with_capacity(height * (1 + width * 3));
+    for row in pixels.chunks(width).take(height) {
+        raw.push(0); // No filter
+        for pixel in row {
+            raw.extend_from_slice(&pixel.to_be_bytes()[..3]);
+        }
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    let blocks = raw.chunks(STORED_BLOCK).count();
+    for (i, block) in raw.chunks(STORED_BLOCK).enumerate() {
+        zlib.push((i + 1 == blocks) as u8);
+        zlib.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        zlib.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+    zlib
+}
+
+// Returns width, height and the pixels.
+pub fn decode(png: &[u8]) -> Result<(usize, usize, Vec<u32>), String> {
+    if !png.starts_with(&SIGNATURE) { return Err("Not a PNG file.".to_string()) }
+    let mut pos = SIGNATURE.len();
+    let (mut width, mut height, mut channels) = (0, 0, 0);
+    let mut zlib = Vec::new();
+    while pos + 8 <= png.len() {
+        let len = u32::from_be_bytes([png[pos], png[pos + 1], png[pos + 2], png[pos + 3]]) as usize;
+        let kind = &png[pos + 4..pos + 8];
+        let data = png.get(pos + 8..pos + 8 + len).ok_or("PNG chunk is truncated.")?;
+        match kind {
+            b"IHDR" if len == 13 => {
+                width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+                height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize;
+                channels = match (data[8], data[9], data[12]) {
+                    (8, 2, 0) => 3,
+                    (8, 6, 0) => 4,
+                    _ => return Err("Only 8 bit RGB/RGBA non-interlaced PNGs are supported.".to_string())
+                };
+            },
+            b"IDAT" => zlib.extend_from_slice(data),
+            b"IEND" => break,
+            _ => (),
+        }
+        pos += 12 + len;
+    }
+    if channels == 0 || zlib.len() < 2 { return Err("PNG has no image.".to_string()) }
+
+    let raw = inflate(&zlib[2..])?;
+    let stride = width * channels;
+    if raw.len() < height * (stride + 1) { return Err("PNG image data is truncated.".to_string()) }
+    let mut pixels = Vec::with_capacity(width * height);
+    let mut previous = vec![0u8; stride];
+    for y in 0..height {
+        let line = &raw[y * (stride + 1)..(y + 1) * (stride + 1)];
+        let mut row = line[1..].to_vec();
+        unfilter(line[0], &mut row, &previous, channels)?;
+        for pixel in row.chunks(channels) {
+            let alpha = if channels == 4 { pixel[3] } else { 0xFF };
+            pixels.push(u32::from_be_bytes([pixel[0], pixel[1], pixel[2], alpha]));
+        }
+        previous = row;
+    }
+    Ok((width, height, pixels))
+}
+
+fn unfilter(filter: u8, row: &mut [u8], previous: &[u8], bpp: usize) -> Result<(), String> {
+    for i in 0..row.len() {
+        let left = if i >= bpp { row[i - bpp] } else { 0 };
+        let up = previous[i];
+        let up_left = if i >= bpp { previous[i - bpp] } else { 0 };
+        let predicted = match filter {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((left as u16 + up as u16) / 2) as u8,
+            4 => paeth(left, up, up_left),
+            _ => return Err("Unknown PNG filter.".to_string())
+        };
+        row[i] = row[i].wrapping_add(predicted);
+    }
+    Ok(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
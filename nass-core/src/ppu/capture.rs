@@ -0,0 +1,79 @@
+use crate::mapper::{self, Mapper_};
+use crate::state::{StateWriter, StateReader};
+use crate::frame::Frame;
+use crate::accuracy::Accuracy;
+use crate::clock::Region;
+use super::{PPU, DOTS_PER_SCANLINE};
+
+#[derive(Clone, Copy)]
+pub enum PpuAccess {
+    Write(u16, u8),
+    Read(u16), // $2002 and $2007, reading them changes PPU state
+    MapperWrite(u16, u8),
+}
+
+struct CapturedFrame {
+    ppu: Vec<u8>,
+    mapper: Vec<u8>,
+    log: Vec<(u16, u16, PpuAccess)>, // (scanline, dot) the access landed on
+    region: Region,
+}
+
+// Records a frame's worth of PPU register and mapper accesses, starting from a snapshot
+// at the top of the pre-render scanline, so the frame can be drawn again away from the CPU.
+pub struct FrameCapture {
+    pub enabled: bool,
+    current: Option<CapturedFrame>,
+    last: Option<CapturedFrame>,
+}
+
+impl FrameCapture {
+    pub fn new() -> Self {
+        FrameCapture { enabled: false, current: None, last: None }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        cfg!(feature = "instrumentation") && self.enabled
+    }
+
+    pub fn start_frame(&mut self, ppu: &PPU, mapper: &Mapper_) {
+        let (mut ppu_state, mut mapper_state) = (StateWriter::new(), StateWriter::new());
+        ppu.save_state(&mut ppu_state);
+        mapper.save_state(&mut mapper_state);
+        let next = CapturedFrame { ppu: ppu_state.into_inner(), mapper: mapper_state.into_inner(), log: Vec::new(), region: ppu.region() };
+        self.last = self.current.replace(next);
+    }
+
+    pub fn record(&mut self, (scanline, dot): (u16, u16), access: PpuAccess) {
+        if let Some(frame) = self.current.as_mut() {
+            frame.log.push((scanline, dot, access));
+        }
+    }
+
+    // Draws the last complete capture again from scratch, `rom` is the loaded file.
+    pub fn render(&self, rom: &Vec<u8>, accuracy: Accuracy) -> Result<Vec<u32>, String> {
+        let frame = self.last.as_ref().ok_or("No frame captured yet.".to_string())?;
+        // A ROM on an unsupported mapper can only have been captured running as NROM.
+        let mut mapper = mapper::new(rom, true)?;
+        mapper.load_state(&mut StateReader::new(&frame.mapper))?;
+        let mut ppu = PPU::new();
+        ppu.set_region(frame.region);
+        ppu.load_state(&mut StateReader::new(&frame.ppu))?;
+        ppu.accuracy = accuracy;
+
+        let mut log = frame.log.iter().peekable();
+        for _ in 0..DOTS_PER_SCANLINE as usize * ppu.scanlines_per_frame() as usize {
+            while let Some(&&(scanline, dot, access)) = log.peek() {
+                if (scanline, dot) != ppu.position() { break }
+                match access {
+                    PpuAccess::Write(addr, value) => { ppu.write_register(addr, value, &mut mapper); },
+                    PpuAccess::Read(addr) => { ppu.read_register(addr, rom.as_ptr(), &mapper); },
+                    PpuAccess::MapperWrite(addr, value) => { mapper.write_prg(addr, value); ppu.forget_tile(); },
+                }
+                log.next();
+            }
+            ppu.tick(rom.as_ptr(), &mut mapper);
+        }
+        Ok(ppu.frame.pixels()[..Frame::WIDTH * Frame::HEIGHT].to_vec())
+    }
+}
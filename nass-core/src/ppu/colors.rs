@@ -0,0 +1,48 @@
+// RR-GG-BB-AA
+pub static COLORS: [u32; 64] = [
+   0x808080FF, 0x003DA6FF, 0x0012B0FF, 0x440096FF, 0xA1005EFF,
+   0xC70028FF, 0xBA0600FF, 0x8C1700FF, 0x5C2F00FF, 0x104500FF,
+   0x054A00FF, 0x00472EFF, 0x004166FF, 0x000000FF, 0x050505FF,
+   0x050505FF, 0xC7C7C7FF, 0x0077FFFF, 0x2155FFFF, 0x8237FAFF,
+   0xEB2FB5FF, 0xFF2950FF, 0xFF2200FF, 0xD63200FF, 0xC46200FF,
+   0x358000FF, 0x058F00FF, 0x008A55FF, 0x0099CCFF, 0x212121FF,
+   0x090909FF, 0x090909FF, 0xFFFFFFFF, 0x0FD7FFFF, 0x69A2FFFF,
+   0xD480FFFF, 0xFF45F3FF, 0xFF618BFF, 0xFF8833FF, 0xFF9C12FF,
+   0xFABC20FF, 0x9FE30EFF, 0x2BF035FF, 0x0CF0A4FF, 0x05FBFFFF,
+   0x5E5E5EFF, 0x0D0D0DFF, 0x0D0D0DFF, 0xFFFFFFFF, 0xA6FCFFFF,
+   0xB3ECFFFF, 0xDAABEBFF, 0xFFA8F9FF, 0xFFABB3FF, 0xFFD2B0FF,
+   0xFFEFA6FF, 0xFFF79CFF, 0xD7E895FF, 0xA6EDAFFF, 0xA2F2DAFF,
+   0x99FFFCFF, 0xDDDDDDFF, 0x111111FF, 0x111111FF
+];
+
+// COLORS under each PPUMASK emphasis combination (index is mask >> 5). Emphasis darkens the
+// other two channels to about 0.816, done here in integers so rendering needs no FPU.
+// https://www.nesdev.org/wiki/NTSC_video#Color_Tint_Bits
+pub static EMPHASIS_COLORS: [[u32; 64]; 8] = emphasis_colors();
+
+const fn emphasis_colors() -> [[u32; 64]; 8] {
+    let mut table = [[0; 64]; 8];
+    let mut emphasis = 0;
+    while emphasis < 8 {
+        let mut index = 0;
+        while index < 64 {
+            let color = COLORS[index];
+            let mut channel = 0;
+            let mut out = color & 0xFF;
+            while channel < 3 {
+                let shift = 24 - channel * 8;
+                let mut value = (color >> shift) & 0xFF;
+                // Bit 0 emphasizes red, 1 green, 2 blue.
+                if emphasis != 0 && emphasis & (1 << channel) == 0 {
+                    value = value * 209 / 256;
+                }
+                out |= value << shift;
+                channel += 1;
+            }
+            table[emphasis][index] = out;
+            index += 1;
+        }
+        emphasis += 1;
+    }
+    table
+}
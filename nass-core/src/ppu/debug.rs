@@ -0,0 +1,34 @@
+use super::{PPU, palette_index};
+use crate::mapper::Mapper_;
+
+// Direct access to PPU memory for tile editors and debuggers, without going through
+// $2006/$2007 (so the address, data buffer and bus stay as the game left them).
+// https://www.nesdev.org/wiki/PPU_memory_map
+impl PPU {
+    // $0000-$3FFF as the PPU sees it: pattern tables through the mapper, nametables after
+    // mirroring and palettes with their mirrors.
+    pub fn peek_vram(&self, addr: u16, rom: *const u8, mapper: &Mapper_) -> u8 {
+        match addr & 0x3FFF {
+            addr @ 0..=0x1FFF => mapper.read_chr(rom, addr),
+            addr @ 0x2000..=0x3EFF => self.vram[mapper.mirror(0x2000 | (addr & 0x0FFF)) as usize],
+            addr => self.palette_table[palette_index(addr)],
+        }
+    }
+
+    // Nametables and palettes, pattern writes only reach CHR RAM (see `Emulator::edit_chr`).
+    pub fn poke_vram(&mut self, addr: u16, value: u8, mapper: &mut Mapper_) {
+        match addr & 0x3FFF {
+            addr @ 0..=0x1FFF => mapper.write_chr(addr, value),
+            addr @ 0x2000..=0x3EFF => self.vram[mapper.mirror(0x2000 | (addr & 0x0FFF)) as usize] = value,
+            addr => self.palette_table[palette_index(addr)] = value,
+        }
+    }
+
+    pub fn oam(&self) -> &[u8; 0x100] {
+        &self.oam_data
+    }
+
+    pub fn oam_mut(&mut self) -> &mut [u8; 0x100] {
+        &mut self.oam_data
+    }
+}
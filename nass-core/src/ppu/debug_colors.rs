@@ -0,0 +1,27 @@
+// Diagnostic rendering: every pixel is tinted by where it came from instead of its palette
+// color, keeping the 2-bit pattern value as brightness so shapes stay readable. Background
+// hues follow the attribute palette, sprite hues the secondary OAM slot, the backdrop is dark
+// grey and the dot that set the sprite 0 hit flag is white. RR-GG-BB-AA like `COLORS`.
+
+const BACKGROUND_HUES: [u32; 4] = [0x3070F0, 0x30C060, 0xD0B030, 0xA050D0];
+const SPRITE_HUES: [u32; 8] = [0xF03030, 0xF07820, 0xF0D020, 0x60F040, 0x20E0E0, 0x4080FF, 0xC040F0, 0xF050A0];
+const BACKDROP: u32 = 0x303030FF;
+const SPRITE_HIT: u32 = 0xFFFFFFFF;
+
+pub enum PixelSource {
+    Backdrop,
+    Background { palette: usize, value: usize },
+    Sprite { slot: usize, value: usize },
+    SpriteHit,
+}
+
+pub fn debug_color(source: PixelSource) -> u32 {
+    let (hue, value) = match source {
+        PixelSource::Backdrop => return BACKDROP,
+        PixelSource::SpriteHit => return SPRITE_HIT,
+        PixelSource::Background { palette, value } => (BACKGROUND_HUES[palette & 3], value),
+        PixelSource::Sprite { slot, value } => (SPRITE_HUES[slot & 7], value),
+    };
+    let shade = |shift: u32| (((hue >> shift) & 0xFF) * (value as u32 + 1) / 4) << shift;
+    (shade(16) | shade(8) | shade(0)) << 8 | 0xFF
+}
@@ -0,0 +1,63 @@
+use super::{PPU, tables::{self, ATTRIBUTE_SHIFT, tile_pixel}};
+use crate::mapper::Mapper_;
+
+// Pattern table and nametable dumps for ROM hacking tools, read the way the PPU sees them
+// right now (current CHR banks, palettes and mirroring).
+// https://www.nesdev.org/wiki/PPU_pattern_tables
+// https://www.nesdev.org/wiki/PPU_nametables
+impl PPU {
+    pub const PATTERN_TABLE_SIZE: usize = 128;
+
+    // Both pattern tables as 8kB of raw CHR data, the .chr file format.
+    pub fn chr(&self, rom: *const u8, mapper: &Mapper_) -> Vec<u8> {
+        (0..0x2000).map(|addr| mapper.read_chr(rom, addr)).collect()
+    }
+
+    // One pattern table (0 at $0000, 1 at $1000) as a 16x16 sheet of tiles drawn with
+    // `palette` (0-3 background, 4-7 sprites).
+    pub fn pattern_table_pixels(&self, table: usize, palette: usize, rom: *const u8, mapper: &Mapper_) -> Vec<u32> {
+        let size = PPU::PATTERN_TABLE_SIZE;
+        let mut pixels = vec![0; size * size];
+        for tile in 0..256 {
+            let addr = ((table & 1) << 12 | tile << 4) as u16;
+            for row in 0..8 {
+                let pattern = tables::tile_row(mapper.read_chr(rom, addr + row), mapper.read_chr(rom, addr + row + 8));
+                for column in 0..8 {
+                    let color = tile_pixel(pattern, 7 - column);
+                    let index = if color == 0 { 0 } else { (palette & 7) * 4 + color as usize };
+                    let (x, y) = (tile % 16 * 8 + column, tile / 16 * 8 + row as usize);
+                    pixels[y * size + x] = self.output_color(index);
+                }
+            }
+        }
+        pixels
+    }
+
+    // Nametable 0-3 ($2000, $2400, $2800, $2C00) after mirroring, with the current
+    // background pattern table and palettes.
+    pub fn nametable_pixels(&self, nametable: usize, rom: *const u8, mapper: &Mapper_) -> Vec<u32> {
+        let (width, height) = (256, 240);
+        let base = 0x2000 | ((nametable & 3) << 10) as u16;
+        let pattern_table = self.ctrl.get_background_pattern_addr();
+        let mut pixels = vec![0; width * height];
+        for tile_row in 0..30u16 {
+            for tile_column in 0..32u16 {
+                let tile = self.vram[mapper.mirror(base | tile_row << 5 | tile_column) as usize] as u16;
+                let attr_addr = base | 0x3C0 | (tile_row >> 2) << 3 | tile_column >> 2;
+                let attr_data = self.vram[mapper.mirror(attr_addr) as usize];
+                let shift = ATTRIBUTE_SHIFT[(tile_row << 5 | tile_column) as usize];
+                let palette = ((attr_data >> shift) & 3) as usize;
+                for row in 0..8 {
+                    let pattern = tables::tile_row(mapper.read_chr(rom, pattern_table | tile << 4 | row), mapper.read_chr(rom, pattern_table | tile << 4 | row | 8));
+                    for column in 0..8 {
+                        let color = tile_pixel(pattern, 7 - column as usize);
+                        let index = if color == 0 { 0 } else { palette * 4 + color as usize };
+                        let (x, y) = ((tile_column * 8 + column) as usize, (tile_row * 8 + row) as usize);
+                        pixels[y * width + x] = self.output_color(index);
+                    }
+                }
+            }
+        }
+        pixels
+    }
+}
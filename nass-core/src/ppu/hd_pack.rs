@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use crate::{mapper::Mapper_, frame::Frame, png};
+
+// Where an output pixel came from: the pattern table tile, palette (0-3 background, 4-7
+// sprites) and the pixel inside the tile in pattern space, flips already applied.
+#[derive(Clone, Copy)]
+pub struct TileSource {
+    pub pattern: u16,
+    pub palette: u8,
+    pub column: u8,
+    pub row: u8,
+    pub flip_h: bool,
+    pub flip_v: bool,
+}
+
+struct Image {
+    width: usize,
+    pixels: Vec<u32>,
+}
+
+// Replacement graphics in the Mesen HD pack format (hires.txt plus PNG sheets). Tiles are
+// matched on their 16 bytes of pattern data and the 4 palette colors, and drawn at `scale`
+// times the resolution into `frame`. Only <scale>, <img> and unconditional <tile> lines are used.
+// https://www.mesen.ca/docs/hdpacks.html
+pub struct HdPack {
+    scale: usize,
+    images: Vec<Image>,
+    tiles: HashMap<(u128, u32), (usize, usize, usize)>, // (image, x, y)
+    frame: Vec<u32>,
+}
+
+impl HdPack {
+    // `image` looks up the files named by <img>, `chr` is the cartridge CHR-ROM that tile
+    // numbers refer to.
+    pub fn load(definition: &str, mut image: impl FnMut(&str) -> Option<Vec<u8>>, chr: &[u8]) -> Result<HdPack, String> {
+        let mut pack = HdPack { scale: 1, images: Vec::new(), tiles: HashMap::new(), frame: Vec::new() };
+        for line in definition.lines().map(str::trim) {
+            if let Some(scale) = line.strip_prefix("<scale>") {
+                pack.scale = scale.parse().ok().filter(|&s| (1..=10).contains(&s)).ok_or(format!("Bad HD pack scale {scale}"))?;
+            } else if let Some(name) = line.strip_prefix("<img>") {
+                let bytes = image(name).ok_or(format!("Missing HD pack image {name}"))?;
+                let (width, _, pixels) = png::decode(&bytes)?;
+                pack.images.push(Image { width, pixels });
+            } else if let Some(tile) = line.strip_prefix("<tile>") {
+                let fields: Vec<&str> = tile.split(',').collect();
+                if fields.len() < 5 { return Err(format!("Bad HD pack tile {tile}")) }
+                let number = |i: usize| fields[i].parse::<usize>().map_err(|_| format!("Bad HD pack tile {tile}"));
+                let data = if fields[1].len() == 32 {
+                    u128::from_str_radix(fields[1], 16).map_err(|_| format!("Bad HD pack tile {tile}"))?
+                } else {
+                    let start = number(1)? * 16;
+                    match chr.get(start..start + 16) {
+                        Some(bytes) => u128::from_be_bytes(bytes.try_into().unwrap()),
+                        None => continue,
+                    }
+                };
+                let palette = u32::from_str_radix(fields[2], 16).map_err(|_| format!("Bad HD pack tile {tile}"))?;
+                pack.tiles.insert((data, palette), (number(0)?, number(3)?, number(4)?));
+            }
+        }
+        pack.frame = vec![0xFF; Frame::WIDTH * pack.scale * Frame::HEIGHT * pack.scale];
+        Ok(pack)
+    }
+
+    pub fn scale(&self) -> usize {
+        self.scale
+    }
+
+    pub fn frame(&self) -> &[u32] {
+        &self.frame
+    }
+
+    // Draws the pixel at `index` of the normal frame as a scale x scale block.
+    pub fn draw(&mut self, index: usize, color: u32, source: Option<TileSource>, palette: u32, rom: *const u8, mapper: &Mapper_) {
+        let replacement = source.and_then(|source| {
+            let mut data = 0u128;
+            for i in 0..16 {
+                data = (data << 8) | mapper.read_chr(rom, source.pattern + i) as u128;
+            }
+            let &(image, x, y) = self.tiles.get(&(data, palette))?;
+            Some((self.images.get(image)?, x, y, source))
+        });
+        let scale = self.scale;
+        let width = Frame::WIDTH * scale;
+        let (out_x, out_y) = ((index % Frame::WIDTH) * scale, (index / Frame::WIDTH) * scale);
+        for sy in 0..scale {
+            for sx in 0..scale {
+                let pixel = match replacement {
+                    Some((image, x, y, source)) => {
+                        let sx = if source.flip_h { scale - 1 - sx } else { sx };
+                        let sy = if source.flip_v { scale - 1 - sy } else { sy };
+                        let (px, py) = (x + source.column as usize * scale + sx, y + source.row as usize * scale + sy);
+                        let pixel = image.pixels.get(py * image.width + px).copied().unwrap_or(color);
+                        // Transparent replacement pixels show the original color.
+                        if pixel & 0xFF == 0 { color } else { pixel }
+                    },
+                    None => color,
+                };
+                self.frame[(out_y + sy) * width + out_x + sx] = pixel;
+            }
+        }
+    }
+}
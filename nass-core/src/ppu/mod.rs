@@ -0,0 +1,780 @@
+mod ppu_addr;
+mod ppu_control;
+mod ppu_mask;
+mod ppu_status;
+mod colors;
+mod sprite_log;
+mod split_log;
+mod capture;
+mod hd_pack;
+mod export;
+mod debug;
+mod tables;
+mod debug_colors;
+mod overlay;
+
+pub use colors::*;
+pub use sprite_log::SpriteLog;
+pub use split_log::{SplitLog, Split};
+pub use capture::{FrameCapture, PpuAccess};
+pub use hd_pack::{HdPack, TileSource};
+pub use overlay::Overlay;
+use crate::frame::Frame;
+use crate::accuracy::Accuracy;
+use crate::rng::Rng;
+use crate::clock::Region;
+
+use crate::mapper::*;
+use crate::state::{StateWriter, StateReader};
+use self::{
+    ppu_addr::PPUAddr,
+    ppu_control::PPUControl,
+    ppu_mask::PPUMask,
+    ppu_status::PPUStatus,
+    tables::{ATTRIBUTE_SHIFT, tile_row, tile_pixel, background_row},
+    debug_colors::{PixelSource, debug_color},
+    overlay::overlay_color,
+};
+
+// https://www.nesdev.org/wiki/PPU_rendering
+pub const DOTS_PER_SCANLINE: u16 = 341;
+// PAL PPUs run 312 scanlines, the 50 extra ones all in vblank (70 lines instead of 20).
+const SCANLINES_PER_FRAME: u16 = 262;
+const PAL_SCANLINES_PER_FRAME: u16 = 312;
+pub const VBLANK_SCANLINE: u16 = 241;
+// Bits on the PPU data bus hold their value for about 600ms without being driven.
+// https://www.nesdev.org/wiki/Open_bus_behavior#PPU_open_bus
+const OPEN_BUS_DECAY_FRAMES: u32 = 36;
+
+// Called once each visible scanline is drawn with its index and its 256 pixels, for
+// line-based post-processing without copying the frame.
+pub type LineHook = Box<dyn FnMut(u16, &[u32])>;
+
+// $3F10/$3F14/$3F18/$3F1C mirror the background entries below them.
+fn palette_index(addr: u16) -> usize {
+    let addr = (addr & 0x1F) as usize;
+    if addr >= 0x10 && addr & 3 == 0 { addr - 0x10 } else { addr }
+}
+
+// What palette RAM, OAM and nametables hold at power on. Real consoles come up with leftover
+// garbage that differs between units, a few games show it for a frame or two.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum PowerOn {
+    Zeroed = 0,
+    Measured = 1, // Palette RAM as read on one console (blargg's power_up_palette), the rest zeroed
+    Random = 2, // Everything from the power-on seed
+}
+
+impl PowerOn {
+    pub fn from_u8(value: u8) -> Option<PowerOn> {
+        match value {
+            0 => Some(PowerOn::Zeroed),
+            1 => Some(PowerOn::Measured),
+            2 => Some(PowerOn::Random),
+            _ => None,
+        }
+    }
+}
+
+// https://www.nesdev.org/wiki/PPU_power_up_state#Palette
+const MEASURED_PALETTE: [u8; 0x20] = [
+    0x09, 0x01, 0x00, 0x01, 0x00, 0x02, 0x02, 0x0D, 0x08, 0x10, 0x08, 0x24, 0x00, 0x00, 0x04, 0x2C,
+    0x09, 0x01, 0x34, 0x03, 0x00, 0x04, 0x00, 0x14, 0x08, 0x3A, 0x00, 0x02, 0x00, 0x20, 0x2C, 0x08,
+];
+
+// Secondary OAM holds 8 sprites, the rest on a scanline are dropped (what makes games flicker).
+pub const SPRITES_PER_SCANLINE: usize = 8;
+
+// The background tile being drawn, fetched on the first of its dots at `v`.
+#[derive(Clone, Copy)]
+struct BackgroundTile {
+    v: u16,
+    pixels: [u8; 8], // Palette RAM indices, leftmost first
+    pattern: u16,
+    palette: u8,
+}
+
+pub struct PPU {
+    pub palette_table: [u8; 0x20],
+    vram: [u8; 0x800], // Nametables (2kB)
+    oam_data: [u8; 0x100],
+    sprites: ([u8; 0x100], usize),
+    pub oam_addr: u8,
+    addr: PPUAddr,
+    temp: u16,
+    ctrl: PPUControl,
+    pub mask: PPUMask,
+    status: PPUStatus,
+    internal_data_buff: u8,
+    io_latch: u8,
+    latch_refreshed: [u32; 8], // Frame each latch bit was last driven on
+    frames: u32,
+    // fine_x: u8,
+    scanline: u16,
+    dot: u16,
+    pub frame: Frame,
+    pub nmi_occured: bool,
+    pub nmi_cancelled: bool,
+    vblank_suppressed: bool,
+    pub frame_completed: bool,
+    pub accuracy: Accuracy,
+    pub sprite_log: SpriteLog,
+    pub split_log: SplitLog,
+    // Up to 64 to draw every sprite on a scanline instead of flickering, only changes the picture.
+    pub sprite_limit: usize,
+    pub sprite_hit_line: Option<u16>, // Scanline of the first sprite 0 hit since it was cleared
+    pub hd_pack: Option<Box<HdPack>>,
+    pub line_hook: Option<LineHook>,
+    pub debug_colors: bool, // See `debug_colors`
+    pub overlay: Overlay,
+    region: Region, // Frame geometry, PAL PPUs also swap the red and green emphasis bits
+    tile: Option<BackgroundTile>,
+}
+
+impl PPU {
+    pub fn new() -> Self {
+        PPU {
+            palette_table: [0; 0x20],
+            vram: [0; 0x800],
+            oam_data: [0; 0x100],
+            sprites: ([0; 0x100], 0),
+            oam_addr: 0,
+            addr: PPUAddr::new(),
+            ctrl: PPUControl::new(),
+            temp: 0,
+            mask: PPUMask::new(),
+            status: PPUStatus::new(),
+            internal_data_buff: 0,
+            io_latch: 0,
+            latch_refreshed: [0; 8],
+            frames: 0,
+            // fine_x: 0,
+            scanline: 0,
+            dot: 0,
+            frame: Frame::new(),
+            nmi_occured: false,
+            nmi_cancelled: false,
+            vblank_suppressed: false,
+            frame_completed: false,
+            accuracy: Accuracy::BALANCED,
+            sprite_log: SpriteLog::new(),
+            split_log: SplitLog::new(),
+            sprite_limit: SPRITES_PER_SCANLINE,
+            sprite_hit_line: None,
+            hd_pack: None,
+            line_hook: None,
+            debug_colors: false,
+            overlay: Overlay::empty(),
+            region: Region::Ntsc,
+            tile: None,
+        }
+    }
+
+    pub fn set_region(&mut self, region: Region) {
+        self.region = region;
+    }
+
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    pub fn scanlines_per_frame(&self) -> u16 {
+        match self.region {
+            Region::Ntsc => SCANLINES_PER_FRAME,
+            Region::Pal => PAL_SCANLINES_PER_FRAME,
+        }
+    }
+
+    // 261 on NTSC, 311 on PAL.
+    pub fn pre_render_scanline(&self) -> u16 {
+        self.scanlines_per_frame() - 1
+    }
+
+    pub fn nmi_enabled(&self) -> bool {
+        self.ctrl.generate_nmi()
+    }
+
+    pub fn power_on(&mut self, power_on: PowerOn, rng: &mut Rng) {
+        match power_on {
+            PowerOn::Zeroed => (),
+            PowerOn::Measured => self.palette_table = MEASURED_PALETTE,
+            PowerOn::Random => {
+                rng.fill(&mut self.palette_table);
+                self.palette_table.iter_mut().for_each(|color| *color &= 0x3F);
+                rng.fill(&mut self.oam_data);
+                rng.fill(&mut self.vram);
+            },
+        }
+    }
+
+    pub fn tick(&mut self, rom: *const u8, mapper: &mut Mapper_) {
+        match self.scanline {
+            0..=239 => self.render_dot(rom, mapper),
+            VBLANK_SCANLINE => if self.dot == 1 {
+                log_debug!(target: "nass::ppu", nmi = self.ctrl.generate_nmi(), suppressed = self.vblank_suppressed, "vblank");
+                self.frame_completed = true;
+                self.frames = self.frames.wrapping_add(1);
+                if self.sprite_log.is_enabled() { self.sprite_log.end_frame(); }
+                if self.split_log.is_enabled() { self.split_log.end_frame(); }
+                if !std::mem::take(&mut self.vblank_suppressed) {
+                    self.status.set_vblank(true);
+                    if self.ctrl.generate_nmi() { 
+                        self.nmi_occured = true; 
+                    }
+                }
+            },
+            line if line == self.pre_render_scanline() => self.pre_render_dot(),
+            _ => (),
+        }
+        mapper.ppu_bus(self.bus_address());
+        self.next_dot();
+    }
+
+    // Frames started since power on, savestates included.
+    pub fn frame_count(&self) -> u32 {
+        self.frames
+    }
+
+    // (scanline, dot) of the dot about to be drawn, the pre-render scanline is the last one.
+    pub fn position(&self) -> (u16, u16) {
+        (self.scanline, self.dot)
+    }
+
+    fn next_dot(&mut self) {
+        self.dot += 1;
+        if self.dot == DOTS_PER_SCANLINE {
+            self.dot = 0;
+            self.scanline = (self.scanline + 1) % self.scanlines_per_frame();
+        }
+    }
+
+    fn pre_render_dot(&mut self) {
+        if self.dot == 1 { 
+            self.status.reset();
+            // With OAMADDR left at 8 or more when rendering starts, its 8-byte row is copied
+            // over the first one. https://www.nesdev.org/wiki/PPU_registers#OAMADDR
+            if self.mask.rendering() && self.oam_addr >= 8 && self.accuracy.contains(Accuracy::OAM_CORRUPTION) {
+                let row = (self.oam_addr & 0xF8) as usize;
+                self.oam_data.copy_within(row..row + 8, 0);
+            }
+        }
+        if self.mask.rendering() && self.dot > 0 {
+            if self.dot % 8 == 0 && self.dot <= 256 { self.addr.coarse_x_increment(); } 
+            if self.dot == 256 { self.addr.coarse_y_increment(); }
+            if self.dot == 257 { self.oam_addr = 0; self.addr.set_horizontal(self.temp); }
+            if self.dot >= 280 && self.dot <= 304 { self.addr.set_vertical(self.temp); }
+        }
+    }
+
+    fn render_dot(&mut self, rom: *const u8, mapper: &mut Mapper_) {
+        if self.dot > 0 {
+            if self.dot <= 256 {
+                let mut color = 0;
+                let mut source = None;
+                let mut sprite_slot = None;
+                let mut sprite_hit = false;
+                if self.mask.show_background() && (self.dot > 8 || self.mask.show_background_leftmost()) {
+                    let v = self.addr.get();
+                    let tile = self.background_tile(v, rom, mapper);
+                    let column = (self.dot - 1) % 8;
+                    color = tile.pixels[column as usize] as usize;
+                    if self.hd_pack.is_some() {
+                        source = Some(TileSource {
+                            pattern: tile.pattern, palette: tile.palette,
+                            column: column as u8, row: ((v & 0x7000) >> 12) as u8, flip_h: false, flip_v: false,
+                        });
+                    }
+                }
+                        
+                if self.mask.show_sprite() && (self.dot > 8 || self.mask.show_sprite_leftmost()) {
+                    for sprite in 0..self.sprites.1 {
+                        let x = self.sprites.0[4*sprite + 3] as usize;
+                        let dot = self.dot as usize;
+                        if dot >= x && dot - x < 8 {
+                            let y = self.sprites.0[4*sprite] as usize;
+                            let tile = self.sprites.0[4*sprite + 1] as u16;
+                            let attr = self.sprites.0[4*sprite + 2];
+                            let bank = (tile & 0x1) << 12;
+                            let priority = attr & 0x20 == 0; // In front of the background
+                            let palette = attr & 0x03;
+                            let flip_h = attr & 0x40 > 0;
+                            let flip_v = attr & 0x80 > 0;
+                            let height = if self.ctrl.is_sprite_size_16() { 16 } else { 8 };
+                            // Sprites left over from before rendering was switched off may not cover this line.
+                            let y = match (self.scanline as usize).checked_sub(y + 1) {
+                                Some(y) if y < height => y,
+                                _ => continue,
+                            };
+                            let x = dot - x;
+                            let fine_x = if flip_h { x } else { 7 - x };
+                            let fine_y = if flip_v { height - 1 - y } else { y } as u16;
+                            let offset = y.div_euclid(8) as u16;
+                            let half_pattern_table = if self.ctrl.is_sprite_size_16() { bank } else { self.ctrl.get_sprite_pattern_addr()};
+                            let color_addr_0 = half_pattern_table | tile << 4 | 0 << 3 | fine_y;
+                            let color_addr_1 = half_pattern_table | tile + offset << 4 | 1 << 3 | fine_y;
+                            let row = tile_row(mapper.read_chr(rom, color_addr_0), mapper.read_chr(rom, color_addr_1));
+                            let color_tile = tile_pixel(row, fine_x);
+
+                            if color_tile > 0 { 
+                                if priority || color == 0 { 
+                                    if !self.status.sprite_hit() && self.mask.show_background() {
+                                        self.status.set_sprite_hit(true);
+                                        self.sprite_hit_line.get_or_insert(self.scanline);
+                                        sprite_hit = true;
+                                    }
+                                    color = (0x10 | palette << 2 | color_tile) as usize;
+                                    sprite_slot = Some(sprite);
+                                    if self.hd_pack.is_some() {
+                                        let tile = if height == 16 { (tile & !1) + fine_y / 8 } else { tile };
+                                        source = Some(TileSource {
+                                            pattern: half_pattern_table | tile << 4, palette: 4 | palette,
+                                            column: 7 - fine_x as u8, row: (fine_y % 8) as u8, flip_h, flip_v,
+                                        });
+                                    }
+                                } 
+                            }
+                        }
+                    }
+                }
+                let output = if self.debug_colors {
+                    debug_color(match (sprite_slot, color) {
+                        _ if sprite_hit => PixelSource::SpriteHit,
+                        (Some(slot), _) => PixelSource::Sprite { slot, value: color & 3 },
+                        (None, 0) => PixelSource::Backdrop,
+                        (None, _) => PixelSource::Background { palette: color >> 2, value: color & 3 },
+                    })
+                } else {
+                    self.output_color(color)
+                };
+                let output = if !self.overlay.is_empty() && self.mask.rendering() {
+                    let v = self.addr.get();
+                    overlay_color(self.overlay, v, ((self.dot - 1) % 8) as u8, (v & 0x7000) >> 12, output)
+                } else {
+                    output
+                };
+                if let Some(hd_pack) = self.hd_pack.as_mut() {
+                    let p = &self.palette_table;
+                    let base = 4 * source.map_or(0, |s| s.palette as usize);
+                    let palette = u32::from_be_bytes([p[0], p[base + 1], p[base + 2], p[base + 3]]);
+                    hd_pack.draw(self.frame.get_index(), output, source, palette, rom, mapper);
+                }
+                self.frame.set_pixel(output);
+                if self.dot == 256 {
+                    if let Some(hook) = self.line_hook.as_mut() { hook(self.scanline, self.frame.row(self.scanline as usize)); }
+                }
+            }
+
+            if self.mask.rendering() {
+                if self.dot % 8 == 0 && self.dot <= 256 { self.addr.coarse_x_increment(); } 
+                if self.dot == 256 { self.addr.coarse_y_increment(); }
+                if self.dot == 257 { self.oam_addr = 0; self.addr.set_horizontal(self.temp); }
+                if self.dot == 270 {
+                    self.sprites = ([0; 0x100], 0);
+                    let height = if self.ctrl.is_sprite_size_16() { 16 } else { 8 };
+                    for n in (0..self.oam_data.len()).step_by(4) {
+                        let y = self.oam_data[n] as usize;
+                        if (self.scanline as usize).checked_sub(y).is_some_and(|row| row < height) && y + height <= 239 {
+                            // Sprites found here are drawn on the next scanline.
+                            let line = self.scanline as usize + 1;
+                            // The log and the overflow flag go by hardware, whatever the limit.
+                            let overflow = self.sprites.1 >= SPRITES_PER_SCANLINE;
+                            if overflow { self.status.set_overflow(true); }
+                            if self.sprite_log.is_enabled() {
+                                if overflow { self.sprite_log.dropped(line, n / 4) } else { self.sprite_log.selected(line, n / 4) }
+                            }
+                            if self.sprites.1 < self.sprite_limit {
+                                self.sprites.0[4*self.sprites.1] = self.oam_data[n];
+                                self.sprites.0[4*self.sprites.1 + 1] = self.oam_data[n + 1];
+                                self.sprites.0[4*self.sprites.1 + 2] = self.oam_data[n + 2];
+                                self.sprites.0[4*self.sprites.1 + 3] = self.oam_data[n + 3];
+                                self.sprites.1 += 1;
+                            } else if !self.sprite_log.is_enabled() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // The tile at `v`, kept for the rest of its dots once fetched. Whatever could change it
+    // between two of them (register and mapper writes, CHR edits, states) drops it first, see
+    // `forget_tile`.
+    fn background_tile(&mut self, v: u16, rom: *const u8, mapper: &Mapper_) -> BackgroundTile {
+        if let Some(tile) = self.tile.filter(|tile| tile.v == v) { return tile }
+        let fine_y = (v & 0x7000) >> 12;
+        let tile = self.vram[mapper.mirror(0x2000 | (v & 0x0FFF)) as usize];
+        let attr_addr = 0x23C0 | (v & 0x0C00) | ((v >> 4) & 0x38) | ((v >> 2) & 0x07);
+        let attr_data = self.vram[mapper.mirror(attr_addr) as usize];
+        let pattern = self.ctrl.get_background_pattern_addr() | (tile as u16) << 4;
+        let row = tile_row(mapper.read_chr(rom, pattern | fine_y), mapper.read_chr(rom, pattern | 1 << 3 | fine_y));
+        let palette = (attr_data >> ATTRIBUTE_SHIFT[(v & 0x3FF) as usize]) & 0x03;
+        let tile = BackgroundTile { v, pixels: background_row(row, palette), pattern, palette };
+        self.tile = Some(tile);
+        tile
+    }
+
+    // Fetches the background tile again on the next dot. The bus calls it before each run of
+    // dots, as mapper writes and CHR edits can change the tile under the PPU.
+    pub fn forget_tile(&mut self) {
+        self.tile = None;
+    }
+
+    fn output_color(&self, palette_index: usize) -> u32 {
+        let mut entry = self.palette_table[palette_index] & 0x3F;
+        if self.mask.greyscale() { entry &= 0x30; }
+        let mut emphasis = self.mask.emphasis();
+        // https://www.nesdev.org/wiki/PPU_registers#Color_control
+        if self.region == Region::Pal { emphasis = (emphasis & 4) | (emphasis & 1) << 1 | (emphasis >> 1) & 1; }
+        EMPHASIS_COLORS[emphasis][entry as usize]
+    }
+
+    // Approximates the address the PPU puts on its bus for the current dot, only the
+    // nametable/pattern table split (A12 and A13) is exact. Outside rendering the bus holds v.
+    fn bus_address(&self) -> u16 {
+        let v = self.addr.get();
+        if !self.mask.rendering() || (240..self.pre_render_scanline()).contains(&self.scanline) || self.dot == 0 {
+            return v & 0x3FFF;
+        }
+        let fine_y = (v & 0x7000) >> 12;
+        match self.dot {
+            1..=256 | 321..=336 => match (self.dot - 1) % 8 {
+                0 | 1 => 0x2000 | (v & 0x0FFF),
+                2 | 3 => 0x23C0 | (v & 0x0C00) | ((v >> 4) & 0x38) | ((v >> 2) & 0x07),
+                4 | 5 => self.ctrl.get_background_pattern_addr() | fine_y,
+                _ => self.ctrl.get_background_pattern_addr() | 8 | fine_y,
+            },
+            257..=320 => {
+                if (self.dot - 257) % 8 < 4 { return 0x2000 | (v & 0x0FFF); }
+                let sprite = (self.dot as usize - 257) / 8;
+                if !self.ctrl.is_sprite_size_16() {
+                    self.ctrl.get_sprite_pattern_addr()
+                } else if sprite < self.sprites.1 {
+                    (self.sprites.0[4*sprite + 1] as u16 & 0x1) << 12
+                } else {
+                    0x1000
+                }
+            },
+            _ => 0x2000 | (v & 0x0FFF),
+        }
+    }
+
+    // $2000-$2007, returns true when the write raises an NMI.
+    pub fn write_register(&mut self, addr: u16, value: u8, mapper: &mut Mapper_) -> bool {
+        self.drive_bus(value, 0xFF);
+        self.forget_tile();
+        if self.split_log.is_enabled() && self.scanline < 240 && self.mask.rendering() && matches!(addr & 0x2007, 0x2000 | 0x2005 | 0x2006) {
+            self.split_log.record(self.position(), addr, value);
+        }
+        match addr & 0x2007 {
+            0x2000 => return self.write_to_ctrl(value),
+            0x2001 => self.mask.update(value),
+            0x2003 => self.oam_addr = value,
+            0x2004 => self.write_to_oam(value),
+            0x2005 => self.write_to_scroll(value),
+            0x2006 => self.write_to_ppu_addr(value),
+            _ => self.write_data(value, mapper),
+        }
+        false
+    }
+
+    pub fn read_register(&mut self, addr: u16, rom: *const u8, mapper: &Mapper_) -> u8 {
+        match addr & 0x2007 {
+            0x2002 => self.read_status(),
+            0x2004 => self.read_oam(),
+            0x2007 => self.read_data(rom, mapper),
+            _ => self.open_bus(),
+        }
+    }
+
+    pub fn write_to_scroll(&mut self, value: u8) {
+        if !self.addr.latch() {
+            // self.fine_x = value & 0x7;
+            let value = value >> 3;
+            self.temp = (self.temp & 0xFFE0) | (value as u16);
+        } else {
+            let fine_y_scroll = (value & 0x07) as u16;
+            let coarse_y_scroll = (value & 0xF8) as u16;
+            self.temp = (self.temp & 0x8C1F) | fine_y_scroll << 12 | coarse_y_scroll << 2;
+        }
+        self.addr.toggle_latch();
+    }
+
+    pub fn read_status(&mut self) -> u8 {
+        let status = self.status.bits() | (self.open_bus() & 0x1F);
+        self.drive_bus(status, 0xE0);
+        // Reading just before the flag is set reads it clear and keeps it from being set this
+        // frame, reading on the dot it's set or the one after still sees it but cancels the NMI.
+        // https://www.nesdev.org/wiki/PPU_frame_timing#VBL_Flag_Timing
+        match (self.scanline, self.dot) {
+            (VBLANK_SCANLINE, 1) => self.vblank_suppressed = true,
+            (VBLANK_SCANLINE, 2..=3) => self.nmi_cancelled = true,
+            _ => (),
+        }
+        self.status.set_vblank(false);
+        self.addr.reset_latch();
+        status
+    }
+
+    // What reading a write-only register returns, the last value on the PPU data bus.
+    pub fn open_bus(&mut self) -> u8 {
+        self.io_latch = self.decayed_latch();
+        self.io_latch
+    }
+
+    fn decayed_latch(&self) -> u8 {
+        let mut latch = self.io_latch;
+        if self.accuracy.contains(Accuracy::OPEN_BUS_DECAY) {
+            for bit in 0..8 {
+                if self.frames.wrapping_sub(self.latch_refreshed[bit]) >= OPEN_BUS_DECAY_FRAMES {
+                    latch &= !(1 << bit);
+                }
+            }
+        }
+        latch
+    }
+
+    // What reading a register would return, without clearing flags, moving the data buffer
+    // or driving the bus. For debuggers.
+    pub fn peek_register(&self, addr: u16) -> u8 {
+        match addr & 0x2007 {
+            0x2002 => self.status.bits() | (self.decayed_latch() & 0x1F),
+            0x2004 => self.oam_data[self.oam_addr as usize],
+            0x2007 if self.addr.get() & 0x3FFF >= 0x3F00 => {
+                self.palette_table[palette_index(self.addr.get())] | (self.decayed_latch() & 0xC0)
+            },
+            0x2007 => self.internal_data_buff,
+            _ => self.decayed_latch(),
+        }
+    }
+
+    // Puts the bits in `mask` on the data bus, every register write drives all of them.
+    pub fn drive_bus(&mut self, value: u8, mask: u8) {
+        self.io_latch = (self.io_latch & !mask) | (value & mask);
+        for bit in 0..8 {
+            if mask & (1 << bit) != 0 { self.latch_refreshed[bit] = self.frames; }
+        }
+    }
+
+    pub fn write_to_ppu_addr(&mut self, value: u8) {
+        // A second write landing on a dot where rendering increments v gets ANDed with the
+        // incremented address, the rest of the line draws from the wrong tiles.
+        // https://www.nesdev.org/wiki/PPU_scrolling
+        let conflict = self.addr.latch() && self.increments_v() && self.accuracy.contains(Accuracy::PPU_ADDR_CONFLICTS);
+        let incremented = self.addr.get();
+        self.addr.update(value, &mut self.temp);
+        if conflict {
+            log_debug!(target: "nass::ppu", scanline = self.scanline, dot = self.dot, "$2006 write conflicting with v increment");
+            self.addr.conflict(incremented);
+        }
+    }
+
+    // Whether this dot's rendering bumps coarse X (and at 256 fine/coarse Y).
+    fn increments_v(&self) -> bool {
+        let rendering_line = self.scanline < 240 || self.scanline == self.pre_render_scanline();
+        self.mask.rendering() && rendering_line && (1..=256).contains(&self.dot) && self.dot & 7 == 0
+    }
+
+    pub fn write_to_ctrl(&mut self, value: u8) -> bool {
+        let before_nmi_status = self.ctrl.generate_nmi();
+        self.ctrl.update(value, &mut self.temp);
+        if !before_nmi_status && self.ctrl.generate_nmi() && self.status.is_vblank() {
+            return true
+        }
+        false
+    }
+
+    fn increment_vram_addr(&mut self) {
+        self.addr.increment(self.ctrl.vram_addr_increment());
+    }
+
+    pub fn read_oam(&mut self) -> u8 {
+        let value = self.oam_data[self.oam_addr as usize];
+        self.drive_bus(value, 0xFF);
+        value
+    }
+
+    pub fn write_to_oam(&mut self, value: u8) {
+        self.oam_data[self.oam_addr as usize] = value;
+        self.oam_addr = self.oam_addr.wrapping_add(1);
+    }
+
+    pub fn write_data(&mut self, value: u8, mapper: &mut Mapper_) {
+        let addr = self.addr.get() & 0x3FFF;
+        self.increment_vram_addr();
+        match addr {
+            0..=0x1FFF => mapper.write_chr(addr, value),
+            0x2000..=0x2FFF => self.vram[mapper.mirror(addr) as usize] = value,
+            0x3000..=0x3EFF => self.vram[mapper.mirror(addr-0x1000) as usize] = value,
+            0x3F00..=0x3FFF => {
+                let mut addr = (addr & 0x1F) as u8;
+                if addr >= 0x10 && addr % 4 == 0 { 
+                    addr -= 0x10; 
+                }
+                self.palette_table[addr as usize] = value;
+            }
+            _ => panic!("Unexpected access to mirrored space {}", addr)
+        }
+    }
+
+    pub fn read_data(&mut self, rom: *const u8, mapper: &Mapper_) -> u8 {
+        let addr = self.addr.get() & 0x3FFF;
+        let value = self.read_vram(rom, mapper, addr);
+        // Palette entries are 6 bits, the top two come from the bus.
+        if addr >= 0x3F00 {
+            let value = value | (self.open_bus() & 0xC0);
+            self.drive_bus(value, 0x3F);
+            value
+        } else {
+            self.drive_bus(value, 0xFF);
+            value
+        }
+    }
+
+    fn read_vram(&mut self, rom: *const u8, mapper: &Mapper_, addr: u16) -> u8 {
+        self.increment_vram_addr();
+        match addr {
+            0..=0x1FFF => {
+                let result = self.internal_data_buff;
+                self.internal_data_buff = mapper.read_chr(rom, addr);
+                result
+            },
+            0x2000..=0x2FFF => {
+                let result = self.internal_data_buff;
+                self.internal_data_buff = self.vram[mapper.mirror(addr) as usize];
+                result
+            },
+            0x3000..=0x3EFF => {
+                let result = self.internal_data_buff;
+                self.internal_data_buff = self.vram[mapper.mirror(addr - 0x1000) as usize];
+                result
+            },
+            0x3F00..=0x3FFF => {
+                self.internal_data_buff = self.vram[mapper.mirror(addr) as usize];
+                let mut addr = addr & 0x1F;
+                if addr >= 0x10 && addr % 4 == 0 { 
+                    addr -= 0x10; 
+                }
+                self.palette_table[addr as usize]
+            }
+            _ => panic!("Unexpected access to mirrored space {}", addr)
+        }
+    }
+
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.palette_table);
+        w.write_bytes(&self.vram);
+        w.write_bytes(&self.oam_data);
+        // Only what hardware would hold, so states don't depend on the sprite limit.
+        w.write_bytes(&self.sprites.0[..4 * SPRITES_PER_SCANLINE]);
+        w.write_usize(self.sprites.1.min(SPRITES_PER_SCANLINE));
+        w.write_u8(self.oam_addr);
+        self.addr.save_state(w);
+        w.write_u16(self.temp);
+        w.write_u8(self.ctrl.bits());
+        w.write_u8(self.mask.bits());
+        w.write_u8(self.status.bits());
+        w.write_u8(self.internal_data_buff);
+        w.write_u8(self.io_latch);
+        self.latch_refreshed.iter().for_each(|&frame| w.write_u32(frame));
+        w.write_u32(self.frames);
+        w.write_usize(self.scanline as usize);
+        w.write_usize(self.dot as usize);
+        w.write_usize(self.frame.get_index());
+        w.write_bool(self.nmi_occured);
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        r.read_bytes(&mut self.palette_table)?;
+        r.read_bytes(&mut self.vram)?;
+        r.read_bytes(&mut self.oam_data)?;
+        r.read_bytes(&mut self.sprites.0[..4 * SPRITES_PER_SCANLINE])?;
+        self.sprites.1 = r.read_usize()?.min(SPRITES_PER_SCANLINE);
+        self.oam_addr = r.read_u8()?;
+        self.addr.load_state(r)?;
+        self.temp = r.read_u16()?;
+        self.ctrl = PPUControl::from_bits_retain(r.read_u8()?);
+        self.mask.update(r.read_u8()?);
+        self.status.update(r.read_u8()?);
+        self.internal_data_buff = r.read_u8()?;
+        if r.version() >= 6 {
+            self.io_latch = r.read_u8()?;
+            for frame in self.latch_refreshed.iter_mut() {
+                *frame = r.read_u32()?;
+            }
+            self.frames = r.read_u32()?;
+        } else {
+            (self.io_latch, self.latch_refreshed, self.frames) = (0, [0; 8], 0);
+        }
+        self.scanline = (r.read_usize()? % self.scanlines_per_frame() as usize) as u16;
+        self.dot = (r.read_usize()? % DOTS_PER_SCANLINE as usize) as u16;
+        self.frame.set_index(r.read_usize()?);
+        self.nmi_occured = r.read_bool()?;
+        self.forget_tile();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mapper::{NROM, Mirroring};
+
+    #[test]
+    fn oam_addr_wraps() {
+        let mut ppu = PPU::new();
+        let mut mapper: Mapper_ = Box::new(NROM::new(0x8000, 0x2000, 0x10, 0x8010, Mirroring::Vertical));
+        ppu.write_register(0x2003, 0xFF, &mut mapper);
+        ppu.write_register(0x2004, 0x12, &mut mapper);
+        ppu.write_register(0x2004, 0x34, &mut mapper);
+        assert_eq!(ppu.oam_addr, 0x01);
+        assert_eq!((ppu.oam_data[0xFF], ppu.oam_data[0x00]), (0x12, 0x34));
+    }
+
+    // Dots from power on until vblank starts and ends, and from one vblank to the next.
+    fn frame_timing(region: Region) -> (usize, usize, usize) {
+        let rom = vec![0; 0x10 + 0x8000 + 0x2000];
+        let mut mapper: Mapper_ = Box::new(NROM::new(0x8000, 0x2000, 0x10, 0x8010, Mirroring::Vertical));
+        let mut ppu = PPU::new();
+        ppu.set_region(region);
+        let mut edges = Vec::new();
+        let mut vblank = false;
+        for dot in 0..3 * 341 * 312 {
+            ppu.tick(rom.as_ptr(), &mut mapper);
+            if ppu.status.is_vblank() != vblank {
+                vblank = !vblank;
+                edges.push(dot);
+            }
+        }
+        (edges[0], edges[1] - edges[0], edges[2] - edges[0])
+    }
+
+    // A frame of random nametables and CHR with the pattern table switched mid-scanline, drawn
+    // with each tile fetched once and fetched again on every dot like `render_dot` used to.
+    fn random_frame(fetch_every_dot: bool) -> Vec<u32> {
+        let mut rom = vec![0; 0x10 + 0x8000 + 0x2000];
+        Rng::new(7).fill(&mut rom);
+        let mut mapper: Mapper_ = Box::new(NROM::new(0x8000, 0x2000, 0x10, 0x8010, Mirroring::Vertical));
+        let mut ppu = PPU::new();
+        ppu.power_on(PowerOn::Random, &mut Rng::new(7));
+        ppu.write_register(0x2001, 0x0A, &mut mapper);
+        for _ in 0..341 * 240 {
+            if ppu.position() == (100, 123) { ppu.write_register(0x2000, 0x10, &mut mapper); }
+            if fetch_every_dot { ppu.forget_tile(); }
+            ppu.tick(rom.as_ptr(), &mut mapper);
+        }
+        ppu.frame.pixels()[..Frame::WIDTH * Frame::HEIGHT].to_vec()
+    }
+
+    #[test]
+    fn tiles_fetched_once_match_every_dot() {
+        assert!(random_frame(false) == random_frame(true));
+    }
+
+    #[test]
+    fn frame_geometry_follows_region() {
+        assert_eq!(frame_timing(Region::Ntsc), (241 * 341 + 1, 20 * 341, 262 * 341));
+        assert_eq!(frame_timing(Region::Pal), (241 * 341 + 1, 70 * 341, 312 * 341));
+    }
+}
@@ -0,0 +1,38 @@
+use bitflags::bitflags;
+
+bitflags! {
+    // Lines drawn over the picture for ROM hackers lining up graphics. They follow the
+    // scrolled nametable space (the PPU address), not the screen, so they move with the game.
+    #[derive(Clone, Copy, PartialEq)]
+    pub struct Overlay: u8 {
+        const TILE_GRID       = 0b001; // 8x8 tiles
+        const ATTRIBUTE_GRID  = 0b010; // 16x16 attribute areas
+        const NAMETABLES      = 0b100; // Where one nametable ends and the next begins
+    }
+}
+
+// RR-GG-BB-AA like `COLORS`.
+const TILE_COLOR: u32 = 0x000000FF;
+const ATTRIBUTE_COLOR: u32 = 0xFFE000FF;
+const NAMETABLE_COLOR: u32 = 0xFF00FFFF;
+
+// `v` is the PPU address for the dot, `column` and `row` the pixel within its tile.
+pub fn overlay_color(overlay: Overlay, v: u16, column: u8, row: u16, color: u32) -> u32 {
+    let (coarse_x, coarse_y) = (v & 0x1F, (v >> 5) & 0x1F);
+    let (left, top) = (column == 0, row == 0);
+    if overlay.contains(Overlay::NAMETABLES) && ((left && coarse_x == 0) || (top && coarse_y == 0)) {
+        return NAMETABLE_COLOR
+    }
+    if overlay.contains(Overlay::ATTRIBUTE_GRID) && ((left && coarse_x & 1 == 0) || (top && coarse_y & 1 == 0)) {
+        return blend(color, ATTRIBUTE_COLOR)
+    }
+    if overlay.contains(Overlay::TILE_GRID) && (left || top) {
+        return blend(color, TILE_COLOR)
+    }
+    color
+}
+
+// Half and half, alpha stays opaque.
+fn blend(a: u32, b: u32) -> u32 {
+    (((a >> 1) & 0x7F7F7F00) + ((b >> 1) & 0x7F7F7F00)) | 0xFF
+}
@@ -1,3 +1,5 @@
+use crate::state::{StateWriter, StateReader};
+
 pub struct PPUAddr {
     value: (u8, u8),
     latch: bool
@@ -74,10 +76,16 @@ impl PPUAddr {
         let lo = self.value.1;
         self.value.1 = self.value.1.wrapping_add(inc);
         if lo > self.value.1 {
-            self.value.0 = self.value.0.wrapping_add(1);
+            // v is only 15 bits wide.
+            self.value.0 = self.value.0.wrapping_add(1) & 0x7F;
         }
     }
 
+    // Both the copy from t and a rendering increment drive v on the same dot.
+    pub fn conflict(&mut self, incremented: u16) {
+        self.set(self.get() & incremented);
+    }
+
     pub fn reset_latch(&mut self) {
         self.latch = false;
     }
@@ -93,4 +101,32 @@ impl PPUAddr {
     pub fn get(&self) -> u16 {
         ( ( self.value.0 as u16 ) << 8 ) | ( self.value.1 as u16 )
     }
+
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.get());
+        w.write_bool(self.latch);
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        let value = r.read_u16()?;
+        self.set(value);
+        self.latch = r.read_bool()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn increment_wraps_at_15_bits() {
+        let mut addr = PPUAddr::new();
+        addr.set(0x7FFF);
+        addr.increment(1);
+        assert_eq!(addr.get(), 0x0000);
+        addr.set(0x7FF0);
+        addr.increment(32);
+        assert_eq!(addr.get(), 0x0010);
+    }
 }
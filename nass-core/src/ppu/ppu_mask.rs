@@ -22,6 +22,15 @@ impl PPUMask {
         *self = PPUMask::from_bits_truncate(data);
     }
 
+    pub fn greyscale(&self) -> bool {
+        self.intersects(PPUMask::GREYSCALE)
+    }
+
+    // The three emphasis bits, red in bit 0.
+    pub fn emphasis(&self) -> usize {
+        (self.bits() >> 5) as usize
+    }
+
     pub fn rendering(&self) -> bool {
         self.show_sprite() || self.show_background()
     }
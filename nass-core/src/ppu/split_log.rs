@@ -0,0 +1,48 @@
+// Scroll and nametable writes ($2000, $2005, $2006) that land while the picture is being
+// drawn, the raster splits games make without a mapper IRQ (status bars, sprite 0 splits,
+// parallax). Kept for the frame being drawn and the last completed one, so frontends can
+// upscale or overlay each part of the screen on its own.
+// https://www.nesdev.org/wiki/PPU_scrolling#Split_X_scroll
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Split {
+    pub scanline: u16,
+    pub dot: u16,
+    pub register: u8, // 0, 5 or 6 for $2000, $2005 and $2006
+    pub value: u8,
+}
+
+pub struct SplitLog {
+    pub enabled: bool,
+    current: Vec<Split>,
+    last: Vec<Split>,
+}
+
+impl SplitLog {
+    pub fn new() -> Self {
+        SplitLog { enabled: false, current: Vec::new(), last: Vec::new() }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        cfg!(feature = "instrumentation") && self.enabled
+    }
+
+    pub fn record(&mut self, (scanline, dot): (u16, u16), addr: u16, value: u8) {
+        self.current.push(Split { scanline, dot, register: (addr & 0x7) as u8, value });
+    }
+
+    pub fn end_frame(&mut self) {
+        self.last = std::mem::take(&mut self.current);
+    }
+
+    pub fn last(&self) -> &[Split] {
+        &self.last
+    }
+
+    // Scanlines of the last frame where a split took effect, each one once.
+    pub fn lines(&self) -> Vec<u16> {
+        let mut lines: Vec<u16> = self.last.iter().map(|split| split.scanline).collect();
+        lines.dedup();
+        lines
+    }
+}
@@ -0,0 +1,37 @@
+// Per visible scanline, the OAM entries (bit n is sprite n) picked into secondary OAM and
+// the ones that were in range but dropped past the 8-sprite limit. Kept for the frame being
+// drawn and the last completed one, for "sprites dropped" diagnostics.
+pub struct SpriteLog {
+    pub enabled: bool,
+    current: [[u64; 2]; 240],
+    last: [[u64; 2]; 240],
+}
+
+impl SpriteLog {
+    pub fn new() -> Self {
+        SpriteLog { enabled: false, current: [[0; 2]; 240], last: [[0; 2]; 240] }
+    }
+
+    // Off in builds without the "instrumentation" feature, whatever `enabled` says.
+    pub fn is_enabled(&self) -> bool {
+        cfg!(feature = "instrumentation") && self.enabled
+    }
+
+    pub fn selected(&mut self, scanline: usize, sprite: usize) {
+        if let Some(line) = self.current.get_mut(scanline) { line[0] |= 1 << sprite; }
+    }
+
+    pub fn dropped(&mut self, scanline: usize, sprite: usize) {
+        if let Some(line) = self.current.get_mut(scanline) { line[1] |= 1 << sprite; }
+    }
+
+    pub fn end_frame(&mut self) {
+        self.last = self.current;
+        self.current = [[0; 2]; 240];
+    }
+
+    // (selected, dropped) masks for each scanline of the last frame.
+    pub fn get_pointer(&self) -> *const u64 {
+        self.last.as_ptr() as *const u64
+    }
+}
@@ -0,0 +1,156 @@
+// Lookup tables that replace per-pixel bit twiddling in the renderer and the dumps.
+// https://www.nesdev.org/wiki/PPU_pattern_tables
+// https://www.nesdev.org/wiki/PPU_attribute_tables
+
+// Bit n of a pattern plane moved to bit 2n, so a tile row's two planes interleave into one
+// u16 of 2-bit pixels with `tile_row`.
+const PLANE_SPREAD: [u16; 256] = plane_spread();
+
+// Shift of a tile's palette inside its attribute byte, indexed by the low 10 bits of the
+// VRAM address (coarse X and Y).
+pub const ATTRIBUTE_SHIFT: [u8; 1024] = attribute_shift();
+
+const fn plane_spread() -> [u16; 256] {
+    let mut table = [0; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let mut bit = 0;
+        while bit < 8 {
+            table[byte] |= ((byte as u16 >> bit) & 1) << (2 * bit);
+            bit += 1;
+        }
+        byte += 1;
+    }
+    table
+}
+
+const fn attribute_shift() -> [u8; 1024] {
+    let mut table = [0; 1024];
+    let mut v = 0;
+    while v < 1024 {
+        let (column, row) = (v & 0x1F, v >> 5);
+        table[v] = ((row & 2) << 1 | (column & 2)) as u8;
+        v += 1;
+    }
+    table
+}
+
+pub fn tile_row(plane_0: u8, plane_1: u8) -> u16 {
+    PLANE_SPREAD[plane_0 as usize] | PLANE_SPREAD[plane_1 as usize] << 1
+}
+
+// Color (0-3) of the pixel at `bit` of an interleaved row, 7 being the leftmost.
+pub fn tile_pixel(row: u16, bit: usize) -> u8 {
+    (row >> (2 * bit)) as u8 & 3
+}
+
+// The 8 pixels of an interleaved row as palette RAM indices (`palette` << 2 | color), leftmost
+// first. Color 0 is the backdrop, index 0 whatever the palette.
+#[cfg(not(feature = "simd"))]
+pub fn background_row(row: u16, palette: u8) -> [u8; 8] {
+    let mut pixels = [0; 8];
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let color = tile_pixel(row, 7 - i);
+        if color > 0 { *pixel = palette << 2 | color; }
+    }
+    pixels
+}
+
+// All 8 lanes at once, see the scalar version above.
+#[cfg(feature = "simd")]
+pub fn background_row(row: u16, palette: u8) -> [u8; 8] {
+    use std::simd::prelude::*;
+    let shifts = u16x8::from_array([14, 12, 10, 8, 6, 4, 2, 0]);
+    let colors = ((u16x8::splat(row) >> shifts) & u16x8::splat(3)).cast::<u8>();
+    colors.simd_ne(u8x8::splat(0)).select(colors | u8x8::splat(palette << 2), u8x8::splat(0)).to_array()
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+    use super::*;
+    use test::{Bencher, black_box};
+
+    // What the tables replaced.
+    fn pixel_bits(plane_0: u8, plane_1: u8, bit: usize) -> u8 {
+        ((plane_1 >> bit) & 1) << 1 | ((plane_0 >> bit) & 1)
+    }
+
+    fn attribute_quadrant_shift(v: usize) -> u8 {
+        let (column, row) = ((v & 0x1F) as u8, (v >> 5) as u8);
+        ((row & 0x2) + ((column & 0x2) >> 1)) * 2
+    }
+
+    #[test]
+    fn tile_row_matches_bits() {
+        for plane_0 in 0..=255 {
+            for plane_1 in 0..=255 {
+                let row = tile_row(plane_0, plane_1);
+                assert!((0..8).all(|bit| tile_pixel(row, bit) == pixel_bits(plane_0, plane_1, bit)));
+            }
+        }
+    }
+
+    #[test]
+    fn background_row_matches_pixels() {
+        for row in 0..=u16::MAX {
+            for palette in 0..4 {
+                let pixels = background_row(row, palette);
+                assert!((0..8).all(|i| match tile_pixel(row, 7 - i) {
+                    0 => pixels[i] == 0,
+                    color => pixels[i] == palette << 2 | color,
+                }));
+            }
+        }
+    }
+
+    #[test]
+    fn attribute_shift_matches_quadrants() {
+        assert!((0..1024).all(|v| ATTRIBUTE_SHIFT[v] == attribute_quadrant_shift(v)));
+    }
+
+    // A row of 8 pixels per pair of planes, like the dumps.
+    #[bench]
+    fn rows_lookup(b: &mut Bencher) {
+        b.iter(|| (0..=255u8).map(|p| {
+            let row = tile_row(black_box(p), black_box(p ^ 0x5A));
+            (0..8).map(|bit| tile_pixel(row, bit) as u32).sum::<u32>()
+        }).sum::<u32>());
+    }
+
+    #[bench]
+    fn rows_bits(b: &mut Bencher) {
+        b.iter(|| (0..=255u8).map(|p| {
+            let (plane_0, plane_1) = (black_box(p), black_box(p ^ 0x5A));
+            (0..8).map(|bit| pixel_bits(plane_0, plane_1, bit) as u32).sum::<u32>()
+        }).sum::<u32>());
+    }
+
+    // The same rows with their palette applied, like the renderer's tile fetches.
+    #[bench]
+    fn background_rows(b: &mut Bencher) {
+        b.iter(|| (0..=255u8).map(|p| {
+            let row = tile_row(black_box(p), black_box(p ^ 0x5A));
+            background_row(row, p & 3).iter().map(|&pixel| pixel as u32).sum::<u32>()
+        }).sum::<u32>());
+    }
+
+    // One pixel and its palette per pair of planes, fetching on every dot.
+    #[bench]
+    fn dots_lookup(b: &mut Bencher) {
+        b.iter(|| (0..1024usize).map(|v| {
+            let p = v as u8;
+            let color = tile_pixel(tile_row(black_box(p), black_box(p ^ 0x5A)), v & 7);
+            (color | black_box(0xE4u8) >> ATTRIBUTE_SHIFT[v] << 2) as u32
+        }).sum::<u32>());
+    }
+
+    #[bench]
+    fn dots_bits(b: &mut Bencher) {
+        b.iter(|| (0..1024usize).map(|v| {
+            let p = v as u8;
+            let color = pixel_bits(black_box(p), black_box(p ^ 0x5A), v & 7);
+            (color | black_box(0xE4u8) >> attribute_quadrant_shift(v) << 2) as u32
+        }).sum::<u32>());
+    }
+}
@@ -0,0 +1,39 @@
+use crate::condition::Condition;
+
+// Speedrun practice on top of savestates: a savepoint to go back to, an optional RAM
+// condition that means the attempt failed (the player died), and how many attempts were
+// made since the savepoint was set. The condition is checked once per frame with nothing
+// kept from the frame before, so d$ operands read the current value.
+pub struct Practice {
+    pub savepoint: Option<Vec<u8>>,
+    pub failure: Option<Condition>,
+    failed: bool, // The condition held at the last check, only a new failure retries
+    pub attempts: u32,
+}
+
+impl Practice {
+    pub fn new() -> Self {
+        Practice { savepoint: None, failure: None, failed: false, attempts: 0 }
+    }
+
+    pub fn set_savepoint(&mut self, state: Vec<u8>) {
+        self.savepoint = Some(state);
+        self.attempts = 0;
+        self.failed = false;
+    }
+
+    // Whether the attempt just failed, true once per failure.
+    pub fn check(&mut self, peek: impl Fn(u16) -> u8) -> bool {
+        let failed = self.savepoint.is_some() && self.failure.is_some_and(|condition| condition.check(&peek, &peek));
+        let new_failure = failed && !self.failed;
+        self.failed = failed;
+        new_failure
+    }
+
+    // Counts a retry, the caller loads the savepoint. A condition still true after going
+    // back has to turn false before it counts again.
+    pub fn retry(&mut self) {
+        self.attempts = self.attempts.wrapping_add(1);
+        self.failed = true;
+    }
+}
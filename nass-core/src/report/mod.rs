@@ -0,0 +1,37 @@
+use std::{cell::RefCell, fmt::Write, panic, sync::Once};
+use crate::{hash::crc32, zip::ZipWriter};
+
+thread_local!{ static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) } }
+static PANIC_HOOK: Once = Once::new();
+
+// Remembers the last panic message so the next report can include it, the emulator
+// itself is still borrowed while the hook runs.
+pub fn record_panics() {
+    PANIC_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            LAST_PANIC.with_borrow_mut(|p| *p = Some(info.to_string()));
+            previous(info);
+        }));
+    });
+}
+
+// Zips everything needed to look into a bug report: the savestate, the instruction trace,
+// ROM checksums and the emulator settings.
+pub fn bundle(state: &[u8], trace: &str, rom: &[u8], config: &str) -> Vec<u8> {
+    let mut info = String::new();
+    let _ = writeln!(info, "nass {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(info, "rom size: {}", rom.len());
+    let _ = writeln!(info, "rom crc32: {:08X}", crc32(rom));
+    let _ = writeln!(info, "rom crc32 (no header): {:08X}", crc32(rom.get(16..).unwrap_or(&[])));
+    if let Some(message) = LAST_PANIC.with_borrow(|p| p.clone()) {
+        let _ = writeln!(info, "last panic: {message}");
+    }
+
+    let mut zip = ZipWriter::new();
+    zip.add("info.txt", info.as_bytes());
+    zip.add("config.txt", config.as_bytes());
+    zip.add("trace.log", trace.as_bytes());
+    zip.add("state.bin", state);
+    zip.finish()
+}
@@ -0,0 +1,39 @@
+use crate::state::{StateWriter, StateReader};
+
+// xorshift64*, small and reproducible across platforms. Everything random in the core draws
+// from the one on the bus, which is part of savestates, so rewind and replays stay exact.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // The all-zero state never leaves zero.
+        Rng { state: seed ^ 0x9E37_79B9_7F4A_7C15 }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn fill(&mut self, bytes: &mut [u8]) {
+        for chunk in bytes.chunks_mut(8) {
+            let value = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&value[..chunk.len()]);
+        }
+    }
+
+    pub fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.state.to_le_bytes());
+    }
+
+    pub fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        let mut state = [0; 8];
+        r.read_bytes(&mut state)?;
+        self.state = u64::from_le_bytes(state);
+        Ok(())
+    }
+}
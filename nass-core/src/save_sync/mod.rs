@@ -0,0 +1,86 @@
+// Incremental persistence: instead of rewriting whole save files, the core hands the bytes
+// that changed since the last sync to a store the frontend provides (cloud storage, IndexedDB).
+// The first sync of a ROM sends everything, the store applies diffs in order on its copy.
+
+// Changed runs closer than this are sent as one, a diff header costs about as much.
+const MERGE_GAP: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SyncKind {
+    Save = 0, // Battery RAM or EEPROM
+    State = 1, // The last `save_state`
+}
+
+// `bytes` replace the old contents at `offset`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Diff {
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+}
+
+pub trait SyncStore {
+    // `len` is the new total length, the store truncates or grows its copy to it first.
+    fn write(&mut self, kind: SyncKind, len: usize, diffs: &[Diff]);
+}
+
+pub fn diff(old: &[u8], new: &[u8]) -> Vec<Diff> {
+    let mut diffs: Vec<Diff> = Vec::new();
+    let mut i = 0;
+    while i < new.len() {
+        if old.get(i) == Some(&new[i]) {
+            i += 1;
+            continue
+        }
+        let start = i;
+        while i < new.len() && old.get(i) != Some(&new[i]) { i += 1; }
+        match diffs.last_mut() {
+            Some(last) if start - (last.offset + last.bytes.len()) < MERGE_GAP => {
+                last.bytes.extend_from_slice(&new[last.offset + last.bytes.len()..i]);
+            },
+            _ => diffs.push(Diff { offset: start, bytes: new[start..i].to_vec() }),
+        }
+    }
+    diffs
+}
+
+// Flat form for the wasm frontend: new length u32, then offset u32, length u32 and the bytes
+// of every diff, little-endian.
+pub fn encode(len: usize, diffs: &[Diff]) -> Vec<u8> {
+    let mut out = (len as u32).to_le_bytes().to_vec();
+    for diff in diffs {
+        out.extend_from_slice(&(diff.offset as u32).to_le_bytes());
+        out.extend_from_slice(&(diff.bytes.len() as u32).to_le_bytes());
+        out.extend_from_slice(&diff.bytes);
+    }
+    out
+}
+
+// What the store was last given, per `SyncKind`.
+#[derive(Default)]
+pub struct SaveSync {
+    pub store: Option<Box<dyn SyncStore>>,
+    synced: [Vec<u8>; 2],
+}
+
+impl SaveSync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Diffs `data` against the last sync and passes them to the store, if any.
+    pub fn sync(&mut self, kind: SyncKind, data: &[u8]) -> Vec<Diff> {
+        let diffs = diff(&self.synced[kind as usize], data);
+        if let Some(store) = self.store.as_mut() { store.write(kind, data.len(), &diffs); }
+        self.mark_synced(kind, data);
+        diffs
+    }
+
+    // The store already has `data`, like a save the frontend just imported.
+    pub fn mark_synced(&mut self, kind: SyncKind, data: &[u8]) {
+        self.synced[kind as usize] = data.to_vec();
+    }
+
+    pub fn clear(&mut self) {
+        self.synced = Default::default();
+    }
+}
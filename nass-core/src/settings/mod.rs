@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use crate::{accuracy::Accuracy, clock::Region, input::DeviceKind};
+
+// Overrides for one game, unset fields keep the emulator-wide setting.
+#[derive(Default, Clone, Copy)]
+pub struct GameSettings {
+    pub accuracy: Option<Accuracy>,
+    pub overclock: Option<u16>,
+    pub region: Option<Region>,
+    pub ports: [Option<DeviceKind>; 3],
+    pub flicker_reduction: Option<bool>,
+}
+
+// Per-game settings keyed by the CRC-32 of the ROM without its header (see
+// `header::rom_crc32`), applied whenever that ROM powers on. One game per line:
+//   <crc32 hex> [accuracy=fast|balanced|accurate|<flag bits>] [overclock=<lines>]
+//               [region=ntsc|pal] [port1=<DeviceKind>] [port2=..] [expansion=..] [flicker=0|1]
+// Empty lines and lines starting with # are skipped.
+#[derive(Default)]
+pub struct SettingsDb {
+    games: HashMap<u32, GameSettings>,
+}
+
+impl SettingsDb {
+    pub fn new() -> Self {
+        SettingsDb { games: HashMap::new() }
+    }
+
+    // Adds the entries in `text`, returns how many there were.
+    pub fn load(&mut self, text: &str) -> Result<usize, String> {
+        let mut count = 0;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+            let (crc, settings) = parse_line(line).map_err(|e| format!("Settings line {}: {e}", number + 1))?;
+            self.games.insert(crc, settings);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    pub fn get(&self, crc: u32) -> Option<&GameSettings> {
+        self.games.get(&crc)
+    }
+}
+
+fn parse_line(line: &str) -> Result<(u32, GameSettings), String> {
+    let mut fields = line.split_whitespace();
+    let crc = fields.next().unwrap_or("");
+    let crc = u32::from_str_radix(crc, 16).map_err(|_| format!("bad checksum {crc}"))?;
+    let mut settings = GameSettings::default();
+    for field in fields {
+        let (key, value) = field.split_once('=').ok_or(format!("expected key=value, got {field}"))?;
+        let bad_value = || format!("bad value for {key}: {value}");
+        let device = || value.parse().ok().and_then(|kind| DeviceKind::from_u8(kind).ok()).ok_or(bad_value());
+        match key {
+            "accuracy" => settings.accuracy = Some(match value {
+                "fast" => Accuracy::FAST,
+                "balanced" => Accuracy::BALANCED,
+                "accurate" => Accuracy::ACCURATE,
+                _ => Accuracy::from_bits_truncate(value.parse().map_err(|_| bad_value())?),
+            }),
+            "overclock" => settings.overclock = Some(value.parse().map_err(|_| bad_value())?),
+            "region" => settings.region = Some(match value {
+                "ntsc" => Region::Ntsc,
+                "pal" => Region::Pal,
+                _ => return Err(bad_value()),
+            }),
+            "port1" => settings.ports[0] = Some(device()?),
+            "port2" => settings.ports[1] = Some(device()?),
+            "expansion" => settings.ports[2] = Some(device()?),
+            "flicker" => settings.flicker_reduction = Some(match value { "0" => false, "1" => true, _ => return Err(bad_value()) }),
+            _ => return Err(format!("unknown key {key}")),
+        }
+    }
+    Ok((crc, settings))
+}
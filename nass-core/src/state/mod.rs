@@ -0,0 +1,182 @@
+mod resume;
+pub use resume::Resume;
+use crate::hash::crc32;
+
+// Savestates are a flat little-endian byte stream, every component writes its fields
+// in declaration order and reads them back in the same order.
+pub const STATE_MAGIC: [u8; 4] = *b"NASS";
+pub const STATE_VERSION: u8 = 14;
+// Older states still load: components check `StateReader::version` for fields added since
+// and fill in power-on values, saving again writes the current version.
+//  14: CNROM CHR banks past 32KB
+//  13: PRG-RAM enable and write protect bits (MMC1, MMC3)
+//  12: random number generator
+//  11: Famicom microphone
+//  10: checksummed chunks, see `Chunk`
+//   9: CRC-32 of the ROM after the version
+//   8: lag frame counter
+//   7: overclock pause
+//   6: PPU open bus latch and decay timers
+//   5: expansion port
+pub const OLDEST_STATE_VERSION: u8 = 4;
+
+// Since version 10 the machine is written in chunks, each framed by its id, length and
+// CRC-32, so a damaged state still loads with only the broken parts powered on again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chunk {
+    Cpu, // Registers, RAM, clock and interrupt lines
+    Ports,
+    Ppu,
+    Mapper,
+}
+
+impl Chunk {
+    pub const ALL: [Chunk; 4] = [Chunk::Cpu, Chunk::Ports, Chunk::Ppu, Chunk::Mapper];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Chunk::Cpu => "CPU",
+            Chunk::Ports => "input ports",
+            Chunk::Ppu => "PPU",
+            Chunk::Mapper => "mapper",
+        }
+    }
+}
+
+pub struct ChunkData<'a> {
+    pub chunk: Chunk,
+    pub bytes: &'a [u8],
+    pub valid: bool, // The checksum matches
+}
+
+// Splits what follows the header into its chunks.
+pub fn read_chunks(data: &[u8]) -> Result<Vec<ChunkData<'_>>, String> {
+    let mut r = StateReader::new(data);
+    let mut chunks = Vec::new();
+    for chunk in Chunk::ALL {
+        if r.read_u8()? != chunk as u8 { return Err(format!("Savestate is missing the {} chunk.", chunk.name())) }
+        let len = r.read_u32()? as usize;
+        let crc = r.read_u32()?;
+        let bytes = data.get(r.pos..r.pos + len).ok_or("Savestate is truncated.".to_string())?;
+        r.pos += len;
+        chunks.push(ChunkData { chunk, bytes, valid: crc32(bytes) == crc });
+    }
+    Ok(chunks)
+}
+
+pub struct StateWriter {
+    data: Vec<u8>,
+    chunks: Vec<(Chunk, usize)>, // Where each chunk starts in `data`
+}
+
+impl StateWriter {
+    pub fn new() -> Self {
+        StateWriter { data: Vec::new(), chunks: Vec::new() }
+    }
+
+    // Everything written from here to the next chunk goes into `chunk`.
+    pub fn begin_chunk(&mut self, chunk: Chunk) {
+        self.chunks.push((chunk, self.data.len()));
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.data.push(value as u8);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_usize(&mut self, value: usize) {
+        self.data.extend_from_slice(&(value as u64).to_le_bytes());
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        let Some(&(_, first)) = self.chunks.first() else { return self.data };
+        let mut data = self.data[..first].to_vec();
+        for (i, &(chunk, start)) in self.chunks.iter().enumerate() {
+            let end = self.chunks.get(i + 1).map_or(self.data.len(), |next| next.1);
+            let bytes = &self.data[start..end];
+            data.push(chunk as u8);
+            data.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            data.extend_from_slice(&crc32(bytes).to_le_bytes());
+            data.extend_from_slice(bytes);
+        }
+        data
+    }
+}
+
+#[derive(Clone)]
+pub struct StateReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    version: u8,
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        StateReader { data, pos: 0, version: STATE_VERSION }
+    }
+
+    // Version of the state being read, the current one unless set from its header.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    // What's left to read.
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.data[self.pos..]
+    }
+
+    pub fn read_bytes(&mut self, bytes: &mut [u8]) -> Result<(), String> {
+        let end = self.pos + bytes.len();
+        if end > self.data.len() { return Err("Savestate is truncated.".to_string()) }
+        bytes.copy_from_slice(&self.data[self.pos..end]);
+        self.pos = end;
+        Ok(())
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, String> {
+        let mut bytes = [0; 1];
+        self.read_bytes(&mut bytes)?;
+        Ok(bytes[0])
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, String> {
+        Ok(self.read_u8()? != 0)
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, String> {
+        let mut bytes = [0; 2];
+        self.read_bytes(&mut bytes)?;
+        Ok(u16::from_le_bytes(bytes))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, String> {
+        let mut bytes = [0; 4];
+        self.read_bytes(&mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    pub fn read_usize(&mut self) -> Result<usize, String> {
+        let mut bytes = [0; 8];
+        self.read_bytes(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes) as usize)
+    }
+}
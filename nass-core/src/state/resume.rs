@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+use super::{StateWriter, StateReader};
+
+// A resume snapshot is a savestate tagged with the CRC-32 of its ROM: "NASR", the CRC and
+// the state. The frontend writes one out when it closes and hands it back on the next
+// start, loading the same ROM then picks up where the player left.
+pub const RESUME_MAGIC: [u8; 4] = *b"NASR";
+
+#[derive(Default)]
+pub struct Resume {
+    pub auto: bool, // Restore on ROM load instead of waiting for `Emulator::resume`
+    snapshots: HashMap<u32, Vec<u8>>,
+}
+
+impl Resume {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, crc: u32) -> Option<&[u8]> {
+        self.snapshots.get(&crc).map(Vec::as_slice)
+    }
+
+    pub fn insert(&mut self, crc: u32, state: Vec<u8>) {
+        self.snapshots.insert(crc, state);
+    }
+
+    pub fn remove(&mut self, crc: u32) {
+        self.snapshots.remove(&crc);
+    }
+
+    pub fn encode(crc: u32, state: &[u8]) -> Vec<u8> {
+        let mut w = StateWriter::new();
+        w.write_bytes(&RESUME_MAGIC);
+        w.write_u32(crc);
+        w.write_bytes(state);
+        w.into_inner()
+    }
+
+    // Keeps a snapshot the frontend stored, returns the CRC of its ROM.
+    pub fn load(&mut self, bytes: &[u8]) -> Result<u32, String> {
+        let mut r = StateReader::new(bytes);
+        let mut magic = [0; 4];
+        r.read_bytes(&mut magic)?;
+        if magic != RESUME_MAGIC { return Err("Not a resume snapshot.".to_string()) }
+        let crc = r.read_u32()?;
+        self.insert(crc, bytes[8..].to_vec());
+        Ok(crc)
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use crate::clock::Region;
+
+// Microseconds per frame.
+pub const NTSC_FRAME_US: u64 = 16_639;
+pub const PAL_FRAME_US: u64 = 19_997;
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct RomStats {
+    pub frames: u64,
+    pub play_us: u64, // Emulated time, frames at the region's rate
+    pub sessions: u32, // Times the ROM was loaded
+    pub resets: u32,
+    pub state_saves: u32,
+    pub state_loads: u32,
+}
+
+impl RomStats {
+    pub fn add_frames(&mut self, frames: u64, region: Region) {
+        self.frames += frames;
+        self.play_us += frames * if region == Region::Pal { PAL_FRAME_US } else { NTSC_FRAME_US };
+    }
+
+    pub fn play_seconds(&self) -> u64 {
+        self.play_us / 1_000_000
+    }
+}
+
+// Library statistics keyed by the CRC-32 of the ROM without its header. The core has no
+// files of its own, the frontend keeps the text in its config directory:
+//   <crc32 hex> frames=.. play_us=.. sessions=.. resets=.. state_saves=.. state_loads=..
+// Empty lines and lines starting with # are skipped.
+#[derive(Default)]
+pub struct Stats {
+    roms: HashMap<u32, RomStats>,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Stats { roms: HashMap::new() }
+    }
+
+    pub fn get(&self, crc: u32) -> Option<&RomStats> {
+        self.roms.get(&crc)
+    }
+
+    pub fn entry(&mut self, crc: u32) -> &mut RomStats {
+        self.roms.entry(crc).or_default()
+    }
+
+    // Adds the entries in `text`, replacing ROMs already known. Returns how many there were.
+    pub fn load(&mut self, text: &str) -> Result<usize, String> {
+        let mut count = 0;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+            let (crc, stats) = parse_line(line).map_err(|e| format!("Stats line {}: {e}", number + 1))?;
+            self.roms.insert(crc, stats);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut crcs: Vec<&u32> = self.roms.keys().collect();
+        crcs.sort();
+        let mut text = String::new();
+        for crc in crcs {
+            let s = &self.roms[crc];
+            let _ = writeln!(text, "{crc:08X} frames={} play_us={} sessions={} resets={} state_saves={} state_loads={}",
+                s.frames, s.play_us, s.sessions, s.resets, s.state_saves, s.state_loads);
+        }
+        text
+    }
+}
+
+fn parse_line(line: &str) -> Result<(u32, RomStats), String> {
+    let mut fields = line.split_whitespace();
+    let crc = fields.next().unwrap_or("");
+    let crc = u32::from_str_radix(crc, 16).map_err(|_| format!("bad checksum {crc}"))?;
+    let mut stats = RomStats::default();
+    for field in fields {
+        let (key, value) = field.split_once('=').ok_or(format!("expected key=value, got {field}"))?;
+        let bad_value = || format!("bad value for {key}: {value}");
+        match key {
+            "frames" => stats.frames = value.parse().map_err(|_| bad_value())?,
+            "play_us" => stats.play_us = value.parse().map_err(|_| bad_value())?,
+            "sessions" => stats.sessions = value.parse().map_err(|_| bad_value())?,
+            "resets" => stats.resets = value.parse().map_err(|_| bad_value())?,
+            "state_saves" => stats.state_saves = value.parse().map_err(|_| bad_value())?,
+            "state_loads" => stats.state_loads = value.parse().map_err(|_| bad_value())?,
+            _ => return Err(format!("unknown key {key}")),
+        }
+    }
+    Ok((crc, stats))
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+// Character table (.tbl) for reading and searching game text. One entry per line:
+//   41=A        byte(s) in hex = text, several bytes for dictionary (DTE/MTE) entries
+//   *FE         line break
+//   /FF         end of string
+// Empty lines and lines starting with # are skipped, a line break or end of string is
+// written as \n. Bytes not in the table come out as [XX].
+pub struct Table {
+    entries: HashMap<Vec<u8>, String>,
+    longest: usize,
+}
+
+impl Table {
+    pub fn parse(text: &str) -> Result<Table, String> {
+        let mut table = Table { entries: HashMap::new(), longest: 1 };
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.trim().is_empty() || line.starts_with('#') { continue }
+            let (hex, value) = match line.split_once('=') {
+                Some((hex, value)) => (hex, value.to_string()),
+                None if line.starts_with(['*', '/']) => (&line[1..], "\n".to_string()),
+                None => return Err(format!("Table line {}: missing =", number + 1)),
+            };
+            let bytes = parse_hex(hex.trim()).ok_or(format!("Table line {}: bad hex {hex}", number + 1))?;
+            table.longest = table.longest.max(bytes.len());
+            table.entries.insert(bytes, value);
+        }
+        Ok(table)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    // Longest entry first, so dictionary entries win over single characters.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        let mut text = String::new();
+        let mut i = 0;
+        'bytes: while i < bytes.len() {
+            for length in (1..=self.longest.min(bytes.len() - i)).rev() {
+                if let Some(value) = self.entries.get(&bytes[i..i + length]) {
+                    text.push_str(value);
+                    i += length;
+                    continue 'bytes;
+                }
+            }
+            text.push_str(&format!("[{:02X}]", bytes[i]));
+            i += 1;
+        }
+        text
+    }
+
+    // The bytes a game would store `text` as, None if a character isn't in the table.
+    pub fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let mut rest = text;
+        while !rest.is_empty() {
+            let (key, value) = self.entries.iter()
+                .filter(|(_, value)| !value.is_empty() && rest.starts_with(value.as_str()))
+                .max_by_key(|(key, value)| (value.len(), std::cmp::Reverse(key.len())))?;
+            bytes.extend_from_slice(key);
+            rest = &rest[value.len()..];
+        }
+        Some(bytes)
+    }
+
+    // Offsets in `memory` where `text` is stored.
+    pub fn search(&self, memory: &[u8], text: &str) -> Vec<usize> {
+        match self.encode(text) {
+            Some(needle) if !needle.is_empty() => memory.windows(needle.len())
+                .enumerate()
+                .filter(|(_, window)| *window == needle.as_slice())
+                .map(|(offset, _)| offset)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.is_empty() { return None }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
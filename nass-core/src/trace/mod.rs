@@ -0,0 +1,39 @@
+use std::collections::VecDeque;
+
+// Instruction trace of the last few frames, one text block per frame. Disabled (and free)
+// while the frame count is 0.
+pub struct TraceLog {
+    frames: usize,
+    log: VecDeque<String>,
+}
+
+impl TraceLog {
+    pub fn new() -> Self {
+        TraceLog { frames: 0, log: VecDeque::new() }
+    }
+
+    pub fn set_frames(&mut self, frames: usize) {
+        self.frames = frames;
+        while self.log.len() > frames { self.log.pop_front(); }
+    }
+
+    // Always off in builds without the "instrumentation" feature.
+    pub fn is_enabled(&self) -> bool {
+        cfg!(feature = "instrumentation") && self.frames > 0
+    }
+
+    pub fn start_frame(&mut self) {
+        if !self.is_enabled() { return }
+        if self.log.len() == self.frames { self.log.pop_front(); }
+        self.log.push_back(String::new());
+    }
+
+    // Current frame's text, lines are expected to end with a newline.
+    pub fn current(&mut self) -> Option<&mut String> {
+        self.log.back_mut()
+    }
+
+    pub fn dump(&self) -> String {
+        self.log.iter().map(String::as_str).collect()
+    }
+}
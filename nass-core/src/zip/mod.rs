@@ -0,0 +1,57 @@
+use crate::hash::crc32;
+
+// Just enough of the zip format to hand a few files to the user: every entry is stored
+// uncompressed and dated 1980-01-01.
+// https://pkware.cachefly.net/webdocs/casestudies/APPNOTE.TXT
+pub struct ZipWriter {
+    data: Vec<u8>,
+    directory: Vec<u8>,
+    entries: u16,
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        ZipWriter { data: Vec::new(), directory: Vec::new(), entries: 0 }
+    }
+
+    pub fn add(&mut self, name: &str, contents: &[u8]) {
+        let offset = self.data.len() as u32;
+        let crc = crc32(contents);
+        let size = contents.len() as u32;
+
+        self.data.extend_from_slice(&0x0403_4B50u32.to_le_bytes());
+        self.data.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]); // version, flags, method, time, date
+        self.data.extend_from_slice(&crc.to_le_bytes());
+        self.data.extend_from_slice(&size.to_le_bytes());
+        self.data.extend_from_slice(&size.to_le_bytes());
+        self.data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        self.data.extend_from_slice(&[0, 0]);
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.extend_from_slice(contents);
+
+        self.directory.extend_from_slice(&0x0201_4B50u32.to_le_bytes());
+        self.directory.extend_from_slice(&[20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]); // versions, flags, method, time, date
+        self.directory.extend_from_slice(&crc.to_le_bytes());
+        self.directory.extend_from_slice(&size.to_le_bytes());
+        self.directory.extend_from_slice(&size.to_le_bytes());
+        self.directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        self.directory.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+        self.directory.extend_from_slice(&offset.to_le_bytes());
+        self.directory.extend_from_slice(name.as_bytes());
+        self.entries += 1;
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let offset = self.data.len() as u32;
+        let size = self.directory.len() as u32;
+        self.data.append(&mut self.directory);
+        self.data.extend_from_slice(&0x0605_4B50u32.to_le_bytes());
+        self.data.extend_from_slice(&[0, 0, 0, 0]); // disk numbers
+        self.data.extend_from_slice(&self.entries.to_le_bytes());
+        self.data.extend_from_slice(&self.entries.to_le_bytes());
+        self.data.extend_from_slice(&size.to_le_bytes());
+        self.data.extend_from_slice(&offset.to_le_bytes());
+        self.data.extend_from_slice(&[0, 0]);
+        self.data
+    }
+}
@@ -0,0 +1,138 @@
+// Frames of synthetic ROMs checked against reference PNGs in tests/fixtures/golden, with
+// `compare_frame_png`. Failing frames leave a `frame_diff_png` (reference, frame, differences)
+// in target/tmp. NASS_BLESS=1 rewrites the references from the current build, look at them
+// before committing.
+use nass_core::Emulator;
+use std::path::Path;
+
+const FRAMES: usize = 3;
+
+// Straight-line setup code, register writes only.
+struct Asm(Vec<u8>);
+
+impl Asm {
+    fn write(&mut self, addr: u16, value: u8) {
+        self.0.extend_from_slice(&[0xA9, value, 0x8D, addr as u8, (addr >> 8) as u8]); // LDA #value, STA addr
+    }
+
+    fn write_vram(&mut self, addr: u16, bytes: impl IntoIterator<Item = u8>) {
+        self.write(0x2006, (addr >> 8) as u8);
+        self.write(0x2006, addr as u8);
+        for value in bytes {
+            self.write(0x2007, value);
+        }
+    }
+}
+
+const PALETTES: [u8; 32] = [
+    0x21, 0x16, 0x2A, 0x30, 0x21, 0x12, 0x27, 0x0F, 0x21, 0x19, 0x05, 0x3C, 0x21, 0x14, 0x38, 0x00,
+    0x21, 0x0F, 0x30, 0x16, 0x21, 0x2D, 0x11, 0x28, 0x21, 0x06, 0x1A, 0x31, 0x21, 0x13, 0x23, 0x33,
+];
+
+// Blank, solid, checkerboard, diagonals, a framed box and an arrow pointing left (for flips).
+fn chr() -> Vec<u8> {
+    let tiles: [([u8; 8], [u8; 8]); 6] = [
+        ([0; 8], [0; 8]),
+        ([0xFF; 8], [0; 8]),
+        ([0; 8], [0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55]),
+        ([0x88, 0x44, 0x22, 0x11, 0x88, 0x44, 0x22, 0x11], [0x88, 0x44, 0x22, 0x11, 0x88, 0x44, 0x22, 0x11]),
+        ([0xFF, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0xFF], [0x00, 0x7E, 0x42, 0x5A, 0x5A, 0x42, 0x7E, 0x00]),
+        ([0x10, 0x30, 0x7F, 0xFF, 0x7F, 0x30, 0x10, 0x00], [0x00, 0x10, 0x30, 0x7F, 0x30, 0x10, 0x00, 0x00]),
+    ];
+    let mut chr = vec![0; 0x2000];
+    for (i, (low, high)) in tiles.iter().enumerate() {
+        chr[i * 16..i * 16 + 8].copy_from_slice(low);
+        chr[i * 16 + 8..i * 16 + 16].copy_from_slice(high);
+    }
+    chr
+}
+
+// NROM with vertical mirroring running `setup` with rendering off, then looping.
+fn rom(pal: bool, setup: impl FnOnce(&mut Asm)) -> Vec<u8> {
+    let mut asm = Asm(Vec::new());
+    asm.write_vram(0x3F00, PALETTES);
+    setup(&mut asm);
+    asm.0.extend_from_slice(&[0x4C, 0x00, 0x00]);
+    let end = 0x8000 + asm.0.len() as u16 - 3;
+    let len = asm.0.len();
+    asm.0[len - 2..].copy_from_slice(&end.to_le_bytes()); // JMP *
+
+    let mut rom = b"NES\x1A\x02\x01\x01\x08".to_vec();
+    rom.resize(16, 0);
+    rom[12] = pal as u8;
+    let mut prg = vec![0xEA; 0x8000];
+    prg[..asm.0.len()].copy_from_slice(&asm.0);
+    prg[0x7FFA..].copy_from_slice(&[0x00, 0x80, 0x00, 0x80, 0x00, 0x80]);
+    rom.extend(prg);
+    rom.extend(chr());
+    rom
+}
+
+// Every tile and palette, scrolled a tile left so a column of the second nametable shows.
+fn background(asm: &mut Asm, mask: u8) {
+    asm.write_vram(0x2000, (0..960).map(|i: usize| [1, 2, 3, 4][(i % 32 / 2 + i / 64) % 4]));
+    asm.write_vram(0x23C0, (0..64).map(|i: u8| i.wrapping_mul(0x1B)));
+    asm.write(0x2000, 0x00);
+    asm.write(0x2005, 8);
+    asm.write(0x2005, 5);
+    asm.write(0x2001, mask);
+}
+
+// A grid of 64 arrows in every palette and flip, odd rows behind a solid band.
+fn sprites(asm: &mut Asm) {
+    asm.write_vram(0x2000, (0..960).map(|i| if (10 * 32..20 * 32).contains(&i) { 1 } else { 0 }));
+    asm.write(0x2003, 0);
+    for i in 0..64u8 {
+        let (row, col) = (i / 8, i % 8);
+        let attributes = (i % 4) | (i / 4 % 4) << 6 | (row & 1) << 5;
+        for value in [40 + row * 22, 5, attributes, 24 + col * 26] {
+            asm.write(0x2004, value);
+        }
+    }
+    asm.write(0x2000, 0x00);
+    asm.write(0x2005, 0);
+    asm.write(0x2005, 0);
+    asm.write(0x2001, 0x1E);
+}
+
+fn check(name: &str, rom: Vec<u8>) {
+    let mut emulator = Emulator::new();
+    emulator.load_rom(&rom).unwrap();
+    for _ in 0..FRAMES {
+        emulator.step();
+    }
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/golden").join(format!("{name}.png"));
+    if std::env::var_os("NASS_BLESS").is_some() {
+        std::fs::write(&path, emulator.frame_png()).unwrap();
+        return
+    }
+    let baseline = std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    let differing = emulator.compare_frame_png(&baseline).unwrap();
+    if differing > 0 {
+        let (diff, line) = emulator.frame_diff_png(&baseline).unwrap();
+        let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}-diff.png"));
+        std::fs::write(&out, diff).unwrap();
+        panic!("{name}: {differing} pixels differ from the reference from line {}, see {}", line.unwrap_or(0), out.display());
+    }
+}
+
+#[test]
+fn golden_background() {
+    check("background", rom(false, |asm| background(asm, 0x1E)));
+}
+
+#[test]
+fn golden_sprites() {
+    check("sprites", rom(false, sprites));
+}
+
+// Red and blue emphasis with greyscale, PAL swaps the red and green bits.
+#[test]
+fn golden_emphasis() {
+    check("emphasis", rom(false, |asm| background(asm, 0xBF)));
+}
+
+#[test]
+fn golden_emphasis_pal() {
+    check("emphasis_pal", rom(true, |asm| background(asm, 0xBF)));
+}
@@ -0,0 +1,38 @@
+// Region detection picks the frame geometry the PPU runs with.
+use nass_core::Emulator;
+
+// NES 2.0 NROM looping on a JMP, byte 12 says PAL.
+fn pal_rom() -> Vec<u8> {
+    let mut rom = b"NES\x1A\x02\x01\x00\x08".to_vec();
+    rom.resize(16, 0);
+    rom[12] = 1;
+    let mut prg = vec![0xEA; 0x8000];
+    prg[..3].copy_from_slice(&[0x4C, 0x00, 0x80]); // JMP $8000
+    prg[0x7FFA..].copy_from_slice(&[0x00, 0x80, 0x00, 0x80, 0x00, 0x80]);
+    rom.extend(prg);
+    rom.extend(vec![0; 0x2000]);
+    rom
+}
+
+// Region bit of `region_info` and the last scanline seen over two frames.
+fn run(detection: bool) -> (u32, u16) {
+    let mut emulator = Emulator::new();
+    emulator.set_region_detection(detection);
+    emulator.load_rom(&pal_rom()).unwrap();
+    let mut last_line = 0;
+    for _ in 0..2 * 33248 / 3 {
+        emulator.step_instruction();
+        last_line = last_line.max(emulator.get_ppu_position().0);
+    }
+    (emulator.region_info() & 1, last_line)
+}
+
+#[test]
+fn detected_pal_runs_312_lines() {
+    assert_eq!(run(true), (1, 311));
+}
+
+#[test]
+fn detection_off_runs_262_lines() {
+    assert_eq!(run(false), (0, 261));
+}
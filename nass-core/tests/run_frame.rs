@@ -0,0 +1,45 @@
+// `run_frame` runs exactly one PPU frame.
+use nass_core::Emulator;
+
+// NROM turning NMIs on and spinning, the NMI handler only returns.
+fn nmi_rom(pal: bool) -> Vec<u8> {
+    let mut rom = b"NES\x1A\x02\x01\x00\x08".to_vec();
+    rom.resize(16, 0);
+    rom[12] = pal as u8;
+    let mut prg = vec![0xEA; 0x8000];
+    prg[..8].copy_from_slice(&[0xA9, 0x80, 0x8D, 0x00, 0x20, 0x4C, 0x05, 0x80]); // LDA #$80, STA $2000, JMP *
+    prg[0x100] = 0x40; // RTI
+    prg[0x7FFA..].copy_from_slice(&[0x00, 0x81, 0x00, 0x80, 0x00, 0x80]);
+    rom.extend(prg);
+    rom.extend(vec![0; 0x2000]);
+    rom
+}
+
+fn frame_cycles(pal: bool) -> Vec<(usize, u32)> {
+    let mut emulator = Emulator::new();
+    emulator.load_rom(&nmi_rom(pal)).unwrap();
+    emulator.run_frame();
+    (0..4).map(|_| emulator.run_frame()).map(|report| (report.cycles, report.nmis)).collect()
+}
+
+#[test]
+fn one_frame_per_call() {
+    // 341 * 262 / 3 CPU cycles per NTSC frame, 341 * 312 / 3.2 on PAL, give or take the
+    // instruction the frame ends in.
+    for (cycles, nmis) in frame_cycles(false) {
+        assert!(cycles.abs_diff(29781) <= 3, "{cycles}");
+        assert_eq!(nmis, 1);
+    }
+    for (cycles, nmis) in frame_cycles(true) {
+        assert!(cycles.abs_diff(33248) <= 3, "{cycles}");
+        assert_eq!(nmis, 1);
+    }
+}
+
+#[test]
+fn frame_hash_is_stable() {
+    let mut emulator = Emulator::new();
+    emulator.load_rom(&nmi_rom(false)).unwrap();
+    let hashes: Vec<u32> = (0..4).map(|_| emulator.run_frame().frame_hash).collect();
+    assert!(hashes.windows(2).all(|pair| pair[0] == pair[1]));
+}
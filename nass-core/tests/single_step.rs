@@ -0,0 +1,203 @@
+// Every opcode against the SingleStepTests nes6502 vectors (github.com/SingleStepTests/65x02,
+// one JSON file per opcode), run on `FlatCpu`. They aren't vendored: point NASS_SINGLESTEP_DIR
+// at the directory with 00.json to ff.json and run `cargo test -p nass-core --features single-step`.
+// Registers (but the B and unused bits of P), memory, the cycle count and every write have to
+// match. The reads have to be in the vector in the same order, the CPU may skip some of the
+// dummy ones as they can't change anything on the console's bus.
+use nass_core::debug::{FlatCpu, Registers};
+use std::path::PathBuf;
+
+// JAM, and the unstable stores and loads whose results depend on the chip.
+const SKIPPED: [u8; 19] = [
+    0x02, 0x12, 0x22, 0x32, 0x42, 0x52, 0x62, 0x72, 0x92, 0xB2, 0xD2, 0xF2,
+    0x8B, 0x93, 0x9B, 0x9C, 0x9E, 0x9F, 0xAB,
+];
+
+enum Json {
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+    Literal, // true, false or null
+}
+
+impl Json {
+    fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v).unwrap_or_else(|| panic!("No {key}")),
+            _ => panic!("{key} of a non-object"),
+        }
+    }
+
+    fn items(&self) -> &[Json] {
+        match self {
+            Json::Array(items) => items,
+            _ => panic!("Not an array"),
+        }
+    }
+
+    fn number(&self) -> u16 {
+        match self {
+            Json::Number(n) => *n as u16,
+            _ => panic!("Not a number"),
+        }
+    }
+
+    fn str(&self) -> &str {
+        match self {
+            Json::String(s) => s,
+            _ => panic!("Not a string"),
+        }
+    }
+}
+
+// Just enough JSON for the vectors: no escapes past \" and \\.
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_space(&mut self) {
+        while self.bytes.get(self.pos).is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) {
+        self.skip_space();
+        assert_eq!(self.bytes[self.pos], byte, "at {}", self.pos);
+        self.pos += 1;
+    }
+
+    // Whether the list goes on after an item, eating the comma.
+    fn more(&mut self, close: u8) -> bool {
+        self.skip_space();
+        let byte = self.bytes[self.pos];
+        self.pos += 1;
+        match byte {
+            b',' => true,
+            _ if byte == close => false,
+            _ => panic!("Expected , or {} at {}", close as char, self.pos - 1),
+        }
+    }
+
+    fn string(&mut self) -> String {
+        self.expect(b'"');
+        let mut s = Vec::new();
+        loop {
+            let byte = self.bytes[self.pos];
+            self.pos += 1;
+            match byte {
+                b'"' => return String::from_utf8(s).unwrap(),
+                b'\\' => { s.push(self.bytes[self.pos]); self.pos += 1; },
+                _ => s.push(byte),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Json {
+        self.skip_space();
+        match self.bytes[self.pos] {
+            b'"' => Json::String(self.string()),
+            b'[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.skip_space();
+                if self.bytes[self.pos] == b']' { self.pos += 1; return Json::Array(items) }
+                loop {
+                    items.push(self.value());
+                    if !self.more(b']') { return Json::Array(items) }
+                }
+            },
+            b'{' => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                self.skip_space();
+                if self.bytes[self.pos] == b'}' { self.pos += 1; return Json::Object(fields) }
+                loop {
+                    let key = self.string();
+                    self.expect(b':');
+                    fields.push((key, self.value()));
+                    if !self.more(b'}') { return Json::Object(fields) }
+                }
+            },
+            _ => {
+                let start = self.pos;
+                while self.bytes.get(self.pos).is_some_and(|b| !b",]} \t\r\n".contains(b)) {
+                    self.pos += 1;
+                }
+                let token = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap();
+                match token {
+                    "true" | "false" | "null" => Json::Literal,
+                    _ => Json::Number(token.parse().unwrap_or_else(|_| panic!("Bad token {token} at {start}"))),
+                }
+            },
+        }
+    }
+}
+
+fn parse(bytes: &[u8]) -> Json {
+    Parser { bytes, pos: 0 }.value()
+}
+
+fn registers(state: &Json) -> Registers {
+    let reg = |key| state.get(key).number();
+    Registers { a: reg("a") as u8, x: reg("x") as u8, y: reg("y") as u8, p: reg("p") as u8, s: reg("s") as u8, pc: reg("pc"), cycles: 0 }
+}
+
+// What differs after running `test`, if anything.
+fn run(cpu: &mut FlatCpu, test: &Json) -> Option<String> {
+    let (initial, expected) = (test.get("initial"), test.get("final"));
+    cpu.set_registers(registers(initial));
+    for entry in initial.get("ram").items() {
+        cpu.memory()[entry.items()[0].number() as usize] = entry.items()[1].number() as u8;
+    }
+    let cycles: Vec<(u16, u8, bool)> = test.get("cycles").items().iter()
+        .map(|c| (c.items()[0].number(), c.items()[1].number() as u8, c.items()[2].str() == "write"))
+        .collect();
+    let (accesses, count) = cpu.step();
+    let accesses = accesses.to_vec();
+
+    let mut reads = cycles.iter().filter(|c| !c.2);
+    let writes: Vec<_> = cycles.iter().filter(|c| c.2).collect();
+    if accesses.iter().filter(|a| a.2).ne(writes.iter().copied()) {
+        return Some(format!("writes {accesses:X?}, expected {cycles:X?}"))
+    }
+    if !accesses.iter().filter(|a| !a.2).all(|read| reads.any(|c| c == read)) {
+        return Some(format!("reads {accesses:X?}, expected {cycles:X?}"))
+    }
+    if count != cycles.len() {
+        return Some(format!("{count} cycles, expected {}", cycles.len()))
+    }
+    let (got, want) = (cpu.registers(), registers(expected));
+    let got = (got.a, got.x, got.y, got.p & 0xCF, got.s, got.pc);
+    let want = (want.a, want.x, want.y, want.p & 0xCF, want.s, want.pc);
+    if got != want {
+        return Some(format!("A X Y P S PC {got:X?}, expected {want:X?}"))
+    }
+    for entry in expected.get("ram").items() {
+        let (addr, value) = (entry.items()[0].number(), entry.items()[1].number() as u8);
+        let actual = cpu.memory()[addr as usize];
+        if actual != value {
+            return Some(format!("${addr:04X} = {actual:02X}, expected {value:02X}"))
+        }
+    }
+    None
+}
+
+#[test]
+fn single_step_tests() {
+    let Some(dir) = std::env::var_os("NASS_SINGLESTEP_DIR").map(PathBuf::from) else { return };
+    let mut cpu = FlatCpu::new();
+    let mut failed = Vec::new();
+    for opcode in (0..=0xFFu8).filter(|op| !SKIPPED.contains(op)) {
+        let path = dir.join(format!("{opcode:02x}.json"));
+        let bytes = std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+        // The first failure of each opcode is enough
+        if let Some((name, diff)) = parse(&bytes).items().iter().find_map(|test| run(&mut cpu, test).map(|diff| (test.get("name").str().to_string(), diff))) {
+            failed.push(format!("{opcode:02X} ({name}): {diff}"));
+        }
+    }
+    assert!(failed.is_empty(), "{} opcodes failed:\n{}", failed.len(), failed.join("\n"));
+}
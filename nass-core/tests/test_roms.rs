@@ -0,0 +1,100 @@
+// Test ROMs reporting through $6000 (blargg's protocol), run headlessly with `run_test_rom`.
+// The mmc3_test_2 and instr_test-v5 suites aren't vendored: point NASS_TEST_ROMS at a checkout
+// of nes-test-roms to run them, without it only the synthetic ROMs below run.
+use nass_core::Emulator;
+use std::path::PathBuf;
+
+const MAX_STEPS: usize = 600;
+
+// MMC3 with 32KB of PRG-ROM whose reset code writes the signature and `status` to $6000.
+fn mmc3_reporting(status: u8) -> Vec<u8> {
+    let mut rom = b"NES\x1A\x02\x01\x40\x00".to_vec();
+    rom.resize(16, 0);
+    let mut prg = vec![0xEA; 0x8000];
+    let code = [
+        0xA9, 0xDE, 0x8D, 0x01, 0x60, // LDA #$DE, STA $6001
+        0xA9, 0xB0, 0x8D, 0x02, 0x60, // LDA #$B0, STA $6002
+        0xA9, 0x61, 0x8D, 0x03, 0x60, // LDA #$61, STA $6003
+        0xA9, status, 0x8D, 0x00, 0x60, // LDA #status, STA $6000
+        0x4C, 0x14, 0xE0, // JMP *
+    ];
+    prg[0x6000..0x6000 + code.len()].copy_from_slice(&code);
+    prg[0x7FFA..].copy_from_slice(&[0x00, 0xE0, 0x00, 0xE0, 0x00, 0xE0]);
+    rom.extend(prg);
+    rom.extend(vec![0; 0x2000]);
+    rom
+}
+
+fn run(rom: &[u8], max_steps: usize) -> u8 {
+    let mut emulator = Emulator::new();
+    emulator.load_rom(rom).unwrap();
+    emulator.run_test_rom(max_steps)
+}
+
+#[test]
+fn reports_passed() {
+    assert_eq!(run(&mmc3_reporting(0), MAX_STEPS), 0);
+}
+
+#[test]
+fn reports_failure_code() {
+    assert_eq!(run(&mmc3_reporting(3), MAX_STEPS), 3);
+}
+
+#[test]
+fn times_out_without_signature() {
+    let mut rom = mmc3_reporting(0);
+    rom[16 + 0x6001] = 0x00; // LDA #$00 instead of the signature's first byte
+    assert_eq!(run(&rom, 30), 0xFF);
+}
+
+// Runs the ROMs under NASS_TEST_ROMS, failing with every one that didn't pass.
+fn suite(roms: &[&str]) {
+    let Some(dir) = std::env::var_os("NASS_TEST_ROMS").map(PathBuf::from) else { return };
+    let mut failed = Vec::new();
+    for name in roms {
+        let rom = std::fs::read(dir.join(name)).unwrap_or_else(|e| panic!("{name}: {e}"));
+        let mut emulator = Emulator::new();
+        emulator.load_rom(&rom).unwrap();
+        let code = emulator.run_test_rom(MAX_STEPS);
+        if code != 0 {
+            let text: Vec<u8> = (0..256).map(|i| emulator.test_output(i)).take_while(|&c| c != 0).collect();
+            failed.push(format!("{name}: {code:#04X} {}", String::from_utf8_lossy(&text).trim()));
+        }
+    }
+    assert!(failed.is_empty(), "{}", failed.join("\n"));
+}
+
+#[test]
+fn mmc3_test_suite() {
+    suite(&[
+        "mmc3_test_2/rom_singles/1-clocking.nes",
+        "mmc3_test_2/rom_singles/2-details.nes",
+        "mmc3_test_2/rom_singles/3-A12_clocking.nes",
+        "mmc3_test_2/rom_singles/4-scanline_timing.nes",
+        "mmc3_test_2/rom_singles/5-MMC3.nes",
+    ]);
+}
+
+// Every official and unofficial opcode, ADC/SBC carry and overflow with D set as well.
+#[test]
+fn instr_test_suite() {
+    suite(&[
+        "instr_test-v5/rom_singles/01-basics.nes",
+        "instr_test-v5/rom_singles/02-implied.nes",
+        "instr_test-v5/rom_singles/03-immediate.nes",
+        "instr_test-v5/rom_singles/04-zero_page.nes",
+        "instr_test-v5/rom_singles/05-zp_xy.nes",
+        "instr_test-v5/rom_singles/06-absolute.nes",
+        "instr_test-v5/rom_singles/07-abs_xy.nes",
+        "instr_test-v5/rom_singles/08-ind_x.nes",
+        "instr_test-v5/rom_singles/09-ind_y.nes",
+        "instr_test-v5/rom_singles/10-branches.nes",
+        "instr_test-v5/rom_singles/11-stack.nes",
+        "instr_test-v5/rom_singles/12-jmp_jsr.nes",
+        "instr_test-v5/rom_singles/13-rts.nes",
+        "instr_test-v5/rom_singles/14-rti.nes",
+        "instr_test-v5/rom_singles/15-brk.nes",
+        "instr_test-v5/rom_singles/16-special.nes",
+    ]);
+}
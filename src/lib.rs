@@ -1,15 +1,9 @@
-#![feature(bigint_helper_methods)]
-
-mod ppu;
-mod cpu;
-mod emulator;
-mod mapper;
-mod frame;
-
+// The WebAssembly build: `#[no_mangle]` exports over one `Emulator` (and a `Multiverse`) for
+// the page, the emulator itself is nass-core.
 use { 
     cfg_if::cfg_if,
     std::cell::RefCell,
-    crate::emulator::Emulator,
+    nass_core::{Emulator, Multiverse},
 };
 
 cfg_if! {
@@ -20,6 +14,7 @@ cfg_if! {
 }
 
 thread_local!{ static EMULATOR: RefCell<Emulator> = RefCell::new(Emulator::new()) }
+thread_local!{ static MULTIVERSE: RefCell<Multiverse> = RefCell::new(Multiverse::new()) }
 
 #[no_mangle]
 pub fn set_rom_length(value: usize) {
@@ -46,11 +41,71 @@ pub fn toggle_button(value: u8) {
     EMULATOR.with_borrow_mut(|e| e.toggle_button(value))
 }
 
+#[no_mangle]
+pub fn toggle_button2(value: u8) {
+    EMULATOR.with_borrow_mut(|e| e.toggle_button2(value))
+}
+
+#[no_mangle]
+pub fn set_port_device(port: usize, kind: u8) -> bool {
+    EMULATOR.with_borrow_mut(|e| e.set_port_device(port, kind))
+}
+
+#[no_mangle]
+pub fn set_port_input(port: usize, value: u32) {
+    EMULATOR.with_borrow_mut(|e| e.set_port_input(port, value))
+}
+
+#[no_mangle]
+pub fn set_paddle(port: usize, position: u8, fire: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_paddle(port, position, fire))
+}
+
+#[no_mangle]
+pub fn set_microphone(level: u8) {
+    EMULATOR.with_borrow_mut(|e| e.set_microphone(level))
+}
+
+#[no_mangle]
+pub fn set_key(key: u8, pressed: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_key(key, pressed))
+}
+
+#[no_mangle]
+pub fn set_tape_mode(mode: u8) {
+    EMULATOR.with_borrow_mut(|e| e.set_tape_mode(mode))
+}
+
+#[no_mangle]
+pub fn export_tape() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_tape())
+}
+
+#[no_mangle]
+pub fn set_tape_length(value: usize) {
+    EMULATOR.with_borrow_mut(|e| e.set_tape_len(value))
+}
+
+#[no_mangle]
+pub fn get_tape_pointer() -> *mut u8 {
+    EMULATOR.with_borrow_mut(|e| e.get_tape_pointer())
+}
+
 #[no_mangle]
 pub fn get_frame_pointer() -> *const u32 {
     EMULATOR.with_borrow_mut(|e| e.get_frame_pointer())
 }
 
+#[no_mangle]
+pub fn set_frame_pool(enabled: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_frame_pool(enabled))
+}
+
+#[no_mangle]
+pub fn get_completed_frame_pointer() -> *const u32 {
+    EMULATOR.with_borrow_mut(|e| e.get_completed_frame_pointer())
+}
+
 #[no_mangle]
 pub fn get_rom_pointer() -> *const u8 {
     EMULATOR.with_borrow_mut(|e| e.get_rom_pointer())
@@ -60,3 +115,776 @@ pub fn get_rom_pointer() -> *const u8 {
 pub fn get_color(index: usize) -> u32 {
     EMULATOR.with_borrow_mut(|e| e.get_color(index))
 }
+
+#[no_mangle]
+pub fn set_mmc3_irq(a12_filter: usize, old_revision: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_mmc3_irq(a12_filter, old_revision))
+}
+
+#[no_mangle]
+pub fn run_test_rom(max_steps: usize) -> u8 {
+    EMULATOR.with_borrow_mut(|e| e.run_test_rom(max_steps))
+}
+
+#[no_mangle]
+pub fn get_test_output(index: u16) -> u8 {
+    EMULATOR.with_borrow_mut(|e| e.test_output(index))
+}
+
+#[no_mangle]
+pub fn get_save_kind() -> u8 {
+    EMULATOR.with_borrow_mut(|e| e.save_kind())
+}
+
+#[no_mangle]
+pub fn wram_protection() -> u8 {
+    EMULATOR.with_borrow_mut(|e| e.wram_protection())
+}
+
+#[no_mangle]
+pub fn is_save_dirty() -> bool {
+    EMULATOR.with_borrow_mut(|e| e.is_save_dirty())
+}
+
+#[no_mangle]
+pub fn export_save() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_save())
+}
+
+#[no_mangle]
+pub fn import_save() {
+    EMULATOR.with_borrow_mut(|e| e.import_save())
+}
+
+#[no_mangle]
+pub fn set_save_length(value: usize) {
+    EMULATOR.with_borrow_mut(|e| e.set_save_len(value))
+}
+
+#[no_mangle]
+pub fn get_save_pointer() -> *mut u8 {
+    EMULATOR.with_borrow_mut(|e| e.get_save_pointer())
+}
+
+#[no_mangle]
+pub fn export_save_sync() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_save_sync())
+}
+
+#[no_mangle]
+pub fn export_state_sync() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_state_sync())
+}
+
+#[no_mangle]
+pub fn get_sync_pointer() -> *const u8 {
+    EMULATOR.with_borrow_mut(|e| e.get_sync_pointer())
+}
+
+#[no_mangle]
+pub fn save_state() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.save_state())
+}
+
+#[no_mangle]
+pub fn load_state() -> bool {
+    EMULATOR.with_borrow_mut(|e| e.load_state())
+}
+
+#[no_mangle]
+pub fn recover_state() -> i32 {
+    EMULATOR.with_borrow_mut(|e| e.recover_state())
+}
+
+#[no_mangle]
+pub fn set_state_rom_check(enabled: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_state_rom_check(enabled))
+}
+
+#[no_mangle]
+pub fn set_auto_resume(enabled: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_auto_resume(enabled))
+}
+
+#[no_mangle]
+pub fn export_resume() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_resume())
+}
+
+#[no_mangle]
+pub fn add_resume() -> bool {
+    EMULATOR.with_borrow_mut(|e| e.add_resume())
+}
+
+#[no_mangle]
+pub fn has_resume() -> bool {
+    EMULATOR.with_borrow_mut(|e| e.has_resume())
+}
+
+#[no_mangle]
+pub fn resume() -> bool {
+    EMULATOR.with_borrow_mut(|e| e.resume())
+}
+
+#[no_mangle]
+pub fn discard_resume() {
+    EMULATOR.with_borrow_mut(|e| e.discard_resume())
+}
+
+#[no_mangle]
+pub fn set_state_length(value: usize) {
+    EMULATOR.with_borrow_mut(|e| e.set_state_len(value))
+}
+
+#[no_mangle]
+pub fn get_state_pointer() -> *mut u8 {
+    EMULATOR.with_borrow_mut(|e| e.get_state_pointer())
+}
+
+#[no_mangle]
+pub fn subscribe_events(mask: u32) {
+    EMULATOR.with_borrow_mut(|e| e.subscribe_events(mask))
+}
+
+#[no_mangle]
+pub fn poll_event() -> u8 {
+    EMULATOR.with_borrow_mut(|e| e.poll_event())
+}
+
+#[no_mangle]
+pub fn add_breakpoint(addr: u16) {
+    EMULATOR.with_borrow_mut(|e| e.add_breakpoint(addr))
+}
+
+#[no_mangle]
+pub fn remove_breakpoint(addr: u16) {
+    EMULATOR.with_borrow_mut(|e| e.remove_breakpoint(addr))
+}
+
+#[no_mangle]
+pub fn get_pc() -> u16 {
+    EMULATOR.with_borrow_mut(|e| e.get_pc())
+}
+
+#[no_mangle]
+pub fn add_bank_breakpoint(bank: usize, addr: u16) {
+    EMULATOR.with_borrow_mut(|e| e.add_bank_breakpoint(bank, addr))
+}
+
+#[no_mangle]
+pub fn remove_bank_breakpoint(bank: usize, addr: u16) {
+    EMULATOR.with_borrow_mut(|e| e.remove_bank_breakpoint(bank, addr))
+}
+
+#[no_mangle]
+pub fn get_scanline() -> u16 {
+    EMULATOR.with_borrow_mut(|e| e.get_ppu_position().0)
+}
+
+#[no_mangle]
+pub fn get_dot() -> u16 {
+    EMULATOR.with_borrow_mut(|e| e.get_ppu_position().1)
+}
+
+#[no_mangle]
+pub fn set_power_on_seed(seed: u64) {
+    EMULATOR.with_borrow_mut(|e| e.set_power_on_seed(Some(seed)))
+}
+
+#[no_mangle]
+pub fn clear_power_on_seed() {
+    EMULATOR.with_borrow_mut(|e| e.set_power_on_seed(None))
+}
+
+#[no_mangle]
+pub fn set_ppu_power_on(mode: u8) -> bool {
+    EMULATOR.with_borrow_mut(|e| e.set_ppu_power_on(mode))
+}
+
+#[no_mangle]
+pub fn set_alignment(alignment: u8) -> bool {
+    EMULATOR.with_borrow_mut(|e| e.set_alignment(alignment))
+}
+
+#[no_mangle]
+pub fn set_region(pal: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_region(pal))
+}
+
+#[no_mangle]
+pub fn set_mapper_fallback(enabled: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_mapper_fallback(enabled))
+}
+
+#[no_mangle]
+pub fn is_mapper_supported() -> bool {
+    EMULATOR.with_borrow_mut(|e| e.is_mapper_supported())
+}
+
+#[no_mangle]
+pub fn set_region_detection(enabled: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_region_detection(enabled))
+}
+
+#[no_mangle]
+pub fn region_info() -> u32 {
+    EMULATOR.with_borrow_mut(|e| e.region_info())
+}
+
+#[no_mangle]
+pub fn set_lockstep(lockstep: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_lockstep(lockstep))
+}
+
+#[no_mangle]
+pub fn set_accuracy_preset(preset: u8) -> bool {
+    EMULATOR.with_borrow_mut(|e| e.set_accuracy_preset(preset))
+}
+
+#[no_mangle]
+pub fn set_accuracy(bits: u8) {
+    EMULATOR.with_borrow_mut(|e| e.set_accuracy(bits))
+}
+
+#[no_mangle]
+pub fn get_accuracy() -> u8 {
+    EMULATOR.with_borrow_mut(|e| e.get_accuracy())
+}
+
+#[no_mangle]
+pub fn set_overclock(lines: u16) {
+    EMULATOR.with_borrow_mut(|e| e.set_overclock(lines))
+}
+
+#[no_mangle]
+pub fn get_overclock() -> u16 {
+    EMULATOR.with_borrow_mut(|e| e.get_overclock())
+}
+
+#[no_mangle]
+pub fn set_speed(speed: f32) {
+    EMULATOR.with_borrow_mut(|e| e.set_speed(speed))
+}
+
+#[no_mangle]
+pub fn get_speed() -> f32 {
+    EMULATOR.with_borrow_mut(|e| e.get_speed())
+}
+
+#[no_mangle]
+pub fn frame_interval_ms() -> f64 {
+    EMULATOR.with_borrow_mut(|e| e.frame_interval_ms())
+}
+
+#[no_mangle]
+pub fn is_lag_frame() -> bool {
+    EMULATOR.with_borrow_mut(|e| e.is_lag_frame())
+}
+
+#[no_mangle]
+pub fn lag_count() -> u32 {
+    EMULATOR.with_borrow_mut(|e| e.lag_count())
+}
+
+#[no_mangle]
+pub fn reset_lag_count() {
+    EMULATOR.with_borrow_mut(|e| e.reset_lag_count())
+}
+
+#[no_mangle]
+pub fn set_trace_frames(frames: usize) {
+    EMULATOR.with_borrow_mut(|e| e.set_trace_frames(frames))
+}
+
+#[no_mangle]
+pub fn crash_report() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.crash_report())
+}
+
+#[no_mangle]
+pub fn get_report_pointer() -> *const u8 {
+    EMULATOR.with_borrow_mut(|e| e.get_report_pointer())
+}
+
+#[no_mangle]
+pub fn screenshot() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.screenshot())
+}
+
+#[no_mangle]
+pub fn start_burst(every: u32, count: u32) {
+    EMULATOR.with_borrow_mut(|e| e.start_burst(every, count))
+}
+
+#[no_mangle]
+pub fn stop_burst() {
+    EMULATOR.with_borrow_mut(|e| e.stop_burst())
+}
+
+#[no_mangle]
+pub fn burst_len() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.burst_len())
+}
+
+#[no_mangle]
+pub fn export_burst_frame(index: usize) -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_burst_frame(index))
+}
+
+#[no_mangle]
+pub fn export_burst_apng() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_burst_apng())
+}
+
+#[no_mangle]
+pub fn set_replay(seconds: u32) {
+    EMULATOR.with_borrow_mut(|e| e.set_replay(seconds))
+}
+
+#[no_mangle]
+pub fn export_replay_gif(seconds: u32) -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_replay_gif(seconds))
+}
+
+#[no_mangle]
+pub fn compare_frame() -> i32 {
+    EMULATOR.with_borrow_mut(|e| e.compare_frame())
+}
+
+#[no_mangle]
+pub fn export_frame_diff() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_frame_diff())
+}
+
+#[no_mangle]
+pub fn get_diff_line() -> i32 {
+    EMULATOR.with_borrow_mut(|e| e.get_diff_line())
+}
+
+#[no_mangle]
+pub fn set_image_length(value: usize) {
+    EMULATOR.with_borrow_mut(|e| e.set_image_len(value))
+}
+
+#[no_mangle]
+pub fn get_image_pointer() -> *mut u8 {
+    EMULATOR.with_borrow_mut(|e| e.get_image_pointer())
+}
+
+#[no_mangle]
+pub fn get_dirty_rects() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.dirty_rects())
+}
+
+#[no_mangle]
+pub fn get_dirty_rects_pointer() -> *const u16 {
+    EMULATOR.with_borrow_mut(|e| e.get_rects_pointer())
+}
+
+#[no_mangle]
+pub fn step_instruction() {
+    EMULATOR.with_borrow_mut(|e| e.step_instruction())
+}
+
+#[no_mangle]
+pub fn debug_read(addr: u16) -> u8 {
+    EMULATOR.with_borrow_mut(|e| e.debug_read(addr))
+}
+
+#[no_mangle]
+pub fn debug_write(addr: u16, value: u8) {
+    EMULATOR.with_borrow_mut(|e| e.debug_write(addr, value))
+}
+
+#[no_mangle]
+pub fn export_stack_view() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_stack_view())
+}
+
+#[no_mangle]
+pub fn export_zero_page_view() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_zero_page_view())
+}
+
+#[no_mangle]
+pub fn get_view_pointer() -> *const u32 {
+    EMULATOR.with_borrow_mut(|e| e.get_view_pointer())
+}
+
+#[no_mangle]
+pub fn debug_read_vram(addr: u16) -> u8 {
+    EMULATOR.with_borrow_mut(|e| e.debug_read_vram(addr))
+}
+
+#[no_mangle]
+pub fn debug_write_vram(addr: u16, value: u8) -> bool {
+    EMULATOR.with_borrow_mut(|e| e.debug_write_vram(addr, value).is_ok())
+}
+
+#[no_mangle]
+pub fn debug_read_oam(index: u8) -> u8 {
+    EMULATOR.with_borrow_mut(|e| e.debug_read_oam(index))
+}
+
+#[no_mangle]
+pub fn debug_write_oam(index: u8, value: u8) {
+    EMULATOR.with_borrow_mut(|e| e.debug_write_oam(index, value))
+}
+
+#[no_mangle]
+pub fn set_frame_capture(enabled: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_frame_capture(enabled))
+}
+
+#[no_mangle]
+pub fn rerender_frame() -> bool {
+    EMULATOR.with_borrow_mut(|e| e.rerender_frame())
+}
+
+#[no_mangle]
+pub fn get_rerendered_pointer() -> *const u32 {
+    EMULATOR.with_borrow_mut(|e| e.get_rerendered_pointer())
+}
+
+#[no_mangle]
+pub fn export_chr() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_chr())
+}
+
+#[no_mangle]
+pub fn export_pattern_table(table: usize, palette: usize) -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_pattern_table(table, palette))
+}
+
+#[no_mangle]
+pub fn export_nametable(nametable: usize) -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_nametable(nametable))
+}
+
+#[no_mangle]
+pub fn edit_rom(offset: usize, value: u8) -> bool {
+    EMULATOR.with_borrow_mut(|e| e.edit_rom(offset, value).is_ok())
+}
+
+#[no_mangle]
+pub fn edit_prg(addr: u16, value: u8) -> bool {
+    EMULATOR.with_borrow_mut(|e| e.edit_prg(addr, value).is_ok())
+}
+
+#[no_mangle]
+pub fn edit_chr(addr: u16, value: u8) -> bool {
+    EMULATOR.with_borrow_mut(|e| e.edit_chr(addr, value).is_ok())
+}
+
+#[no_mangle]
+pub fn export_patch() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_patch())
+}
+
+#[no_mangle]
+pub fn get_patch_pointer() -> *const u8 {
+    EMULATOR.with_borrow_mut(|e| e.get_patch_pointer())
+}
+
+#[no_mangle]
+pub fn load_stats() -> i32 {
+    EMULATOR.with_borrow_mut(|e| e.load_stats())
+}
+
+#[no_mangle]
+pub fn export_stats() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.export_stats())
+}
+
+#[no_mangle]
+pub fn play_seconds() -> u64 {
+    EMULATOR.with_borrow_mut(|e| e.play_seconds())
+}
+
+#[no_mangle]
+pub fn set_savepoint() {
+    EMULATOR.with_borrow_mut(|e| e.set_savepoint())
+}
+
+#[no_mangle]
+pub fn retry() -> bool {
+    EMULATOR.with_borrow_mut(|e| e.retry())
+}
+
+#[no_mangle]
+pub fn set_failure_condition() -> bool {
+    EMULATOR.with_borrow_mut(|e| e.set_failure_condition_text())
+}
+
+#[no_mangle]
+pub fn attempts() -> u32 {
+    EMULATOR.with_borrow_mut(|e| e.attempts())
+}
+
+#[no_mangle]
+pub fn add_rule(id: u32) -> bool {
+    EMULATOR.with_borrow_mut(|e| e.add_rule_text(id))
+}
+
+#[no_mangle]
+pub fn remove_rule(id: u32) {
+    EMULATOR.with_borrow_mut(|e| e.remove_rule(id))
+}
+
+#[no_mangle]
+pub fn reset_rule(id: u32) {
+    EMULATOR.with_borrow_mut(|e| e.reset_rule(id))
+}
+
+// Next triggered rule id, -1 when there is none.
+#[no_mangle]
+pub fn poll_rule() -> i64 {
+    EMULATOR.with_borrow_mut(|e| e.poll_rule().map_or(-1, |id| id as i64))
+}
+
+#[no_mangle]
+pub fn load_macros() -> i32 {
+    EMULATOR.with_borrow_mut(|e| e.load_macros())
+}
+
+#[no_mangle]
+pub fn play_macro(port: usize) -> bool {
+    EMULATOR.with_borrow_mut(|e| e.play_macro_named(port))
+}
+
+#[no_mangle]
+pub fn stop_macros() {
+    EMULATOR.with_borrow_mut(|e| e.stop_macros())
+}
+
+#[no_mangle]
+pub fn set_text_length(value: usize) {
+    EMULATOR.with_borrow_mut(|e| e.set_text_len(value))
+}
+
+#[no_mangle]
+pub fn get_text_pointer() -> *mut u8 {
+    EMULATOR.with_borrow_mut(|e| e.get_text_pointer())
+}
+
+#[no_mangle]
+pub fn load_table() -> i32 {
+    EMULATOR.with_borrow_mut(|e| e.load_table())
+}
+
+#[no_mangle]
+pub fn extract_text(addr: u16, len: usize) -> usize {
+    EMULATOR.with_borrow_mut(|e| e.extract_text(addr, len))
+}
+
+#[no_mangle]
+pub fn search_text() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.search_text())
+}
+
+#[no_mangle]
+pub fn get_text_matches_pointer() -> *const u32 {
+    EMULATOR.with_borrow_mut(|e| e.get_text_matches_pointer())
+}
+
+#[no_mangle]
+pub fn run_frame() {
+    EMULATOR.with_borrow_mut(|e| e.run_frame_report())
+}
+
+#[no_mangle]
+pub fn get_frame_report_pointer() -> *const u32 {
+    EMULATOR.with_borrow_mut(|e| e.get_frame_report_pointer())
+}
+
+#[no_mangle]
+pub fn record_frame_time(start_ms: f64, end_ms: f64) {
+    EMULATOR.with_borrow_mut(|e| e.record_frame_time(start_ms, end_ms))
+}
+
+#[no_mangle]
+pub fn update_metrics() {
+    EMULATOR.with_borrow_mut(|e| e.update_metrics())
+}
+
+#[no_mangle]
+pub fn get_metrics_pointer() -> *const f32 {
+    EMULATOR.with_borrow_mut(|e| e.get_metrics_pointer())
+}
+
+#[no_mangle]
+pub fn set_hd_pack_file_length(value: usize) {
+    EMULATOR.with_borrow_mut(|e| e.set_hd_pack_file_len(value))
+}
+
+#[no_mangle]
+pub fn get_hd_pack_file_pointer() -> *mut u8 {
+    EMULATOR.with_borrow_mut(|e| e.get_hd_pack_file_pointer())
+}
+
+#[no_mangle]
+pub fn add_hd_pack_file() {
+    EMULATOR.with_borrow_mut(|e| e.add_hd_pack_file())
+}
+
+#[no_mangle]
+pub fn load_hd_pack() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.load_hd_pack_files())
+}
+
+#[no_mangle]
+pub fn get_hd_frame_pointer() -> *const u32 {
+    EMULATOR.with_borrow_mut(|e| e.get_hd_frame_pointer())
+}
+
+#[no_mangle]
+pub fn set_flicker_reduction(enabled: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_flicker_reduction(enabled))
+}
+
+#[no_mangle]
+pub fn set_sprite_log(enabled: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_sprite_log(enabled))
+}
+
+#[no_mangle]
+pub fn get_sprite_log_pointer() -> *const u64 {
+    EMULATOR.with_borrow_mut(|e| e.get_sprite_log_pointer())
+}
+
+#[no_mangle]
+pub fn set_split_log(enabled: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_split_log(enabled))
+}
+
+#[no_mangle]
+pub fn split_count() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.split_count())
+}
+
+#[no_mangle]
+pub fn get_split_log_pointer() -> *const u16 {
+    EMULATOR.with_borrow_mut(|e| e.get_split_log_pointer())
+}
+
+#[no_mangle]
+pub fn set_watchdog(frames: u32) {
+    EMULATOR.with_borrow_mut(|e| e.set_watchdog(frames))
+}
+
+#[no_mangle]
+pub fn set_debug_colors(enabled: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_debug_colors(enabled))
+}
+
+#[no_mangle]
+pub fn set_overlay(overlay: u8) {
+    EMULATOR.with_borrow_mut(|e| e.set_overlay(overlay))
+}
+
+#[no_mangle]
+pub fn set_timeline(enabled: bool) {
+    EMULATOR.with_borrow_mut(|e| e.set_timeline(enabled))
+}
+
+#[no_mangle]
+pub fn timeline_count() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.timeline_count())
+}
+
+#[no_mangle]
+pub fn get_timeline_pointer() -> *const u32 {
+    EMULATOR.with_borrow_mut(|e| e.get_timeline_pointer())
+}
+
+#[no_mangle]
+pub fn set_game_db_length(value: usize) {
+    EMULATOR.with_borrow_mut(|e| e.set_game_db_len(value))
+}
+
+#[no_mangle]
+pub fn get_game_db_pointer() -> *mut u8 {
+    EMULATOR.with_borrow_mut(|e| e.get_game_db_pointer())
+}
+
+#[no_mangle]
+pub fn load_game_db() -> i32 {
+    EMULATOR.with_borrow_mut(|e| e.load_game_db())
+}
+
+#[no_mangle]
+pub fn set_settings_length(value: usize) {
+    EMULATOR.with_borrow_mut(|e| e.set_settings_len(value))
+}
+
+#[no_mangle]
+pub fn get_settings_pointer() -> *mut u8 {
+    EMULATOR.with_borrow_mut(|e| e.get_settings_pointer())
+}
+
+#[no_mangle]
+pub fn load_settings() -> i32 {
+    EMULATOR.with_borrow_mut(|e| e.load_settings())
+}
+
+#[no_mangle]
+pub fn repair_rom() -> usize {
+    EMULATOR.with_borrow_mut(|e| e.repair_rom())
+}
+
+#[no_mangle]
+pub fn get_repaired_pointer() -> *const u8 {
+    EMULATOR.with_borrow_mut(|e| e.get_repaired_pointer())
+}
+
+#[no_mangle]
+pub fn set_multiverse_rom_length(value: usize) {
+    MULTIVERSE.with_borrow_mut(|m| m.set_rom_len(value))
+}
+
+#[no_mangle]
+pub fn get_multiverse_rom_pointer() -> *mut u8 {
+    MULTIVERSE.with_borrow_mut(|m| m.get_rom_pointer())
+}
+
+#[no_mangle]
+pub fn multiverse_load(index: usize) -> bool {
+    MULTIVERSE.with_borrow_mut(|m| m.load(index))
+}
+
+#[no_mangle]
+pub fn multiverse_step() {
+    MULTIVERSE.with_borrow_mut(|m| m.step())
+}
+
+#[no_mangle]
+pub fn multiverse_set_shared_input(shared: bool) {
+    MULTIVERSE.with_borrow_mut(|m| m.set_shared_input(shared))
+}
+
+#[no_mangle]
+pub fn multiverse_toggle_button(index: usize, value: u8) {
+    MULTIVERSE.with_borrow_mut(|m| m.toggle_button(index, value))
+}
+
+#[no_mangle]
+pub fn multiverse_set_accuracy(index: usize, bits: u8) {
+    MULTIVERSE.with_borrow_mut(|m| m.instance(index).set_accuracy(bits))
+}
+
+#[no_mangle]
+pub fn multiverse_set_diff(enabled: bool) {
+    MULTIVERSE.with_borrow_mut(|m| m.set_diff(enabled))
+}
+
+// Step of the first divergence or -1, bit 32 set when the frames differ and bit 33 when
+// the states do.
+#[no_mangle]
+pub fn multiverse_divergence() -> i64 {
+    MULTIVERSE.with_borrow_mut(|m| m.divergence().map_or(-1, |d| d.step as i64 | (d.frame as i64) << 32 | (d.state as i64) << 33))
+}
+
+#[no_mangle]
+pub fn get_multiverse_frame_pointer() -> *const u32 {
+    MULTIVERSE.with_borrow_mut(|m| m.get_frame_pointer())
+}